@@ -0,0 +1,61 @@
+use crate::ray::Ray;
+use crate::tuple::Tuple;
+
+// What a ray sees when it misses every object
+#[derive(PartialEq, Debug, Clone)]
+pub enum Background {
+    Solid(Tuple),
+    // Blends from bottom to top by the height of the ray direction
+    Gradient { bottom: Tuple, top: Tuple },
+}
+
+impl Background {
+    pub fn color_at(&self, ray: &Ray) -> Tuple {
+        match self {
+            Background::Solid(color) => color.clone(),
+            Background::Gradient { bottom, top } => {
+                let t = 0.5 * (ray.direction.normalize().1 + 1.);
+                let color = bottom.clone() * (1. - t) + top.clone() * t;
+                Tuple::color(color.0, color.1, color.2)
+            }
+        }
+    }
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Background::Solid(Tuple::color(0., 0., 0.))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_background_is_black() {
+        let r = Ray::new(Tuple::point(0., 0., 0.), Tuple::vector(0., 1., 0.));
+        assert_eq!(Background::default().color_at(&r), Tuple::color(0., 0., 0.));
+    }
+
+    #[test]
+    fn solid_background_ignores_direction() {
+        let b = Background::Solid(Tuple::color(0.2, 0.3, 0.4));
+        for direction in [Tuple::vector(0., 1., 0.), Tuple::vector(1., -1., 2.)] {
+            let r = Ray::new(Tuple::point(0., 0., 0.), direction);
+            assert_eq!(b.color_at(&r), Tuple::color(0.2, 0.3, 0.4));
+        }
+    }
+
+    #[test]
+    fn gradient_background_blends_by_height() {
+        let b = Background::Gradient {
+            bottom: Tuple::color(1., 1., 1.),
+            top: Tuple::color(0., 0., 1.),
+        };
+        let at = |x, y, z| b.color_at(&Ray::new(Tuple::point(0., 0., 0.), Tuple::vector(x, y, z)));
+        assert!(at(0., 1., 0.).approx_eq(&Tuple::color(0., 0., 1.)));
+        assert!(at(0., -2., 0.).approx_eq(&Tuple::color(1., 1., 1.)));
+        assert!(at(0., 0., 1.).approx_eq(&Tuple::color(0.5, 0.5, 1.)));
+    }
+}
@@ -0,0 +1,321 @@
+use crate::matrix::Matrix4;
+use crate::ray::Ray;
+use crate::tuple::Tuple;
+use crate::EPSILON;
+
+// Axis-aligned bounding box, an empty box has min above max on every axis
+#[derive(PartialEq, Debug, Clone)]
+pub struct BoundingBox {
+    pub min: Tuple,
+    pub max: Tuple,
+}
+
+impl BoundingBox {
+    pub fn new(min: Tuple, max: Tuple) -> Self {
+        BoundingBox { min, max }
+    }
+
+    pub fn empty() -> Self {
+        BoundingBox::new(
+            Tuple::point(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+            Tuple::point(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+        )
+    }
+
+    pub fn infinite() -> Self {
+        BoundingBox::new(
+            Tuple::point(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+            Tuple::point(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+        )
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.0 > self.max.0 || self.min.1 > self.max.1 || self.min.2 > self.max.2
+    }
+
+    pub fn add_point(&mut self, point: &Tuple) {
+        self.min = Tuple::point(
+            self.min.0.min(point.0),
+            self.min.1.min(point.1),
+            self.min.2.min(point.2),
+        );
+        self.max = Tuple::point(
+            self.max.0.max(point.0),
+            self.max.1.max(point.1),
+            self.max.2.max(point.2),
+        );
+    }
+
+    pub fn merge(&mut self, other: &BoundingBox) {
+        if other.is_empty() {
+            return;
+        }
+        self.add_point(&other.min);
+        self.add_point(&other.max);
+    }
+
+    pub fn contains_point(&self, point: &Tuple) -> bool {
+        self.min.0 <= point.0
+            && point.0 <= self.max.0
+            && self.min.1 <= point.1
+            && point.1 <= self.max.1
+            && self.min.2 <= point.2
+            && point.2 <= self.max.2
+    }
+
+    pub fn contains_box(&self, other: &BoundingBox) -> bool {
+        self.contains_point(&other.min) && self.contains_point(&other.max)
+    }
+
+    // Bounds of the transformed box, infinite boxes stay infinite since their
+    // corners can't be transformed
+    pub fn transform(&self, m: &Matrix4) -> BoundingBox {
+        if self.is_empty() {
+            return BoundingBox::empty();
+        }
+        let (min, max) = (&self.min, &self.max);
+        let finite = [min.0, min.1, min.2, max.0, max.1, max.2]
+            .iter()
+            .all(|v| v.is_finite());
+        if !finite {
+            return BoundingBox::infinite();
+        }
+
+        let mut bounds = BoundingBox::empty();
+        for x in [min.0, max.0] {
+            for y in [min.1, max.1] {
+                for z in [min.2, max.2] {
+                    bounds.add_point(&(*m * Tuple::point(x, y, z)));
+                }
+            }
+        }
+        bounds
+    }
+
+    pub fn intersects(&self, ray: &Ray) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+        let (xtmin, xtmax) = check_axis(ray.origin.0, ray.direction.0, self.min.0, self.max.0);
+        let (ytmin, ytmax) = check_axis(ray.origin.1, ray.direction.1, self.min.1, self.max.1);
+        let (ztmin, ztmax) = check_axis(ray.origin.2, ray.direction.2, self.min.2, self.max.2);
+
+        let tmin = xtmin.max(ytmin).max(ztmin);
+        let tmax = xtmax.min(ytmax).min(ztmax);
+        tmin <= tmax
+    }
+
+    // Halves the box across its longest axis
+    pub fn split(&self) -> (BoundingBox, BoundingBox) {
+        let dx = self.max.0 - self.min.0;
+        let dy = self.max.1 - self.min.1;
+        let dz = self.max.2 - self.min.2;
+        let greatest = dx.max(dy).max(dz);
+
+        let (mut x0, mut y0, mut z0) = (self.min.0, self.min.1, self.min.2);
+        let (mut x1, mut y1, mut z1) = (self.max.0, self.max.1, self.max.2);
+        if greatest == dx {
+            x0 += dx / 2.;
+            x1 = x0;
+        } else if greatest == dy {
+            y0 += dy / 2.;
+            y1 = y0;
+        } else {
+            z0 += dz / 2.;
+            z1 = z0;
+        }
+
+        (
+            BoundingBox::new(self.min.clone(), Tuple::point(x1, y1, z1)),
+            BoundingBox::new(Tuple::point(x0, y0, z0), self.max.clone()),
+        )
+    }
+}
+
+// Slab test for one axis, returns where the ray enters and leaves [min, max]
+pub(crate) fn check_axis(origin: f32, direction: f32, min: f32, max: f32) -> (f32, f32) {
+    let tmin_numerator = min - origin;
+    let tmax_numerator = max - origin;
+
+    let (tmin, tmax) = if direction.abs() >= EPSILON {
+        (tmin_numerator / direction, tmax_numerator / direction)
+    } else {
+        (
+            tmin_numerator * f32::INFINITY,
+            tmax_numerator * f32::INFINITY,
+        )
+    };
+
+    if tmin > tmax {
+        (tmax, tmin)
+    } else {
+        (tmin, tmax)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::{FRAC_PI_4, SQRT_2};
+
+    use super::*;
+
+    fn bbox(min: (f32, f32, f32), max: (f32, f32, f32)) -> BoundingBox {
+        BoundingBox::new(
+            Tuple::point(min.0, min.1, min.2),
+            Tuple::point(max.0, max.1, max.2),
+        )
+    }
+
+    #[test]
+    fn create_empty_bounding_box() {
+        let b = BoundingBox::empty();
+        assert!(b.is_empty());
+        assert_eq!(b.min.0, f32::INFINITY);
+        assert_eq!(b.max.0, f32::NEG_INFINITY);
+    }
+
+    #[test]
+    fn add_points_to_empty_bounding_box() {
+        let mut b = BoundingBox::empty();
+        b.add_point(&Tuple::point(-5., 2., 0.));
+        b.add_point(&Tuple::point(7., 0., -3.));
+        assert_eq!(b, bbox((-5., 0., -3.), (7., 2., 0.)));
+    }
+
+    #[test]
+    fn merge_bounding_boxes() {
+        let mut b = bbox((-5., -2., 0.), (7., 4., 4.));
+        b.merge(&bbox((8., -7., -2.), (14., 2., 8.)));
+        assert_eq!(b, bbox((-5., -7., -2.), (14., 4., 8.)));
+        b.merge(&BoundingBox::empty());
+        assert_eq!(b, bbox((-5., -7., -2.), (14., 4., 8.)));
+    }
+
+    #[test]
+    fn box_contains_point() {
+        let b = bbox((5., -2., 0.), (11., 4., 7.));
+        let cases = [
+            ((5., -2., 0.), true),
+            ((11., 4., 7.), true),
+            ((8., 1., 3.), true),
+            ((3., 0., 3.), false),
+            ((8., -4., 3.), false),
+            ((8., 1., -1.), false),
+            ((13., 1., 3.), false),
+            ((8., 5., 3.), false),
+            ((8., 1., 8.), false),
+        ];
+        for ((x, y, z), expected) in cases {
+            assert_eq!(b.contains_point(&Tuple::point(x, y, z)), expected);
+        }
+    }
+
+    #[test]
+    fn box_contains_box() {
+        let b = bbox((5., -2., 0.), (11., 4., 7.));
+        let cases = [
+            ((5., -2., 0.), (11., 4., 7.), true),
+            ((6., -1., 1.), (10., 3., 6.), true),
+            ((4., -3., -1.), (10., 3., 6.), false),
+            ((6., -1., 1.), (12., 5., 8.), false),
+        ];
+        for (min, max, expected) in cases {
+            assert_eq!(b.contains_box(&bbox(min, max)), expected);
+        }
+    }
+
+    #[test]
+    fn transform_bounding_box() {
+        let b = bbox((-1., -1., -1.), (1., 1., 1.));
+        let m = Matrix4::rotation_x(FRAC_PI_4) * Matrix4::rotation_y(FRAC_PI_4);
+        let t = b.transform(&m);
+        assert!(t.min.approx_eq(&Tuple::point(-SQRT_2, -1.70711, -1.70711)));
+        assert!(t.max.approx_eq(&Tuple::point(SQRT_2, 1.70711, 1.70711)));
+    }
+
+    #[test]
+    fn transform_infinite_bounding_box() {
+        let b = bbox(
+            (f32::NEG_INFINITY, 0., f32::NEG_INFINITY),
+            (f32::INFINITY, 0., f32::INFINITY),
+        );
+        assert_eq!(
+            b.transform(&Matrix4::rotation_x(FRAC_PI_4)),
+            BoundingBox::infinite()
+        );
+    }
+
+    #[test]
+    fn intersect_ray_with_cubic_bounding_box() {
+        let b = bbox((-1., -1., -1.), (1., 1., 1.));
+        let cases = [
+            ((5., 0.5, 0.), (-1., 0., 0.), true),
+            ((-5., 0.5, 0.), (1., 0., 0.), true),
+            ((0.5, 5., 0.), (0., -1., 0.), true),
+            ((0.5, -5., 0.), (0., 1., 0.), true),
+            ((0.5, 0., 5.), (0., 0., -1.), true),
+            ((0.5, 0., -5.), (0., 0., 1.), true),
+            ((0., 0.5, 0.), (0., 0., 1.), true),
+            ((-2., 0., 0.), (2., 4., 6.), false),
+            ((0., -2., 0.), (6., 2., 4.), false),
+            ((0., 0., -2.), (4., 6., 2.), false),
+            ((2., 0., 2.), (0., 0., -1.), false),
+            ((0., 2., 2.), (0., -1., 0.), false),
+            ((2., 2., 0.), (-1., 0., 0.), false),
+        ];
+        for ((ox, oy, oz), (dx, dy, dz), expected) in cases {
+            let r = Ray::new(
+                Tuple::point(ox, oy, oz),
+                Tuple::vector(dx, dy, dz).normalize(),
+            );
+            assert_eq!(b.intersects(&r), expected);
+        }
+    }
+
+    #[test]
+    fn intersect_ray_with_noncubic_bounding_box() {
+        let b = bbox((5., -2., 0.), (11., 4., 7.));
+        let cases = [
+            ((15., 1., 2.), (-1., 0., 0.), true),
+            ((-5., -1., 4.), (1., 0., 0.), true),
+            ((7., 6., 5.), (0., -1., 0.), true),
+            ((9., -5., 6.), (0., 1., 0.), true),
+            ((8., 2., 12.), (0., 0., -1.), true),
+            ((6., 0., -5.), (0., 0., 1.), true),
+            ((8., 1., 3.5), (0., 0., 1.), true),
+            ((9., -1., -8.), (2., 4., 6.), false),
+            ((8., 3., -4.), (6., 2., 4.), false),
+            ((9., -1., -2.), (4., 6., 2.), false),
+            ((4., 0., 9.), (0., 0., -1.), false),
+            ((8., 6., -1.), (0., -1., 0.), false),
+            ((12., 5., 4.), (-1., 0., 0.), false),
+        ];
+        for ((ox, oy, oz), (dx, dy, dz), expected) in cases {
+            let r = Ray::new(
+                Tuple::point(ox, oy, oz),
+                Tuple::vector(dx, dy, dz).normalize(),
+            );
+            assert_eq!(b.intersects(&r), expected);
+        }
+    }
+
+    #[test]
+    fn split_bounding_box() {
+        let cases = [
+            ((-1., -4., -5.), (9., 6., 5.), (4., 6., 5.), (4., -4., -5.)),
+            (
+                (-1., -2., -3.),
+                (9., 5.5, 3.),
+                (4., 5.5, 3.),
+                (4., -2., -3.),
+            ),
+            ((-1., -2., -3.), (5., 8., 3.), (5., 3., 3.), (-1., 3., -3.)),
+            ((-1., -2., -3.), (5., 3., 7.), (5., 3., 2.), (-1., -2., 2.)),
+        ];
+        for (min, max, left_max, right_min) in cases {
+            let (left, right) = bbox(min, max).split();
+            assert_eq!(left, bbox(min, left_max));
+            assert_eq!(right, bbox(right_min, max));
+        }
+    }
+}
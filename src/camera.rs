@@ -0,0 +1,986 @@
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rayon::prelude::*;
+
+use crate::canvas::{Canvas, CanvasTile, Tile};
+use crate::lens::Aperture;
+use crate::matrix::Matrix4;
+use crate::random::{seed_from, Rng};
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::stats::{RayStats, RayStatsMap};
+use crate::tuple::Tuple;
+use crate::world::World;
+use crate::EPSILON;
+
+// Block sizes of the coarse to fine passes of a budgeted render
+const BUDGET_BLOCK_SIZES: [usize; 5] = [16, 8, 4, 2, 1];
+
+// Edge length of the tiles a focused render works through
+const FOCUS_TILE_SIZE: usize = 16;
+// Edge length of the tiles a progressive render hands out
+const PROGRESSIVE_TILE_SIZE: usize = 32;
+
+// Part of the frame to render first, either a rectangle of pixels or the
+// screen area covered by one of the world's objects
+#[derive(PartialEq, Debug, Clone)]
+pub enum FocusRegion {
+    Rect {
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    },
+    Object(usize),
+}
+
+// Camera looking down -z with the canvas one unit in front of it. A pinhole
+// by default, with an aperture it becomes a thin lens that is only sharp at
+// the focal distance.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Camera {
+    hsize: usize,
+    vsize: usize,
+    field_of_view: f32,
+    transform: Matrix4,
+    inverse_transform: Matrix4,
+    half_width: f32,
+    half_height: f32,
+    pixel_size: f32,
+    sampler: Sampler,
+    // Radius of the lens, 0 for a pinhole
+    aperture: f32,
+    // Depth along the view direction that is in focus
+    focal_distance: f32,
+    aperture_shape: Aperture,
+}
+
+impl Camera {
+    pub fn new(hsize: usize, vsize: usize, field_of_view: f32) -> Self {
+        let half_view = (field_of_view / 2.).tan();
+        let aspect = hsize as f32 / vsize as f32;
+        let (half_width, half_height) = if aspect >= 1. {
+            (half_view, half_view / aspect)
+        } else {
+            (half_view * aspect, half_view)
+        };
+        Camera {
+            hsize,
+            vsize,
+            field_of_view,
+            transform: Matrix4::identity(),
+            inverse_transform: Matrix4::identity(),
+            half_width,
+            half_height,
+            pixel_size: half_width * 2. / hsize as f32,
+            sampler: Sampler::Single,
+            aperture: 0.,
+            focal_distance: 1.,
+            aperture_shape: Aperture::Disc,
+        }
+    }
+
+    // Antialiasing with the given number of jittered rays per pixel
+    pub fn with_samples(self, samples: usize) -> Self {
+        self.with_sampler(Sampler::Jittered { samples, seed: 0 })
+    }
+
+    pub fn with_sampler(mut self, sampler: Sampler) -> Self {
+        self.sampler = sampler;
+        self
+    }
+
+    // Every sample goes through a different spot of the lens, so this needs a
+    // sampler taking several samples per pixel to look smooth
+    pub fn with_depth_of_field(mut self, aperture: f32, focal_distance: f32) -> Self {
+        self.aperture = aperture;
+        self.focal_distance = focal_distance;
+        self
+    }
+
+    pub fn get_hsize(&self) -> usize {
+        self.hsize
+    }
+
+    pub fn get_vsize(&self) -> usize {
+        self.vsize
+    }
+
+    pub fn get_field_of_view(&self) -> f32 {
+        self.field_of_view
+    }
+
+    pub fn get_pixel_size(&self) -> f32 {
+        self.pixel_size
+    }
+
+    pub fn get_transform(&self) -> &Matrix4 {
+        &self.transform
+    }
+
+    pub fn get_sampler(&self) -> &Sampler {
+        &self.sampler
+    }
+
+    pub fn set_sampler(&mut self, sampler: Sampler) {
+        self.sampler = sampler;
+    }
+
+    pub fn get_aperture(&self) -> f32 {
+        self.aperture
+    }
+
+    pub fn set_aperture(&mut self, aperture: f32) {
+        self.aperture = aperture;
+    }
+
+    pub fn get_focal_distance(&self) -> f32 {
+        self.focal_distance
+    }
+
+    pub fn set_focal_distance(&mut self, focal_distance: f32) {
+        self.focal_distance = focal_distance;
+    }
+
+    pub fn get_aperture_shape(&self) -> &Aperture {
+        &self.aperture_shape
+    }
+
+    pub fn set_aperture_shape(&mut self, aperture_shape: Aperture) {
+        self.aperture_shape = aperture_shape;
+    }
+
+    pub fn set_transform(&mut self, transform: Matrix4) -> Result<(), String> {
+        self.inverse_transform = transform.inverse().ok_or("transform is not invertible")?;
+        self.transform = transform;
+        Ok(())
+    }
+
+    // Center of the lens in world space
+    pub fn get_position(&self) -> Tuple {
+        self.inverse_transform * &Tuple::point(0., 0., 0.)
+    }
+
+    pub fn ray_for_pixel(&self, px: usize, py: usize) -> Ray {
+        // Aim through the center of the pixel
+        self.ray_through(px as f32 + 0.5, py as f32 + 0.5)
+    }
+
+    // Ray through any position on the canvas, measured in pixels from the top left
+    pub fn ray_through(&self, x: f32, y: f32) -> Ray {
+        // The canvas is at z = -1
+        let world_x = self.half_width - x * self.pixel_size;
+        let world_y = self.half_height - y * self.pixel_size;
+        let target = Tuple::point(world_x, world_y, -1.);
+        let origin = Tuple::point(0., 0., 0.);
+        let ray = Ray::new(origin.clone(), target - origin).transform(&self.inverse_transform);
+        Ray::new(ray.origin, ray.direction.normalize()).with_viewpoint(Some(self.get_position()))
+    }
+
+    // Ray from a random point on the lens through the spot on the focal plane
+    // the pinhole ray would pass, so everything at that depth stays sharp
+    pub fn lens_ray(&self, x: f32, y: f32, rng: &mut Rng) -> Ray {
+        if self.aperture <= 0. {
+            return self.ray_through(x, y);
+        }
+        let d = self.focal_distance;
+        let world_x = self.half_width - x * self.pixel_size;
+        let world_y = self.half_height - y * self.pixel_size;
+        let target = Tuple::point(world_x * d, world_y * d, -d);
+        let (lx, ly) = self.aperture_shape.sample(rng);
+        let origin = Tuple::point(lx * self.aperture, ly * self.aperture, 0.);
+        let ray = Ray::new(origin.clone(), target - origin).transform(&self.inverse_transform);
+        Ray::new(ray.origin, ray.direction.normalize()).with_viewpoint(Some(self.get_position()))
+    }
+
+    // Pixel position a world point shows up at, None if it's not in front of the camera
+    pub fn project(&self, point: &Tuple) -> Option<(f32, f32)> {
+        let p = self.transform * point;
+        if p.2 > -EPSILON {
+            return None;
+        }
+        let x = (self.half_width - p.0 / -p.2) / self.pixel_size;
+        let y = (self.half_height - p.1 / -p.2) / self.pixel_size;
+        Some((x, y))
+    }
+
+    // Depth along the view direction of whatever is seen at the canvas position,
+    // i.e. the distance a lens would have to focus at to make it sharp
+    pub fn focus_distance_at(&self, world: &World, x: f32, y: f32) -> Option<f32> {
+        let ray = self.ray_through(x, y);
+        let xs = world.intersect_world(&ray);
+        let point = ray.position(xs.hit()?.t);
+        Some(-(self.transform * point).2)
+    }
+
+    // Focuses the lens on whatever is seen at the canvas position and returns
+    // the new focal distance. When nothing is there the focal distance stays
+    // as it was and None is returned.
+    pub fn autofocus(&mut self, world: &World, x: f32, y: f32) -> Option<f32> {
+        let distance = self.focus_distance_at(world, x, y)?;
+        self.focal_distance = distance;
+        Some(distance)
+    }
+
+    // Pixel rectangle (x, y, width, height) covered by the region, clipped to the image
+    pub fn focus_rect(&self, world: &World, focus: &FocusRegion) -> (usize, usize, usize, usize) {
+        let everything = (0, 0, self.hsize, self.vsize);
+        let (x0, y0, x1, y1) = match focus {
+            FocusRegion::Rect {
+                x,
+                y,
+                width,
+                height,
+            } => (
+                *x as f32,
+                *y as f32,
+                (x + width) as f32,
+                (y + height) as f32,
+            ),
+            FocusRegion::Object(index) => {
+                let Some(object) = world.objects.get(*index) else {
+                    return everything;
+                };
+                let bounds = object.parent_space_bounds();
+                if bounds.is_empty() {
+                    return (0, 0, 0, 0);
+                }
+                // Objects reaching behind the camera or to infinity may cover anything
+                let (mut x0, mut y0) = (f32::INFINITY, f32::INFINITY);
+                let (mut x1, mut y1) = (f32::NEG_INFINITY, f32::NEG_INFINITY);
+                for x in [bounds.min.0, bounds.max.0] {
+                    for y in [bounds.min.1, bounds.max.1] {
+                        for z in [bounds.min.2, bounds.max.2] {
+                            let Some((px, py)) = self.project(&Tuple::point(x, y, z)) else {
+                                return everything;
+                            };
+                            if !px.is_finite() || !py.is_finite() {
+                                return everything;
+                            }
+                            (x0, y0) = (x0.min(px), y0.min(py));
+                            (x1, y1) = (x1.max(px), y1.max(py));
+                        }
+                    }
+                }
+                (x0.floor(), y0.floor(), x1.ceil(), y1.ceil())
+            }
+        };
+        let clip = |v: f32, max: usize| v.clamp(0., max as f32) as usize;
+        let (x0, x1) = (clip(x0, self.hsize), clip(x1, self.hsize));
+        let (y0, y1) = (clip(y0, self.vsize), clip(y1, self.vsize));
+        (x0, y0, x1 - x0, y1 - y0)
+    }
+
+    // Average of the rays the sampler sends through the pixel, each from its
+    // own point on the lens
+    pub fn color_for_pixel(&self, world: &World, x: usize, y: usize) -> Tuple {
+        self.sample_pixel(world, x, y, &self.sampler)
+    }
+
+    fn sample_pixel(&self, world: &World, x: usize, y: usize, sampler: &Sampler) -> Tuple {
+        let offsets = sampler.offsets(x, y);
+        let sum = offsets
+            .iter()
+            .enumerate()
+            .map(|(i, (u, v))| self.sample_color(world, x, y, i, (*u, *v)))
+            .fold(Tuple::color(0., 0., 0.), |acc, c| acc + c);
+        let color = sum * (1. / offsets.len() as f32);
+        Tuple::color(color.0, color.1, color.2)
+    }
+
+    // Color seen by the pixel's sample at the given index and offset
+    fn sample_color(
+        &self,
+        world: &World,
+        x: usize,
+        y: usize,
+        i: usize,
+        offset: (f32, f32),
+    ) -> Tuple {
+        let (u, v) = offset;
+        let ray = self.lens_ray(x as f32 + u, y as f32 + v, &mut lens_rng(x, y, i));
+        world.color_at(&ray)
+    }
+
+    // Renders the tiles touching the focus region first with the camera's
+    // sampler raised to at least the given number of samples per pixel, then
+    // the rest of the frame with the sampler as it is. on_pass sees the
+    // image after each of the two passes.
+    pub fn render_focused<F>(
+        &self,
+        world: &World,
+        focus: &FocusRegion,
+        samples: usize,
+        mut on_pass: F,
+    ) -> Canvas
+    where
+        F: FnMut(&Canvas),
+    {
+        let (fx, fy, fw, fh) = self.focus_rect(world, focus);
+        let in_focus = |tile: &CanvasTile| {
+            fw > 0
+                && fh > 0
+                && tile.get_x() < fx + fw
+                && fx < tile.get_x() + tile.get_width()
+                && tile.get_y() < fy + fh
+                && fy < tile.get_y() + tile.get_height()
+        };
+
+        let mut image = Canvas::new(self.hsize, self.vsize);
+        for focused in [true, false] {
+            let tiles: Vec<_> = image
+                .tiles_mut(FOCUS_TILE_SIZE, FOCUS_TILE_SIZE)
+                .expect("Focus tiles have a positive size")
+                .into_iter()
+                .filter(|tile| in_focus(tile) == focused)
+                .collect();
+            let sampler = if focused {
+                self.sampler.at_least(samples)
+            } else {
+                self.sampler.clone()
+            };
+            tiles.into_par_iter().for_each(|mut tile| {
+                for (x, y, pixel) in tile.pixels_mut() {
+                    *pixel = self.sample_pixel(world, x, y, &sampler);
+                }
+            });
+            on_pass(&image);
+        }
+        image
+    }
+
+    pub fn render(&self, world: &World) -> Canvas {
+        let mut image = Canvas::new(self.hsize, self.vsize);
+        for y in 0..self.vsize {
+            for x in 0..self.hsize {
+                let color = self.color_for_pixel(world, x, y);
+                image
+                    .write_pixel_at(x, y, color)
+                    .expect("Pixel is inside the canvas");
+            }
+        }
+        image
+    }
+
+    // Same image as render, with the tiles spread across all cores
+    pub fn render_parallel(&self, world: &World) -> Canvas {
+        self.render_progressive(world, |_| ControlFlow::Continue(()))
+    }
+
+    // Renders tiles across all cores and hands each one to on_tile on the
+    // calling thread as soon as it's done, in no particular order. Breaking
+    // out of on_tile cancels the render, tiles that were still being worked on
+    // are finished but not handed out. The image holds every finished tile.
+    pub fn render_progressive<F>(&self, world: &World, mut on_tile: F) -> Canvas
+    where
+        F: FnMut(Tile) -> ControlFlow<()>,
+    {
+        let mut image = Canvas::new(self.hsize, self.vsize);
+        let tiles: Vec<_> = image
+            .tiles_mut(PROGRESSIVE_TILE_SIZE, PROGRESSIVE_TILE_SIZE)
+            .expect("Progressive tiles have a positive size")
+            .into_iter()
+            .collect();
+        let cancelled = AtomicBool::new(false);
+        let (sender, receiver) = mpsc::channel();
+
+        // Workers run on the rayon pool from a thread of their own, so the
+        // caller is free to wait for tiles even in a single threaded pool
+        thread::scope(|scope| {
+            let cancelled = &cancelled;
+            scope.spawn(move || {
+                tiles
+                    .into_par_iter()
+                    .for_each_with(sender, |sender, mut tile| {
+                        if cancelled.load(Ordering::Relaxed) {
+                            return;
+                        }
+                        for (x, y, pixel) in tile.pixels_mut() {
+                            *pixel = self.color_for_pixel(world, x, y);
+                        }
+                        // The receiver is gone once the render is cancelled
+                        let _ = sender.send(tile.to_tile());
+                    });
+            });
+            for tile in receiver {
+                if on_tile(tile).is_break() {
+                    cancelled.store(true, Ordering::Relaxed);
+                    break;
+                }
+            }
+        });
+        image
+    }
+
+    // Refines the image until the budget runs out: first in ever smaller blocks
+    // until every pixel is rendered, then by averaging in the samples of the
+    // camera's sampler one at a time, ending at the image render gives. The
+    // coarsest pass always completes so there is an image.
+    pub fn render_with_budget(&self, world: &World, budget: Duration) -> Canvas {
+        let deadline = Instant::now() + budget;
+        let mut image = Canvas::new(self.hsize, self.vsize);
+
+        for (pass, &block) in BUDGET_BLOCK_SIZES.iter().enumerate() {
+            if pass > 0 && Instant::now() >= deadline {
+                return image;
+            }
+            let bands: Vec<_> = image
+                .tiles_mut(self.hsize.max(1), block)
+                .expect("Block tiles have a positive size")
+                .into_iter()
+                .collect();
+            bands.into_par_iter().for_each(|mut band| {
+                if pass > 0 && Instant::now() >= deadline {
+                    return;
+                }
+                // One color per block of the band, None where the block's origin
+                // is on the grid of the previous pass and already rendered
+                let y = band.get_y();
+                let coarser = block * 2;
+                let colors: Vec<_> = (0..self.hsize)
+                    .step_by(block)
+                    .map(|x| {
+                        (pass == 0 || x % coarser != 0 || y % coarser != 0).then(|| {
+                            let mut lens = lens_rng(x, y, 0);
+                            let ray = self.lens_ray(x as f32 + 0.5, y as f32 + 0.5, &mut lens);
+                            world.color_at(&ray)
+                        })
+                    })
+                    .collect();
+                for (px, _, pixel) in band.pixels_mut() {
+                    if let Some(color) = &colors[px / block] {
+                        *pixel = color.clone();
+                    }
+                }
+            });
+        }
+
+        // Every pixel now holds one centered sample, which is all a single
+        // centered ray sampler would take
+        let centered = matches!(self.sampler, Sampler::Single | Sampler::Grid(0 | 1));
+        if centered {
+            return image;
+        }
+        // Samples of the camera's sampler averaged so far on each row, the
+        // centered sample is replaced by the first one
+        let mut samples = vec![0; self.vsize];
+        for sample in 0..self.sampler.samples() {
+            if Instant::now() >= deadline {
+                break;
+            }
+            let rows: Vec<_> = image
+                .tiles_mut(self.hsize.max(1), 1)
+                .expect("Row tiles have a positive size")
+                .into_iter()
+                .zip(samples.iter_mut())
+                .collect();
+            rows.into_par_iter().for_each(|(mut row, count)| {
+                if Instant::now() >= deadline {
+                    return;
+                }
+                for (x, y, pixel) in row.pixels_mut() {
+                    let offset = self.sampler.offsets(x, y)[sample];
+                    let color = self.sample_color(world, x, y, sample, offset);
+                    let n = *count as f32;
+                    let average = (pixel.clone() * n + color) * (1. / (n + 1.));
+                    *pixel = Tuple::color(average.0, average.1, average.2);
+                }
+                *count += 1;
+            });
+        }
+        image
+    }
+
+    // Renders in parallel while recording how many rays and BVH nodes each pixel needed
+    pub fn render_with_stats(&self, world: &World) -> (Canvas, RayStatsMap) {
+        let mut image = Canvas::new(self.hsize, self.vsize);
+        let rows: Vec<_> = image
+            .tiles_mut(self.hsize.max(1), 1)
+            .expect("Row tiles have a positive size")
+            .into_iter()
+            .collect();
+        let row_stats: Vec<Vec<RayStats>> = rows
+            .into_par_iter()
+            .map(|mut row| {
+                let mut stats = vec![];
+                for (x, y, pixel) in row.pixels_mut() {
+                    RayStats::take();
+                    *pixel = self.color_for_pixel(world, x, y);
+                    stats.push(RayStats::take());
+                }
+                stats
+            })
+            .collect();
+
+        let mut stats_map = RayStatsMap::new(self.hsize, self.vsize);
+        for (y, row) in row_stats.into_iter().enumerate() {
+            for (x, stats) in row.into_iter().enumerate() {
+                stats_map
+                    .write_stats_at(x, y, stats)
+                    .expect("Stats map has the size of the image");
+            }
+        }
+        (image, stats_map)
+    }
+}
+
+// Lens samples get their own stream, drawing them from the sampler's would tie
+// the point on the lens to the position in the pixel. Each sample of a pixel
+// starts a stream of its own, so renders that come back to a pixel for more
+// samples pick the same points on the lens as a full render.
+fn lens_rng(x: usize, y: usize, sample: usize) -> Rng {
+    Rng::new(seed_from(&[x as f32, y as f32, sample as f32]))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::{FRAC_PI_2, FRAC_PI_4};
+
+    use float_cmp::approx_eq;
+
+    use super::*;
+    use crate::plane::Plane;
+    use crate::world::tests::default_world;
+
+    #[test]
+    fn construct_camera() {
+        let c = Camera::new(160, 120, FRAC_PI_2);
+        assert_eq!(c.get_hsize(), 160);
+        assert_eq!(c.get_vsize(), 120);
+        assert_eq!(c.get_field_of_view(), FRAC_PI_2);
+        assert_eq!(c.get_transform(), &Matrix4::identity());
+    }
+
+    #[test]
+    fn pixel_size_for_horizontal_canvas() {
+        let c = Camera::new(200, 125, FRAC_PI_2);
+        assert!(approx_eq!(f32, c.get_pixel_size(), 0.01, epsilon = 0.00001));
+    }
+
+    #[test]
+    fn pixel_size_for_vertical_canvas() {
+        let c = Camera::new(125, 200, FRAC_PI_2);
+        assert!(approx_eq!(f32, c.get_pixel_size(), 0.01, epsilon = 0.00001));
+    }
+
+    #[test]
+    fn ray_through_center_of_canvas() {
+        let c = Camera::new(201, 101, FRAC_PI_2);
+        let r = c.ray_for_pixel(100, 50);
+        assert!(r.origin.approx_eq(&Tuple::point(0., 0., 0.)));
+        assert!(r.direction.approx_eq(&Tuple::vector(0., 0., -1.)));
+    }
+
+    #[test]
+    fn ray_through_corner_of_canvas() {
+        let c = Camera::new(201, 101, FRAC_PI_2);
+        let r = c.ray_for_pixel(0, 0);
+        assert!(r.origin.approx_eq(&Tuple::point(0., 0., 0.)));
+        assert!(r
+            .direction
+            .approx_eq(&Tuple::vector(0.66519, 0.33259, -0.66851)));
+    }
+
+    #[test]
+    fn ray_when_camera_is_transformed() {
+        let mut c = Camera::new(201, 101, FRAC_PI_2);
+        c.set_transform(Matrix4::rotation_y(FRAC_PI_4) * Matrix4::translation(0., -2., 5.))
+            .unwrap();
+        let r = c.ray_for_pixel(100, 50);
+        let v = 2_f32.sqrt() / 2.;
+        assert!(r.origin.approx_eq(&Tuple::point(0., 2., -5.)));
+        assert!(r.direction.approx_eq(&Tuple::vector(v, 0., -v)));
+    }
+
+    fn default_camera() -> Camera {
+        let mut c = Camera::new(11, 11, FRAC_PI_2);
+        c.set_transform(Matrix4::view_transform(
+            &Tuple::point(0., 0., -5.),
+            &Tuple::point(0., 0., 0.),
+            &Tuple::vector(0., 1., 0.),
+        ))
+        .unwrap();
+        c
+    }
+
+    #[test]
+    fn render_world_with_camera() {
+        let image = default_camera().render(&default_world());
+        assert!(image
+            .get_pixel_at(5, 5)
+            .unwrap()
+            .approx_eq(&Tuple::color(0.38066, 0.47583, 0.2855)));
+    }
+
+    #[test]
+    fn render_parallel_matches_render() {
+        let w = default_world();
+        let c = default_camera();
+        assert_eq!(
+            c.render_parallel(&w).as_f32_rgba(),
+            c.render(&w).as_f32_rgba()
+        );
+    }
+
+    #[test]
+    fn camera_samples_one_ray_by_default() {
+        let c = Camera::new(10, 10, FRAC_PI_2);
+        assert_eq!(c.get_sampler(), &Sampler::Single);
+        let c = c.with_samples(4);
+        assert_eq!(
+            c.get_sampler(),
+            &Sampler::Jittered {
+                samples: 4,
+                seed: 0
+            }
+        );
+    }
+
+    #[test]
+    fn supersampled_pixel_averages_its_rays() {
+        let w = default_world();
+        let c = default_camera().with_sampler(Sampler::Grid(2));
+        let image = c.render(&w);
+        // On the edge of the sphere some of the sub-pixel rays miss
+        let (x, y) = (4, 5);
+        let expected = [(0.25, 0.25), (0.75, 0.25), (0.25, 0.75), (0.75, 0.75)]
+            .iter()
+            .map(|(u, v)| w.color_at(&c.ray_through(x as f32 + u, y as f32 + v)))
+            .fold(Tuple::color(0., 0., 0.), |acc, color| acc + color)
+            * 0.25;
+        let pixel = image.get_pixel_at(x, y).unwrap();
+        assert!(pixel.approx_eq(&Tuple::color(expected.0, expected.1, expected.2)));
+        assert!(!pixel.approx_eq(default_camera().render(&w).get_pixel_at(x, y).unwrap()));
+    }
+
+    #[test]
+    fn seeded_supersampling_is_repeatable() {
+        let w = default_world();
+        let c = default_camera().with_sampler(Sampler::Stratified {
+            per_side: 2,
+            seed: 9,
+        });
+        let image = c.render(&w);
+        assert_eq!(image.as_f32_rgba(), c.render(&w).as_f32_rgba());
+        assert_eq!(image.as_f32_rgba(), c.render_parallel(&w).as_f32_rgba());
+    }
+
+    #[test]
+    fn render_with_stats_counts_rays_per_pixel() {
+        let w = default_world();
+        let c = default_camera();
+        let (image, stats) = c.render_with_stats(&w);
+        assert_eq!(image.as_f32_rgba(), c.render(&w).as_f32_rgba());
+        assert_eq!(stats.total().primary_rays, 121);
+
+        // The center pixel hits a sphere and needs a shadow ray, the corner misses
+        let center = stats.get_stats_at(5, 5).unwrap();
+        assert_eq!(center.primary_rays, 1);
+        assert_eq!(center.shadow_rays, 1);
+        assert_eq!(stats.get_stats_at(0, 0).unwrap().shadow_rays, 0);
+    }
+
+    #[test]
+    fn ray_through_pixel_center() {
+        let c = default_camera();
+        assert_eq!(c.ray_through(3.5, 7.5), c.ray_for_pixel(3, 7));
+    }
+
+    #[test]
+    fn budgeted_render_with_no_time_is_coarse() {
+        let w = default_world();
+        let c = Camera::new(20, 20, FRAC_PI_2);
+        let image = c.render_with_budget(&w, Duration::ZERO);
+        // Only the coarsest pass ran, so each block repeats its top left pixel
+        let corner = c.render(&w);
+        for y in 0..20 {
+            for x in 0..20 {
+                let expected = corner.get_pixel_at(x / 16 * 16, y / 16 * 16).unwrap();
+                assert_eq!(image.get_pixel_at(x, y).unwrap(), expected);
+            }
+        }
+    }
+
+    fn assert_canvases_match(a: &Canvas, b: &Canvas) {
+        for y in 0..a.get_height() {
+            for x in 0..a.get_width() {
+                let (pa, pb) = (a.get_pixel_at(x, y).unwrap(), b.get_pixel_at(x, y).unwrap());
+                assert!(pa.approx_eq(pb), "pixel {},{}: {:?} != {:?}", x, y, pa, pb);
+            }
+        }
+    }
+
+    #[test]
+    fn budgeted_render_with_time_converges() {
+        let w = default_world();
+        let c = default_camera();
+        let image = c.render_with_budget(&w, Duration::from_secs(5));
+        assert_eq!(image.as_f32_rgba(), c.render(&w).as_f32_rgba());
+
+        let samplers = [
+            Sampler::Grid(2),
+            Sampler::Stratified {
+                per_side: 2,
+                seed: 3,
+            },
+            Sampler::Jittered {
+                samples: 80,
+                seed: 5,
+            },
+        ];
+        for sampler in samplers {
+            let c = default_camera().with_sampler(sampler);
+            let image = c.render_with_budget(&w, Duration::from_secs(30));
+            assert_canvases_match(&image, &c.render(&w));
+        }
+    }
+
+    #[test]
+    fn budgeted_render_converges_with_depth_of_field() {
+        let w = default_world();
+        let c = default_camera()
+            .with_samples(16)
+            .with_depth_of_field(2., 100.);
+        let coarse = c.render_with_budget(&w, Duration::ZERO);
+        let mut lens = lens_rng(0, 0, 0);
+        let first = w.color_at(&c.lens_ray(0.5, 0.5, &mut lens));
+        assert_eq!(coarse.get_pixel_at(0, 0).unwrap(), &first);
+
+        // Both average the same lens samples of the blurred edge of the sphere
+        let image = c.render_with_budget(&w, Duration::from_secs(30));
+        let reference = c.render(&w);
+        assert_ne!(
+            reference.get_pixel_at(3, 5).unwrap(),
+            &Tuple::color(0., 0., 0.)
+        );
+        assert_canvases_match(&image, &reference);
+    }
+
+    #[test]
+    fn project_point_onto_canvas() {
+        let c = default_camera();
+        let (x, y) = c.project(&Tuple::point(0., 0., 0.)).unwrap();
+        assert!(approx_eq!(f32, x, 5.5, epsilon = 0.0001));
+        assert!(approx_eq!(f32, y, 5.5, epsilon = 0.0001));
+        assert!(c.project(&Tuple::point(0., 0., -6.)).is_none());
+    }
+
+    #[test]
+    fn project_inverts_ray_through() {
+        let c = default_camera();
+        let r = c.ray_through(2.25, 8.5);
+        let (x, y) = c.project(&r.position(3.)).unwrap();
+        assert!(approx_eq!(f32, x, 2.25, epsilon = 0.001));
+        assert!(approx_eq!(f32, y, 8.5, epsilon = 0.001));
+    }
+
+    #[test]
+    fn focus_distance_to_object_under_pixel() {
+        let c = default_camera();
+        let w = default_world();
+        let d = c.focus_distance_at(&w, 5.5, 5.5).unwrap();
+        assert!(approx_eq!(f32, d, 4., epsilon = 0.0001));
+        assert_eq!(c.focus_distance_at(&w, 0., 0.), None);
+    }
+
+    #[test]
+    fn focus_distance_is_measured_along_view_direction() {
+        let c = default_camera();
+        let mut w = default_world();
+        w.objects = vec![Box::new(Plane::new())];
+        w.objects[0]
+            .set_transform(Matrix4::translation(0., 0., 2.) * Matrix4::rotation_x(FRAC_PI_2))
+            .unwrap();
+        // Every point of a plane facing the camera is in focus at the same distance
+        let center = c.focus_distance_at(&w, 5.5, 5.5).unwrap();
+        let corner = c.focus_distance_at(&w, 0.5, 0.5).unwrap();
+        assert!(approx_eq!(f32, center, 7., epsilon = 0.001));
+        assert!(approx_eq!(f32, corner, 7., epsilon = 0.001));
+    }
+
+    #[test]
+    fn focus_rect_is_clipped_to_image() {
+        let c = default_camera();
+        let w = default_world();
+        let focus = FocusRegion::Rect {
+            x: 8,
+            y: 2,
+            width: 10,
+            height: 3,
+        };
+        assert_eq!(c.focus_rect(&w, &focus), (8, 2, 3, 3));
+    }
+
+    #[test]
+    fn focus_rect_of_object() {
+        let c = default_camera();
+        let w = default_world();
+        // The inner sphere of radius 0.5, seen from 5 units away
+        let (x, y, width, height) = c.focus_rect(&w, &FocusRegion::Object(1));
+        assert_eq!((x, y), (4, 4));
+        assert_eq!((width, height), (3, 3));
+        assert_eq!(c.focus_rect(&w, &FocusRegion::Object(7)), (0, 0, 11, 11));
+    }
+
+    #[test]
+    fn render_focused_fills_focus_first() {
+        let w = default_world();
+        let mut c = Camera::new(40, 20, FRAC_PI_2);
+        c.set_transform(Matrix4::view_transform(
+            &Tuple::point(0., 0., -5.),
+            &Tuple::point(0., 0., 0.),
+            &Tuple::vector(0., 1., 0.),
+        ))
+        .unwrap();
+        let focus = FocusRegion::Rect {
+            x: 0,
+            y: 0,
+            width: 4,
+            height: 4,
+        };
+        let reference = c.render(&w);
+        let mut passes = vec![];
+        let image = c.render_focused(&w, &focus, 1, |image| passes.push(image.clone()));
+
+        assert_eq!(passes.len(), 2);
+        let first = &passes[0];
+        // Only the top left tile was rendered in the first pass
+        let center = (20, 10);
+        assert_eq!(
+            first.get_pixel_at(center.0, center.1).unwrap(),
+            &Tuple::color(0., 0., 0.)
+        );
+        assert_ne!(
+            reference.get_pixel_at(center.0, center.1).unwrap(),
+            &Tuple::color(0., 0., 0.)
+        );
+        assert_eq!(image.as_f32_rgba(), reference.as_f32_rgba());
+    }
+
+    #[test]
+    fn focus_gets_more_samples() {
+        let w = default_world();
+        let c = default_camera();
+        let focus = FocusRegion::Object(0);
+        let image = c.render_focused(&w, &focus, 8, |_| {});
+        let reference = c.render(&w);
+        // Supersampled pixels differ from single centered samples along the edges
+        assert_ne!(image.as_f32_rgba(), reference.as_f32_rgba());
+    }
+
+    #[test]
+    fn focus_render_keeps_the_camera_sampler() {
+        let w = default_world();
+        let c = default_camera().with_samples(16);
+        let focus = FocusRegion::Rect {
+            x: 0,
+            y: 0,
+            width: 4,
+            height: 4,
+        };
+        // The focus asks for fewer samples than the camera takes anyway
+        let image = c.render_focused(&w, &focus, 4, |_| {});
+        assert_eq!(image.as_f32_rgba(), c.render(&w).as_f32_rgba());
+
+        let more = c.render_focused(&w, &FocusRegion::Object(0), 64, |_| {});
+        let expected = c.clone().with_samples(64).color_for_pixel(&w, 4, 5);
+        assert_eq!(more.get_pixel_at(4, 5).unwrap(), &expected);
+    }
+
+    #[test]
+    fn camera_is_pinhole_by_default() {
+        let c = default_camera();
+        assert_eq!(c.get_aperture(), 0.);
+        assert_eq!(c.get_focal_distance(), 1.);
+        assert_eq!(c.get_aperture_shape(), &Aperture::Disc);
+        let mut rng = Rng::new(3);
+        assert_eq!(c.lens_ray(2.5, 7.5, &mut rng), c.ray_through(2.5, 7.5));
+    }
+
+    #[test]
+    fn lens_rays_meet_at_focal_plane() {
+        let c = default_camera().with_depth_of_field(0.5, 5.);
+        let pinhole = c.ray_through(2.5, 7.5);
+        // The focal plane is 5 units in front of the camera at z = -5, i.e. at z = 0
+        let focus = pinhole.position(-pinhole.origin.2 / pinhole.direction.2);
+        let mut rng = Rng::new(3);
+        let mut origins = vec![];
+        for _ in 0..8 {
+            let ray = c.lens_ray(2.5, 7.5, &mut rng);
+            let lens = Tuple::point(ray.origin.0, ray.origin.1, ray.origin.2 + 5.);
+            assert!(lens.mag() <= 0.5 + EPSILON);
+            assert!(ray
+                .position(-ray.origin.2 / ray.direction.2)
+                .approx_eq(&focus));
+            origins.push(ray.origin);
+        }
+        assert!(origins.iter().any(|o| !o.approx_eq(&origins[0])));
+    }
+
+    #[test]
+    fn out_of_focus_edges_blur() {
+        let w = default_world();
+        let sharp = default_camera().with_samples(64);
+        let blurred = default_camera()
+            .with_samples(64)
+            .with_depth_of_field(2., 100.);
+        // Just outside the sphere, only rays from the side of the lens reach it
+        assert_eq!(sharp.color_for_pixel(&w, 3, 5), Tuple::color(0., 0., 0.));
+        assert_ne!(blurred.color_for_pixel(&w, 3, 5), Tuple::color(0., 0., 0.));
+        assert_eq!(
+            blurred.color_for_pixel(&w, 3, 5),
+            blurred.color_for_pixel(&w, 3, 5)
+        );
+    }
+
+    #[test]
+    fn autofocus_on_screen_point() {
+        let w = default_world();
+        let mut c = default_camera().with_depth_of_field(0.1, 1.);
+        let d = c.autofocus(&w, 5.5, 5.5).unwrap();
+        assert!(approx_eq!(f32, d, 4., epsilon = EPSILON));
+        assert_eq!(c.get_focal_distance(), d);
+    }
+
+    #[test]
+    fn autofocus_on_nothing_keeps_the_focal_distance() {
+        let w = default_world();
+        let mut c = default_camera().with_depth_of_field(0.1, 2.5);
+        assert_eq!(c.autofocus(&w, 0., 0.), None);
+        assert_eq!(c.get_focal_distance(), 2.5);
+    }
+
+    #[test]
+    fn progressive_render_hands_out_every_tile() {
+        let w = default_world();
+        let c = Camera::new(70, 40, FRAC_PI_2);
+        let mut shown = Canvas::new(70, 40);
+        let mut count = 0;
+        let image = c.render_progressive(&w, |tile| {
+            shown.write_tile(&tile).unwrap();
+            count += 1;
+            ControlFlow::Continue(())
+        });
+        // 3 by 2 tiles of at most 32x32 pixels
+        assert_eq!(count, 6);
+        assert_eq!(shown.as_f32_rgba(), image.as_f32_rgba());
+        assert_eq!(image.as_f32_rgba(), c.render(&w).as_f32_rgba());
+    }
+
+    #[test]
+    fn progressive_render_can_be_cancelled() {
+        let w = default_world();
+        let c = Camera::new(160, 160, FRAC_PI_2).with_samples(4);
+        let mut count = 0;
+        let image = c.render_progressive(&w, |_| {
+            count += 1;
+            ControlFlow::Break(())
+        });
+        assert_eq!(count, 1);
+        assert_eq!(image.get_width(), 160);
+    }
+}
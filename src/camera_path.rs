@@ -0,0 +1,263 @@
+use crate::camera::Camera;
+use crate::canvas::Canvas;
+use crate::matrix::Matrix4;
+use crate::tuple::Tuple;
+use crate::world::World;
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct Keyframe {
+    pub from: Tuple,
+    pub to: Tuple,
+    pub up: Tuple,
+}
+
+impl Keyframe {
+    pub fn new(from: Tuple, to: Tuple, up: Tuple) -> Self {
+        Keyframe { from, to, up }
+    }
+}
+
+// Smooth camera motion through keyframes, every segment between two keyframes
+// takes the same share of the path
+#[derive(PartialEq, Debug, Clone)]
+pub struct CameraPath {
+    keyframes: Vec<Keyframe>,
+}
+
+impl CameraPath {
+    pub fn new(keyframes: Vec<Keyframe>) -> Result<Self, String> {
+        if keyframes.is_empty() {
+            return Err("camera path needs at least one keyframe".to_string());
+        }
+        Ok(CameraPath { keyframes })
+    }
+
+    pub fn get_keyframes(&self) -> &[Keyframe] {
+        &self.keyframes
+    }
+
+    // Position along the path for t from 0 (first keyframe) to 1 (last keyframe)
+    pub fn keyframe_at(&self, t: f32) -> Keyframe {
+        let segments = self.keyframes.len() - 1;
+        if segments == 0 {
+            return self.keyframes[0].clone();
+        }
+        let position = t.clamp(0., 1.) * segments as f32;
+        let segment = (position.floor() as usize).min(segments - 1);
+        let u = position - segment as f32;
+
+        // The end keyframes are repeated so the curve starts and stops at them
+        let at = |i: isize| &self.keyframes[i.clamp(0, segments as isize) as usize];
+        let i = segment as isize;
+        let (k0, k1, k2, k3) = (at(i - 1), at(i), at(i + 1), at(i + 2));
+        Keyframe {
+            from: catmull_rom(&k0.from, &k1.from, &k2.from, &k3.from, u),
+            to: catmull_rom(&k0.to, &k1.to, &k2.to, &k3.to, u),
+            up: catmull_rom(&k0.up, &k1.up, &k2.up, &k3.up, u),
+        }
+    }
+
+    pub fn view_transform_at(&self, t: f32) -> Matrix4 {
+        let keyframe = self.keyframe_at(t);
+        Matrix4::view_transform(&keyframe.from, &keyframe.to, &keyframe.up)
+    }
+
+    // Renders frame_count frames spread evenly over the path and hands each to
+    // on_frame, e.g. to save it. The world and its bounding volumes are shared
+    // by all frames.
+    pub fn render_frames<F>(
+        &self,
+        camera: &Camera,
+        world: &World,
+        frame_count: usize,
+        mut on_frame: F,
+    ) -> Result<(), String>
+    where
+        F: FnMut(usize, &Canvas) -> Result<(), String>,
+    {
+        let mut camera = camera.clone();
+        for frame in 0..frame_count {
+            camera.set_transform(self.view_transform_at(frame_time(frame, frame_count)))?;
+            on_frame(frame, &camera.render_parallel(world))?;
+        }
+        Ok(())
+    }
+
+    // Like render_frames, but before each frame update gets the chance to change
+    // the world, e.g. to move objects by script or simulation
+    pub fn render_animated_frames<U, F>(
+        &self,
+        camera: &Camera,
+        world: &mut World,
+        frame_count: usize,
+        mut update: U,
+        mut on_frame: F,
+    ) -> Result<(), String>
+    where
+        U: FnMut(usize, f32, &mut World),
+        F: FnMut(usize, &Canvas) -> Result<(), String>,
+    {
+        let mut camera = camera.clone();
+        for frame in 0..frame_count {
+            let t = frame_time(frame, frame_count);
+            update(frame, t, world);
+            camera.set_transform(self.view_transform_at(t))?;
+            on_frame(frame, &camera.render_parallel(world))?;
+        }
+        Ok(())
+    }
+}
+
+// Where on the path a frame is, the first and last frames sit on the end keyframes
+pub(crate) fn frame_time(frame: usize, frame_count: usize) -> f32 {
+    if frame_count <= 1 {
+        0.
+    } else {
+        frame as f32 / (frame_count - 1) as f32
+    }
+}
+
+fn catmull_rom(p0: &Tuple, p1: &Tuple, p2: &Tuple, p3: &Tuple, u: f32) -> Tuple {
+    let (u2, u3) = (u * u, u * u * u);
+    let a = p1.clone() * 2.;
+    let b = (p2.clone() - p0.clone()) * u;
+    let c = (p0.clone() * 2. - p1.clone() * 5. + p2.clone() * 4. - p3.clone()) * u2;
+    let d = (p1.clone() * 3. - p0.clone() - p2.clone() * 3. + p3.clone()) * u3;
+    (a + b + c + d) * 0.5
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use super::*;
+    use crate::background::Background;
+    use crate::world::tests::default_world;
+
+    fn keyframe(x: f32, z: f32) -> Keyframe {
+        Keyframe::new(
+            Tuple::point(x, 0., z),
+            Tuple::point(0., 0., 0.),
+            Tuple::vector(0., 1., 0.),
+        )
+    }
+
+    fn path() -> CameraPath {
+        CameraPath::new(vec![
+            keyframe(0., -5.),
+            keyframe(5., 0.),
+            keyframe(0., 5.),
+            keyframe(-5., 0.),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn path_needs_keyframes() {
+        assert!(CameraPath::new(vec![]).is_err());
+    }
+
+    #[test]
+    fn path_passes_through_keyframes() {
+        let p = path();
+        for (i, t) in [0., 1. / 3., 2. / 3., 1.].into_iter().enumerate() {
+            let k = p.keyframe_at(t);
+            assert!(k.from.approx_eq(&p.get_keyframes()[i].from));
+            assert!(k.to.approx_eq(&p.get_keyframes()[i].to));
+            assert!(k.up.approx_eq(&p.get_keyframes()[i].up));
+        }
+    }
+
+    #[test]
+    fn path_is_clamped_to_ends() {
+        let p = path();
+        assert_eq!(p.keyframe_at(-1.), p.keyframe_at(0.));
+        assert!(p.keyframe_at(2.).from.approx_eq(&Tuple::point(-5., 0., 0.)));
+    }
+
+    #[test]
+    fn catmull_rom_is_smooth_between_keyframes() {
+        let p = path();
+        // Halfway between the first two keyframes, pulled towards the third one
+        let k = p.keyframe_at(1. / 6.);
+        assert!(k.from.approx_eq(&Tuple::point(2.8125, 0., -2.8125)));
+        assert_eq!(k.from.3, 1.);
+        assert_eq!(k.up.3, 0.);
+    }
+
+    #[test]
+    fn single_keyframe_path_stands_still() {
+        let p = CameraPath::new(vec![keyframe(1., -5.)]).unwrap();
+        assert_eq!(p.keyframe_at(0.7), keyframe(1., -5.));
+    }
+
+    #[test]
+    fn render_frames_along_path() {
+        let w = default_world();
+        let camera = Camera::new(11, 11, FRAC_PI_2);
+        let p = path();
+        let mut frames = vec![];
+        p.render_frames(&camera, &w, 4, |frame, canvas| {
+            frames.push((frame, canvas.clone()));
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(frames.len(), 4);
+
+        let mut expected = camera.clone();
+        expected
+            .set_transform(p.view_transform_at(1. / 3.))
+            .unwrap();
+        assert_eq!(frames[1].0, 1);
+        assert_eq!(frames[1].1.as_f32_rgba(), expected.render(&w).as_f32_rgba());
+    }
+
+    #[test]
+    fn render_frames_stops_on_error() {
+        let w = default_world();
+        let camera = Camera::new(5, 5, FRAC_PI_2);
+        let mut rendered = 0;
+        let result = path().render_frames(&camera, &w, 4, |frame, _| {
+            rendered += 1;
+            if frame == 1 {
+                return Err("disk full".to_string());
+            }
+            Ok(())
+        });
+        assert_eq!(result, Err("disk full".to_string()));
+        assert_eq!(rendered, 2);
+    }
+
+    #[test]
+    fn render_animated_frames_updates_world_before_each_frame() {
+        let mut w = World::new();
+        let camera = Camera::new(3, 3, FRAC_PI_2);
+        let mut updates = vec![];
+        let mut pixels = vec![];
+        path()
+            .render_animated_frames(
+                &camera,
+                &mut w,
+                3,
+                |frame, t, world| {
+                    updates.push((frame, t));
+                    world.background = Background::Solid(Tuple::color(t, t, t));
+                },
+                |_, canvas| {
+                    pixels.push(canvas.get_pixel_at(1, 1).unwrap().clone());
+                    Ok(())
+                },
+            )
+            .unwrap();
+        assert_eq!(updates, vec![(0, 0.), (1, 0.5), (2, 1.)]);
+        assert_eq!(
+            pixels,
+            vec![
+                Tuple::color(0., 0., 0.),
+                Tuple::color(0.5, 0.5, 0.5),
+                Tuple::color(1., 1., 1.),
+            ]
+        );
+        assert_eq!(w.background, Background::Solid(Tuple::color(1., 1., 1.)));
+    }
+}
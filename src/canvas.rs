@@ -1,8 +1,12 @@
-use std::fs::File;
-use std::io::prelude::*;
+use std::fs::{self, File};
+use std::io::{self, prelude::*, BufWriter};
+use std::path::Path;
+
+use rayon::prelude::*;
 
 use crate::tuple::Tuple;
 
+#[derive(PartialEq, Debug, Clone)]
 pub struct Canvas {
     width: usize,
     height: usize,
@@ -18,6 +22,78 @@ impl Canvas {
         }
     }
 
+    // Reads plain (P3) and binary (P6) PPM images, scaling colors by the maximum value
+    pub fn from_ppm(data: &[u8]) -> Result<Self, String> {
+        let mut reader = PpmReader { data, position: 0 };
+        let magic = reader.next_token().ok_or("missing PPM magic number")?;
+        let binary = match magic {
+            "P3" => false,
+            "P6" => true,
+            _ => return Err(format!("unsupported PPM magic number '{}'", magic)),
+        };
+        let width = reader.next_number()?;
+        let height = reader.next_number()?;
+        if width == 0 || height == 0 {
+            return Err(format!(
+                "PPM image of size {}x{} has no pixels",
+                width, height
+            ));
+        }
+        let max_value = reader.next_number()?;
+        if max_value == 0 || max_value > 65535 {
+            return Err(format!("invalid PPM maximum value {}", max_value));
+        }
+
+        // A single whitespace byte separates the header from binary pixel data
+        reader.position += 1;
+        let bytes_per_value = if max_value < 256 { 1 } else { 2 };
+        let scale = max_value as f32;
+
+        // Check the size against the data before allocating anything for it. Plain
+        // values take at least a digit and a separator each, except the last one.
+        let bytes_per_pixel = if binary { 3 * bytes_per_value } else { 6 };
+        let needed = width
+            .checked_mul(height)
+            .and_then(|pixels| pixels.checked_mul(bytes_per_pixel))
+            .map(|bytes| if binary { bytes } else { bytes - 1 });
+        let remaining = data.len().saturating_sub(reader.position);
+        if needed.is_none_or(|needed| needed > remaining) {
+            return Err(format!(
+                "unexpected end of PPM data for a {}x{} image",
+                width, height
+            ));
+        }
+
+        let mut canvas = Canvas::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let mut channels = [0.; 3];
+                for channel in &mut channels {
+                    let value = if binary {
+                        reader.next_binary(bytes_per_value)?
+                    } else {
+                        reader.next_number()?
+                    };
+                    if value > max_value {
+                        return Err(format!(
+                            "PPM value {} exceeds the maximum value {}",
+                            value, max_value
+                        ));
+                    }
+                    *channel = value as f32 / scale;
+                }
+                canvas.pixels[y][x] = Tuple::color(channels[0], channels[1], channels[2]);
+            }
+        }
+        Ok(canvas)
+    }
+
+    pub fn load_ppm(path: &Path) -> Result<Self, String> {
+        let data =
+            fs::read(path).map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+        Self::from_ppm(&data)
+    }
+
     pub fn get_width(&self) -> usize {
         self.width
     }
@@ -47,29 +123,355 @@ impl Canvas {
         Ok(())
     }
 
+    // Row-major, top row first, four bytes per pixel with opaque alpha
+    pub fn as_rgba8(&self) -> Vec<u8> {
+        encode_rows(&self.pixels, |row| {
+            let mut bytes = Vec::with_capacity(row.len() * 4);
+            for pixel in row {
+                bytes.extend([to_byte(pixel.0), to_byte(pixel.1), to_byte(pixel.2), 255]);
+            }
+            bytes
+        })
+        .concat()
+    }
+
+    // Same layout as as_rgba8 but keeps the unclamped colors, e.g. for HDR output
+    pub fn as_f32_rgba(&self) -> Vec<f32> {
+        let mut buffer = Vec::with_capacity(self.width * self.height * 4);
+        for pixel in self.pixels.iter().flatten() {
+            buffer.extend([pixel.0, pixel.1, pixel.2, 1.]);
+        }
+        buffer
+    }
+
+    // Splits the canvas into disjoint tiles, row by row from the top left, so each
+    // can be filled by a different thread. Tiles on the right and bottom edges
+    // are cut to fit.
+    pub fn tiles_mut(
+        &mut self,
+        tile_width: usize,
+        tile_height: usize,
+    ) -> Result<CanvasTiles<'_>, String> {
+        if tile_width == 0 || tile_height == 0 {
+            return Err(format!(
+                "tile size must be positive, got {}x{}",
+                tile_width, tile_height
+            ));
+        }
+        let width = self.width;
+        let mut tiles = vec![];
+        for (band, rows) in self.pixels.chunks_mut(tile_height).enumerate() {
+            let y = band * tile_height;
+            let mut band_tiles: Vec<CanvasTile> = (0..width)
+                .step_by(tile_width)
+                .map(|x| CanvasTile {
+                    x,
+                    y,
+                    width: tile_width.min(width - x),
+                    height: rows.len(),
+                    rows: vec![],
+                })
+                .collect();
+            for row in rows.iter_mut() {
+                for (tile, part) in band_tiles.iter_mut().zip(row.chunks_mut(tile_width)) {
+                    tile.rows.push(part);
+                }
+            }
+            tiles.extend(band_tiles);
+        }
+        Ok(CanvasTiles { tiles })
+    }
+
+    // Copies a finished tile into place, e.g. one sent over by a progressive render
+    pub fn write_tile(&mut self, tile: &Tile) -> Result<(), String> {
+        if tile.x + tile.width > self.width || tile.y + tile.height > self.height {
+            return Err(format!(
+                "tile at {}, {} of size {}x{} doesn't fit into the canvas",
+                tile.x, tile.y, tile.width, tile.height
+            ));
+        }
+        for (row, pixels) in tile.pixels.chunks(tile.width.max(1)).enumerate() {
+            let start = tile.x;
+            self.pixels[tile.y + row][start..start + tile.width].clone_from_slice(pixels);
+        }
+        Ok(())
+    }
+
     pub fn to_ppm_string(&self) -> String {
-        let mut ppm = format!("P3\n{} {}\n255\n", self.width, self.height);
-        for col in &self.pixels {
-            for pixel in col {
-                let red = (pixel.0 * 255.).clamp(0., 255.).round();
-                let green = (pixel.1 * 255.).clamp(0., 255.).round();
-                let blue = (pixel.2 * 255.).clamp(0., 255.).round();
-                ppm.push_str(&format!("{} {} {} ", red, green, blue));
+        self.ppm_header() + &encode_rows(&self.pixels, ppm_row).concat()
+    }
+
+    // Streams the PPM in batches of rows so large images never exist as one String
+    pub fn write_ppm<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(self.ppm_header().as_bytes())?;
+        let threads = rayon::current_num_threads();
+        for batch in self.pixels.chunks(threads * PPM_ROWS_PER_THREAD) {
+            for row in encode_rows(batch, ppm_row) {
+                writer.write_all(row.as_bytes())?;
             }
-            ppm.push('\n');
         }
-        ppm
+        Ok(())
+    }
+
+    pub fn save_ppm(&self, path: &Path) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_ppm(&mut writer)?;
+        writer.flush()
+    }
+
+    pub fn write_to_file(&self, file_path: &str) -> io::Result<()> {
+        self.save_ppm(Path::new(file_path))
+    }
+
+    fn ppm_header(&self) -> String {
+        format!("P3\n{} {}\n255\n", self.width, self.height)
+    }
+}
+
+// Rows each thread encodes per batch when streaming a PPM
+const PPM_ROWS_PER_THREAD: usize = 16;
+// Longest line PPM readers have to accept
+const PPM_MAX_LINE_LENGTH: usize = 70;
+
+// Encodes the rows on the shared thread pool, results stay in row order
+fn encode_rows<T, F>(rows: &[Vec<Tuple>], encode: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(&[Tuple]) -> T + Sync,
+{
+    rows.par_iter().map(|row| encode(row)).collect()
+}
+
+// One canvas row, wrapped so no line is longer than 70 characters
+fn ppm_row(row: &[Tuple]) -> String {
+    let mut text = String::new();
+    let mut line_length = 0;
+    for value in row.iter().flat_map(|pixel| [pixel.0, pixel.1, pixel.2]) {
+        let token = to_byte(value).to_string();
+        if line_length > 0 && line_length + 1 + token.len() > PPM_MAX_LINE_LENGTH {
+            text.push('\n');
+            line_length = 0;
+        }
+        if line_length > 0 {
+            text.push(' ');
+            line_length += 1;
+        }
+        text.push_str(&token);
+        line_length += token.len();
+    }
+    text.push('\n');
+    text
+}
+
+// Mutable view of a rectangle of a canvas, coordinates are those of the whole canvas
+#[derive(Debug)]
+pub struct CanvasTile<'a> {
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+    rows: Vec<&'a mut [Tuple]>,
+}
+
+impl<'a> CanvasTile<'a> {
+    pub fn get_x(&self) -> usize {
+        self.x
     }
 
-    pub fn write_to_file(&self, file_path: &str) -> std::io::Result<()> {
-        let mut file = File::create(file_path)?;
-        file.write_all(self.to_ppm_string().as_bytes())?;
+    pub fn get_y(&self) -> usize {
+        self.y
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get_pixel_at(&self, x: usize, y: usize) -> Result<&Tuple, String> {
+        let (col, row) = self.local(x, y)?;
+        Ok(&self.rows[row][col])
+    }
+
+    pub fn write_pixel_at(&mut self, x: usize, y: usize, color: Tuple) -> Result<(), String> {
+        let (col, row) = self.local(x, y)?;
+        self.rows[row][col] = color;
         Ok(())
     }
+
+    // Every pixel of the tile along with its canvas coordinates
+    pub fn pixels_mut(&mut self) -> impl Iterator<Item = (usize, usize, &mut Tuple)> + use<'_, 'a> {
+        let (x0, y0) = (self.x, self.y);
+        self.rows
+            .iter_mut()
+            .enumerate()
+            .flat_map(move |(row, pixels)| {
+                pixels
+                    .iter_mut()
+                    .enumerate()
+                    .map(move |(col, pixel)| (x0 + col, y0 + row, pixel))
+            })
+    }
+
+    // Copy of the pixels that outlives the borrow of the canvas
+    pub fn to_tile(&self) -> Tile {
+        Tile {
+            x: self.x,
+            y: self.y,
+            width: self.width,
+            height: self.height,
+            pixels: self.rows.concat(),
+        }
+    }
+
+    fn local(&self, x: usize, y: usize) -> Result<(usize, usize), String> {
+        tile_local(self.x, self.y, self.width, self.height, x, y)
+    }
+}
+
+// Finished rectangle of an image, pixels are stored row by row and addressed
+// with the coordinates of the whole canvas
+#[derive(PartialEq, Debug, Clone)]
+pub struct Tile {
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+    pixels: Vec<Tuple>,
+}
+
+impl Tile {
+    pub fn get_x(&self) -> usize {
+        self.x
+    }
+
+    pub fn get_y(&self) -> usize {
+        self.y
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get_pixels(&self) -> &[Tuple] {
+        &self.pixels
+    }
+
+    pub fn get_pixel_at(&self, x: usize, y: usize) -> Result<&Tuple, String> {
+        let (col, row) = tile_local(self.x, self.y, self.width, self.height, x, y)?;
+        Ok(&self.pixels[row * self.width + col])
+    }
+}
+
+fn tile_local(
+    x0: usize,
+    y0: usize,
+    width: usize,
+    height: usize,
+    x: usize,
+    y: usize,
+) -> Result<(usize, usize), String> {
+    if x < x0 || x >= x0 + width {
+        return Err(format!("x {} outside of tile", x));
+    }
+    if y < y0 || y >= y0 + height {
+        return Err(format!("y {} outside of tile", y));
+    }
+    Ok((x - x0, y - y0))
+}
+
+#[derive(Debug)]
+pub struct CanvasTiles<'a> {
+    tiles: Vec<CanvasTile<'a>>,
+}
+
+impl<'a> CanvasTiles<'a> {
+    pub fn len(&self) -> usize {
+        self.tiles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, CanvasTile<'a>> {
+        self.tiles.iter_mut()
+    }
+}
+
+impl<'a> IntoIterator for CanvasTiles<'a> {
+    type Item = CanvasTile<'a>;
+    type IntoIter = std::vec::IntoIter<CanvasTile<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.tiles.into_iter()
+    }
+}
+
+struct PpmReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> PpmReader<'a> {
+    // Skips whitespace and comments, which run from '#' to the end of the line
+    fn next_token(&mut self) -> Option<&'a str> {
+        loop {
+            match self.data.get(self.position)? {
+                b'#' => {
+                    while self.data.get(self.position).is_some_and(|&b| b != b'\n') {
+                        self.position += 1;
+                    }
+                }
+                b if b.is_ascii_whitespace() => self.position += 1,
+                _ => break,
+            }
+        }
+        let start = self.position;
+        while self
+            .data
+            .get(self.position)
+            .is_some_and(|b| !b.is_ascii_whitespace())
+        {
+            self.position += 1;
+        }
+        std::str::from_utf8(&self.data[start..self.position]).ok()
+    }
+
+    fn next_number(&mut self) -> Result<usize, String> {
+        let token = self.next_token().ok_or("unexpected end of PPM data")?;
+        token
+            .parse()
+            .map_err(|_| format!("invalid number '{}' in PPM data", token))
+    }
+
+    fn next_binary(&mut self, bytes: usize) -> Result<usize, String> {
+        let end = self.position + bytes;
+        let value = self
+            .data
+            .get(self.position..end)
+            .ok_or("unexpected end of PPM data")?
+            .iter()
+            .fold(0, |value, &b| (value << 8) | b as usize);
+        self.position = end;
+        Ok(value)
+    }
+}
+
+fn to_byte(channel: f32) -> u8 {
+    (channel * 255.).clamp(0., 255.).round() as u8
 }
 
 #[cfg(test)]
 mod tests {
+    use std::thread;
+
     use super::*;
 
     #[test]
@@ -107,7 +509,263 @@ mod tests {
 
         assert_eq!(
             c.to_ppm_string(),
-            "P3\n5 3\n255\n255 0 0 0 0 0 0 0 0 0 0 0 0 0 0 \n0 0 0 0 0 0 0 128 0 0 0 0 0 0 0 \n0 0 0 0 0 0 0 0 0 0 0 0 0 0 255 \n"
+            "P3\n5 3\n255\n255 0 0 0 0 0 0 0 0 0 0 0 0 0 0\n0 0 0 0 0 0 0 128 0 0 0 0 0 0 0\n0 0 0 0 0 0 0 0 0 0 0 0 0 0 255\n"
         );
     }
+
+    #[test]
+    fn split_long_lines_in_ppm() {
+        let mut c = Canvas::new(10, 2);
+        for y in 0..2 {
+            for x in 0..10 {
+                c.write_pixel_at(x, y, Tuple::color(1., 0.8, 0.6)).unwrap();
+            }
+        }
+        let ppm = c.to_ppm_string();
+        let lines: Vec<&str> = ppm.lines().skip(3).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "255 204 153 255 204 153 255 204 153 255 204 153 255 204 153 255 204",
+                "153 255 204 153 255 204 153 255 204 153 255 204 153",
+                "255 204 153 255 204 153 255 204 153 255 204 153 255 204 153 255 204",
+                "153 255 204 153 255 204 153 255 204 153 255 204 153",
+            ]
+        );
+    }
+
+    #[test]
+    fn ppm_ends_with_newline() {
+        let c = Canvas::new(5, 3);
+        assert!(c.to_ppm_string().ends_with('\n'));
+    }
+
+    #[test]
+    fn save_ppm_matches_ppm_string() {
+        let mut c = Canvas::new(30, 40);
+        c.write_pixel_at(29, 39, Tuple::color(0.2, 0.4, 0.6))
+            .unwrap();
+        let path = std::env::temp_dir().join(format!("ray_tracer_{}.ppm", std::process::id()));
+        c.save_ppm(&path).unwrap();
+        let written = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(written, c.to_ppm_string());
+    }
+
+    #[test]
+    fn canvas_as_rgba8() {
+        let mut c = Canvas::new(2, 2);
+        c.write_pixel_at(1, 0, Tuple::color(1., 0.5, 0.)).unwrap();
+        c.write_pixel_at(0, 1, Tuple::color(1.5, -0.5, 0.2))
+            .unwrap();
+        assert_eq!(
+            c.as_rgba8(),
+            vec![0, 0, 0, 255, 255, 128, 0, 255, 255, 0, 51, 255, 0, 0, 0, 255]
+        );
+    }
+
+    #[test]
+    fn canvas_as_f32_rgba() {
+        let mut c = Canvas::new(2, 1);
+        c.write_pixel_at(1, 0, Tuple::color(1.5, -0.5, 0.2))
+            .unwrap();
+        assert_eq!(c.as_f32_rgba(), vec![0., 0., 0., 1., 1.5, -0.5, 0.2, 1.]);
+    }
+
+    #[test]
+    fn encode_tall_canvas_in_row_order() {
+        let mut c = Canvas::new(1, 100);
+        for y in 0..100 {
+            c.write_pixel_at(0, y, Tuple::color(y as f32 / 255., 0., 0.))
+                .unwrap();
+        }
+        let rgba = c.as_rgba8();
+        let ppm = c.to_ppm_string();
+        let lines: Vec<&str> = ppm.lines().skip(3).collect();
+        for y in 0..100 {
+            assert_eq!(rgba[y * 4], y as u8);
+            assert_eq!(lines[y], format!("{} 0 0", y));
+        }
+    }
+
+    #[test]
+    fn read_file_with_wrong_magic_number() {
+        assert!(Canvas::from_ppm(b"P32\n1 1\n255\n0 0 0").is_err());
+    }
+
+    #[test]
+    fn read_ppm_returns_canvas_of_right_size() {
+        let c = Canvas::from_ppm(b"P3\n10 2\n255\n").unwrap_err();
+        assert!(c.contains("end of PPM data"));
+
+        let mut data = String::from("P3\n10 2\n255\n");
+        data.push_str(&"0 0 0 ".repeat(20));
+        let c = Canvas::from_ppm(data.as_bytes()).unwrap();
+        assert_eq!(c.get_width(), 10);
+        assert_eq!(c.get_height(), 2);
+    }
+
+    #[test]
+    fn read_ppm_checks_size_before_allocating() {
+        let c = Canvas::from_ppm(b"P6\n100000 100000\n255\n\0\0\0").unwrap_err();
+        assert!(c.contains("end of PPM data"));
+        let huge = format!("P3\n{} {}\n255\n0 0 0", usize::MAX, usize::MAX);
+        assert!(Canvas::from_ppm(huge.as_bytes()).is_err());
+        assert!(Canvas::from_ppm(b"P3\n0 1\n255\n").is_err());
+        assert!(Canvas::from_ppm(b"P3\n1 0\n255\n").is_err());
+    }
+
+    #[test]
+    fn read_ppm_rejects_values_above_maximum() {
+        let c = Canvas::from_ppm(b"P3\n1 1\n100\n50 101 0").unwrap_err();
+        assert!(c.contains("exceeds"));
+        let mut data = b"P6\n1 1\n15\n".to_vec();
+        data.extend([0, 16, 0]);
+        assert!(Canvas::from_ppm(&data).is_err());
+    }
+
+    #[test]
+    fn read_pixel_data_from_ppm() {
+        let data = b"P3\n4 3\n255\n\
+                     255 127 0  0 127 255  127 255 0  255 255 255\n\
+                     0 0 0  255 0 0  0 255 0  0 0 255\n\
+                     255 255 0  0 255 255  255 0 255  127 127 127\n";
+        let c = Canvas::from_ppm(data).unwrap();
+        let cases = [
+            (0, 0, Tuple::color(1., 0.49804, 0.)),
+            (1, 0, Tuple::color(0., 0.49804, 1.)),
+            (2, 0, Tuple::color(0.49804, 1., 0.)),
+            (3, 0, Tuple::color(1., 1., 1.)),
+            (0, 1, Tuple::color(0., 0., 0.)),
+            (1, 1, Tuple::color(1., 0., 0.)),
+            (2, 1, Tuple::color(0., 1., 0.)),
+            (3, 1, Tuple::color(0., 0., 1.)),
+            (0, 2, Tuple::color(1., 1., 0.)),
+            (1, 2, Tuple::color(0., 1., 1.)),
+            (2, 2, Tuple::color(1., 0., 1.)),
+            (3, 2, Tuple::color(0.49804, 0.49804, 0.49804)),
+        ];
+        for (x, y, expected) in cases {
+            assert!(c.get_pixel_at(x, y).unwrap().approx_eq(&expected));
+        }
+    }
+
+    #[test]
+    fn ppm_parsing_ignores_comments_and_line_breaks() {
+        let data = b"P3\n# this is a comment\n2 1\n# another\n100\n\
+                     100 100 100 # more\n0\n50\n100\n";
+        let c = Canvas::from_ppm(data).unwrap();
+        assert_eq!(c.get_pixel_at(0, 0).unwrap(), &Tuple::color(1., 1., 1.));
+        assert_eq!(c.get_pixel_at(1, 0).unwrap(), &Tuple::color(0., 0.5, 1.));
+    }
+
+    #[test]
+    fn read_binary_ppm() {
+        let mut data = b"P6\n2 1\n255\n".to_vec();
+        data.extend([255, 0, 51, 0, 255, 0]);
+        let c = Canvas::from_ppm(&data).unwrap();
+        assert!(c
+            .get_pixel_at(0, 0)
+            .unwrap()
+            .approx_eq(&Tuple::color(1., 0., 0.2)));
+        assert_eq!(c.get_pixel_at(1, 0).unwrap(), &Tuple::color(0., 1., 0.));
+    }
+
+    #[test]
+    fn ppm_round_trip() {
+        let mut c = Canvas::new(3, 2);
+        c.write_pixel_at(2, 1, Tuple::color(1., 0.2, 0.6)).unwrap();
+        let read = Canvas::from_ppm(c.to_ppm_string().as_bytes()).unwrap();
+        assert!(read
+            .get_pixel_at(2, 1)
+            .unwrap()
+            .approx_eq(&Tuple::color(1., 0.2, 0.6)));
+    }
+
+    #[test]
+    fn split_canvas_into_tiles() {
+        let mut c = Canvas::new(5, 3);
+        let tiles = c.tiles_mut(2, 2).unwrap();
+        assert_eq!(tiles.len(), 6);
+        let layout: Vec<_> = tiles
+            .into_iter()
+            .map(|t| (t.get_x(), t.get_y(), t.get_width(), t.get_height()))
+            .collect();
+        assert_eq!(
+            layout,
+            vec![
+                (0, 0, 2, 2),
+                (2, 0, 2, 2),
+                (4, 0, 1, 2),
+                (0, 2, 2, 1),
+                (2, 2, 2, 1),
+                (4, 2, 1, 1),
+            ]
+        );
+        assert!(c.tiles_mut(0, 2).is_err());
+    }
+
+    #[test]
+    fn tiles_write_into_canvas() {
+        let mut c = Canvas::new(5, 3);
+        let mut tiles = c.tiles_mut(2, 2).unwrap();
+        for tile in tiles.iter_mut() {
+            for (x, y, pixel) in tile.pixels_mut() {
+                *pixel = Tuple::color(x as f32, y as f32, 0.);
+            }
+        }
+        for y in 0..3 {
+            for x in 0..5 {
+                let expected = Tuple::color(x as f32, y as f32, 0.);
+                assert_eq!(c.get_pixel_at(x, y).unwrap(), &expected);
+            }
+        }
+    }
+
+    #[test]
+    fn tile_uses_canvas_coordinates() {
+        let mut c = Canvas::new(4, 4);
+        let mut tile = c.tiles_mut(2, 2).unwrap().into_iter().nth(3).unwrap();
+        let red = Tuple::color(1., 0., 0.);
+        tile.write_pixel_at(3, 2, red.clone()).unwrap();
+        assert_eq!(tile.get_pixel_at(3, 2).unwrap(), &red);
+        assert!(tile.write_pixel_at(1, 2, red.clone()).is_err());
+        assert!(tile.get_pixel_at(2, 4).is_err());
+        assert_eq!(c.get_pixel_at(3, 2).unwrap(), &red);
+    }
+
+    #[test]
+    fn tiles_can_be_filled_on_threads() {
+        let mut c = Canvas::new(8, 8);
+        thread::scope(|scope| {
+            for mut tile in c.tiles_mut(3, 3).unwrap() {
+                scope.spawn(move || {
+                    for (_, _, pixel) in tile.pixels_mut() {
+                        *pixel = Tuple::color(1., 1., 1.);
+                    }
+                });
+            }
+        });
+        assert!(c.as_f32_rgba().iter().all(|&v| v == 1.));
+    }
+
+    #[test]
+    fn tiles_copy_out_and_back_in() {
+        let mut c = Canvas::new(5, 3);
+        let mut tile = c.tiles_mut(2, 2).unwrap().into_iter().nth(2).unwrap();
+        for (x, y, pixel) in tile.pixels_mut() {
+            *pixel = Tuple::color(x as f32, y as f32, 0.);
+        }
+        let tile = tile.to_tile();
+        assert_eq!((tile.get_x(), tile.get_y()), (4, 0));
+        assert_eq!((tile.get_width(), tile.get_height()), (1, 2));
+        assert_eq!(tile.get_pixels().len(), 2);
+        assert_eq!(tile.get_pixel_at(4, 1).unwrap(), &Tuple::color(4., 1., 0.));
+        assert!(tile.get_pixel_at(3, 1).is_err());
+
+        let mut copy = Canvas::new(5, 3);
+        copy.write_tile(&tile).unwrap();
+        assert_eq!(copy.as_f32_rgba(), c.as_f32_rgba());
+        assert!(Canvas::new(4, 3).write_tile(&tile).is_err());
+    }
 }
@@ -0,0 +1,211 @@
+use crate::bounds::BoundingBox;
+use crate::cylinder::check_cap;
+use crate::intersection::Intersection;
+use crate::ray::Ray;
+use crate::shape::{Shape, ShapeBase};
+use crate::tuple::Tuple;
+use crate::EPSILON;
+
+// Double-napped cone around the y axis with its tip at the origin
+#[derive(PartialEq, Debug, Clone)]
+pub struct Cone {
+    base: ShapeBase,
+    pub minimum: f32,
+    pub maximum: f32,
+    pub closed: bool,
+}
+
+impl Cone {
+    pub fn new() -> Self {
+        Cone {
+            base: ShapeBase::new(),
+            minimum: f32::NEG_INFINITY,
+            maximum: f32::INFINITY,
+            closed: false,
+        }
+    }
+
+    pub fn truncated(minimum: f32, maximum: f32, closed: bool) -> Self {
+        Cone {
+            minimum,
+            maximum,
+            closed,
+            ..Cone::new()
+        }
+    }
+
+    fn intersect_caps<'a>(&'a self, ray: &Ray, xs: &mut Vec<Intersection<'a>>) {
+        if !self.closed || ray.direction.1.abs() < EPSILON {
+            return;
+        }
+        // The cone's radius at any y is |y|
+        for y in [self.minimum, self.maximum] {
+            let t = (y - ray.origin.1) / ray.direction.1;
+            if check_cap(ray, t, y.abs()) {
+                xs.push(Intersection::new(t, self));
+            }
+        }
+    }
+}
+
+impl Default for Cone {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shape for Cone {
+    fn get_base(&self) -> &ShapeBase {
+        &self.base
+    }
+
+    fn get_base_mut(&mut self) -> &mut ShapeBase {
+        &mut self.base
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        let (o, d) = (&ray.origin, &ray.direction);
+        let a = d.0.powf(2.) - d.1.powf(2.) + d.2.powf(2.);
+        let b = 2. * o.0 * d.0 - 2. * o.1 * d.1 + 2. * o.2 * d.2;
+        let c = o.0.powf(2.) - o.1.powf(2.) + o.2.powf(2.);
+
+        let mut ts = vec![];
+        if a.abs() < EPSILON {
+            // Parallel to one of the halves, so at most one hit on the other half
+            if b.abs() >= EPSILON {
+                ts.push(-c / (2. * b));
+            }
+        } else {
+            // Rounding can push grazing hits slightly below zero, so allow a little slack
+            let discriminant = b.powf(2.) - 4. * a * c;
+            if discriminant > -EPSILON {
+                let discriminant = discriminant.max(0.);
+                let t0 = (-b - discriminant.sqrt()) / (2. * a);
+                let t1 = (-b + discriminant.sqrt()) / (2. * a);
+                ts.push(t0.min(t1));
+                ts.push(t0.max(t1));
+            }
+        }
+
+        let mut xs = vec![];
+        for t in ts {
+            let y = o.1 + t * d.1;
+            if self.minimum < y && y < self.maximum {
+                xs.push(Intersection::new(t, self));
+            }
+        }
+
+        self.intersect_caps(ray, &mut xs);
+        xs
+    }
+
+    fn local_normal_at(&self, point: &Tuple) -> Tuple {
+        let dist = point.0.powf(2.) + point.2.powf(2.);
+        if dist < point.1.powf(2.) && point.1 >= self.maximum - EPSILON {
+            return Tuple::vector(0., 1., 0.);
+        }
+        if dist < point.1.powf(2.) && point.1 <= self.minimum + EPSILON {
+            return Tuple::vector(0., -1., 0.);
+        }
+
+        let y = if point.1 > 0. {
+            -dist.sqrt()
+        } else {
+            dist.sqrt()
+        };
+        Tuple::vector(point.0, y, point.2)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        let limit = self.minimum.abs().max(self.maximum.abs());
+        BoundingBox::new(
+            Tuple::point(-limit, self.minimum, -limit),
+            Tuple::point(limit, self.maximum, limit),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_cmp::approx_eq;
+
+    use super::*;
+
+    #[test]
+    fn intersect_cone_with_ray() {
+        let shape = Cone::new();
+        let cases = [
+            (Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.), 5., 5.),
+            (
+                Tuple::point(0., 0., -5.),
+                Tuple::vector(1., 1., 1.),
+                8.66025,
+                8.66025,
+            ),
+            (
+                Tuple::point(1., 1., -5.),
+                Tuple::vector(-0.5, -1., 1.),
+                4.55006,
+                49.44994,
+            ),
+        ];
+        for (origin, direction, t0, t1) in cases {
+            let r = Ray::new(origin, direction.normalize());
+            let xs = shape.local_intersect(&r);
+            assert_eq!(xs.len(), 2);
+            assert!(approx_eq!(f32, xs[0].t, t0, epsilon = 0.001));
+            assert!(approx_eq!(f32, xs[1].t, t1, epsilon = 0.001));
+        }
+    }
+
+    #[test]
+    fn intersect_cone_with_ray_parallel_to_one_half() {
+        let shape = Cone::new();
+        let direction = Tuple::vector(0., 1., 1.).normalize();
+        let r = Ray::new(Tuple::point(0., 0., -1.), direction);
+        let xs = shape.local_intersect(&r);
+        assert_eq!(xs.len(), 1);
+        assert!(approx_eq!(f32, xs[0].t, 0.35355, epsilon = 0.0001));
+    }
+
+    #[test]
+    fn intersect_cone_end_caps() {
+        let shape = Cone::truncated(-0.5, 0.5, true);
+        let cases = [
+            (Tuple::point(0., 0., -5.), Tuple::vector(0., 1., 0.), 0),
+            (Tuple::point(0., 0., -0.25), Tuple::vector(0., 1., 1.), 2),
+            (Tuple::point(0., 0., -0.25), Tuple::vector(0., 1., 0.), 4),
+        ];
+        for (origin, direction, count) in cases {
+            let r = Ray::new(origin, direction.normalize());
+            assert_eq!(shape.local_intersect(&r).len(), count);
+        }
+    }
+
+    #[test]
+    fn normal_on_cone() {
+        let shape = Cone::new();
+        let cases = [
+            (Tuple::point(0., 0., 0.), Tuple::vector(0., 0., 0.)),
+            (
+                Tuple::point(1., 1., 1.),
+                Tuple::vector(1., -2_f32.sqrt(), 1.),
+            ),
+            (Tuple::point(-1., -1., 0.), Tuple::vector(-1., 1., 0.)),
+        ];
+        for (point, normal) in cases {
+            assert!(shape.local_normal_at(&point).approx_eq(&normal));
+        }
+    }
+
+    #[test]
+    fn cone_has_bounding_box() {
+        let b = Cone::new().bounds();
+        assert_eq!(b.min.1, f32::NEG_INFINITY);
+        assert_eq!(b.max.1, f32::INFINITY);
+
+        let b = Cone::truncated(-5., 3., false).bounds();
+        assert_eq!(b.min, Tuple::point(-5., -5., -5.));
+        assert_eq!(b.max, Tuple::point(5., 3., 5.));
+    }
+}
@@ -0,0 +1,175 @@
+use crate::bounds::{check_axis, BoundingBox};
+use crate::intersection::Intersection;
+use crate::ray::Ray;
+use crate::shape::{Shape, ShapeBase};
+use crate::tuple::Tuple;
+
+// Axis-aligned cube spanning -1 to 1 on every axis
+#[derive(PartialEq, Debug, Clone)]
+pub struct Cube {
+    base: ShapeBase,
+}
+
+impl Cube {
+    pub fn new() -> Self {
+        Cube {
+            base: ShapeBase::new(),
+        }
+    }
+}
+
+impl Default for Cube {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shape for Cube {
+    fn get_base(&self) -> &ShapeBase {
+        &self.base
+    }
+
+    fn get_base_mut(&mut self) -> &mut ShapeBase {
+        &mut self.base
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        let (xtmin, xtmax) = check_axis(ray.origin.0, ray.direction.0, -1., 1.);
+        let (ytmin, ytmax) = check_axis(ray.origin.1, ray.direction.1, -1., 1.);
+        let (ztmin, ztmax) = check_axis(ray.origin.2, ray.direction.2, -1., 1.);
+
+        let tmin = xtmin.max(ytmin).max(ztmin);
+        let tmax = xtmax.min(ytmax).min(ztmax);
+
+        if tmin > tmax {
+            return vec![];
+        }
+        vec![Intersection::new(tmin, self), Intersection::new(tmax, self)]
+    }
+
+    fn local_normal_at(&self, point: &Tuple) -> Tuple {
+        // The component with the largest magnitude tells which face was hit
+        let maxc = point.0.abs().max(point.1.abs()).max(point.2.abs());
+        if maxc == point.0.abs() {
+            Tuple::vector(point.0, 0., 0.)
+        } else if maxc == point.1.abs() {
+            Tuple::vector(0., point.1, 0.)
+        } else {
+            Tuple::vector(0., 0., point.2)
+        }
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(Tuple::point(-1., -1., -1.), Tuple::point(1., 1., 1.))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ray_intersects_cube() {
+        let c = Cube::new();
+        let cases = [
+            (
+                Tuple::point(5., 0.5, 0.),
+                Tuple::vector(-1., 0., 0.),
+                4.,
+                6.,
+            ),
+            (
+                Tuple::point(-5., 0.5, 0.),
+                Tuple::vector(1., 0., 0.),
+                4.,
+                6.,
+            ),
+            (
+                Tuple::point(0.5, 5., 0.),
+                Tuple::vector(0., -1., 0.),
+                4.,
+                6.,
+            ),
+            (
+                Tuple::point(0.5, -5., 0.),
+                Tuple::vector(0., 1., 0.),
+                4.,
+                6.,
+            ),
+            (
+                Tuple::point(0.5, 0., 5.),
+                Tuple::vector(0., 0., -1.),
+                4.,
+                6.,
+            ),
+            (
+                Tuple::point(0.5, 0., -5.),
+                Tuple::vector(0., 0., 1.),
+                4.,
+                6.,
+            ),
+            (
+                Tuple::point(0., 0.5, 0.),
+                Tuple::vector(0., 0., 1.),
+                -1.,
+                1.,
+            ),
+        ];
+        for (origin, direction, t1, t2) in cases {
+            let xs = c.local_intersect(&Ray::new(origin, direction));
+            assert_eq!(xs.len(), 2);
+            assert_eq!(xs[0].t, t1);
+            assert_eq!(xs[1].t, t2);
+        }
+    }
+
+    #[test]
+    fn ray_misses_cube() {
+        let c = Cube::new();
+        let cases = [
+            (
+                Tuple::point(-2., 0., 0.),
+                Tuple::vector(0.2673, 0.5345, 0.8018),
+            ),
+            (
+                Tuple::point(0., -2., 0.),
+                Tuple::vector(0.8018, 0.2673, 0.5345),
+            ),
+            (
+                Tuple::point(0., 0., -2.),
+                Tuple::vector(0.5345, 0.8018, 0.2673),
+            ),
+            (Tuple::point(2., 0., 2.), Tuple::vector(0., 0., -1.)),
+            (Tuple::point(0., 2., 2.), Tuple::vector(0., -1., 0.)),
+            (Tuple::point(2., 2., 0.), Tuple::vector(-1., 0., 0.)),
+        ];
+        for (origin, direction) in cases {
+            assert!(c.local_intersect(&Ray::new(origin, direction)).is_empty());
+        }
+    }
+
+    #[test]
+    fn normal_on_surface_of_cube() {
+        let c = Cube::new();
+        let cases = [
+            (Tuple::point(1., 0.5, -0.8), Tuple::vector(1., 0., 0.)),
+            (Tuple::point(-1., -0.2, 0.9), Tuple::vector(-1., 0., 0.)),
+            (Tuple::point(-0.4, 1., -0.1), Tuple::vector(0., 1., 0.)),
+            (Tuple::point(0.3, -1., -0.7), Tuple::vector(0., -1., 0.)),
+            (Tuple::point(-0.6, 0.3, 1.), Tuple::vector(0., 0., 1.)),
+            (Tuple::point(0.4, 0.4, -1.), Tuple::vector(0., 0., -1.)),
+            (Tuple::point(1., 1., 1.), Tuple::vector(1., 0., 0.)),
+            (Tuple::point(-1., -1., -1.), Tuple::vector(-1., 0., 0.)),
+        ];
+        for (point, normal) in cases {
+            assert_eq!(c.local_normal_at(&point), normal);
+        }
+    }
+
+    #[test]
+    fn cube_has_bounding_box() {
+        let b = Cube::new().bounds();
+        assert_eq!(b.min, Tuple::point(-1., -1., -1.));
+        assert_eq!(b.max, Tuple::point(1., 1., 1.));
+    }
+}
@@ -0,0 +1,240 @@
+use crate::bounds::BoundingBox;
+use crate::intersection::Intersection;
+use crate::ray::Ray;
+use crate::shape::{Shape, ShapeBase};
+use crate::tuple::Tuple;
+use crate::EPSILON;
+
+// Cylinder of radius 1 around the y axis, optionally truncated and capped
+#[derive(PartialEq, Debug, Clone)]
+pub struct Cylinder {
+    base: ShapeBase,
+    pub minimum: f32,
+    pub maximum: f32,
+    pub closed: bool,
+}
+
+impl Cylinder {
+    pub fn new() -> Self {
+        Cylinder {
+            base: ShapeBase::new(),
+            minimum: f32::NEG_INFINITY,
+            maximum: f32::INFINITY,
+            closed: false,
+        }
+    }
+
+    pub fn truncated(minimum: f32, maximum: f32, closed: bool) -> Self {
+        Cylinder {
+            minimum,
+            maximum,
+            closed,
+            ..Cylinder::new()
+        }
+    }
+
+    fn intersect_caps<'a>(&'a self, ray: &Ray, xs: &mut Vec<Intersection<'a>>) {
+        if !self.closed || ray.direction.1.abs() < EPSILON {
+            return;
+        }
+        for y in [self.minimum, self.maximum] {
+            let t = (y - ray.origin.1) / ray.direction.1;
+            if check_cap(ray, t, 1.) {
+                xs.push(Intersection::new(t, self));
+            }
+        }
+    }
+}
+
+impl Default for Cylinder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Whether the ray at t lies within the given radius of the y axis
+pub(crate) fn check_cap(ray: &Ray, t: f32, radius: f32) -> bool {
+    let x = ray.origin.0 + t * ray.direction.0;
+    let z = ray.origin.2 + t * ray.direction.2;
+    x.powf(2.) + z.powf(2.) <= radius.powf(2.) + EPSILON
+}
+
+impl Shape for Cylinder {
+    fn get_base(&self) -> &ShapeBase {
+        &self.base
+    }
+
+    fn get_base_mut(&mut self) -> &mut ShapeBase {
+        &mut self.base
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        let mut xs = vec![];
+
+        // Rays parallel to the y axis can only hit the caps
+        let a = ray.direction.0.powf(2.) + ray.direction.2.powf(2.);
+        if a.abs() >= EPSILON {
+            let b = 2. * ray.origin.0 * ray.direction.0 + 2. * ray.origin.2 * ray.direction.2;
+            let c = ray.origin.0.powf(2.) + ray.origin.2.powf(2.) - 1.;
+            let discriminant = b.powf(2.) - 4. * a * c;
+            if discriminant < 0. {
+                return xs;
+            }
+
+            let t0 = (-b - discriminant.sqrt()) / (2. * a);
+            let t1 = (-b + discriminant.sqrt()) / (2. * a);
+            for t in [t0.min(t1), t0.max(t1)] {
+                let y = ray.origin.1 + t * ray.direction.1;
+                if self.minimum < y && y < self.maximum {
+                    xs.push(Intersection::new(t, self));
+                }
+            }
+        }
+
+        self.intersect_caps(ray, &mut xs);
+        xs
+    }
+
+    fn local_normal_at(&self, point: &Tuple) -> Tuple {
+        let dist = point.0.powf(2.) + point.2.powf(2.);
+        if dist < 1. && point.1 >= self.maximum - EPSILON {
+            Tuple::vector(0., 1., 0.)
+        } else if dist < 1. && point.1 <= self.minimum + EPSILON {
+            Tuple::vector(0., -1., 0.)
+        } else {
+            Tuple::vector(point.0, 0., point.2)
+        }
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(
+            Tuple::point(-1., self.minimum, -1.),
+            Tuple::point(1., self.maximum, 1.),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_cmp::approx_eq;
+
+    use super::*;
+
+    #[test]
+    fn ray_misses_cylinder() {
+        let cyl = Cylinder::new();
+        let cases = [
+            (Tuple::point(1., 0., 0.), Tuple::vector(0., 1., 0.)),
+            (Tuple::point(0., 0., 0.), Tuple::vector(0., 1., 0.)),
+            (Tuple::point(0., 0., -5.), Tuple::vector(1., 1., 1.)),
+        ];
+        for (origin, direction) in cases {
+            let r = Ray::new(origin, direction.normalize());
+            assert!(cyl.local_intersect(&r).is_empty());
+        }
+    }
+
+    #[test]
+    fn ray_strikes_cylinder() {
+        let cyl = Cylinder::new();
+        let cases = [
+            (Tuple::point(1., 0., -5.), Tuple::vector(0., 0., 1.), 5., 5.),
+            (Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.), 4., 6.),
+            (
+                Tuple::point(0.5, 0., -5.),
+                Tuple::vector(0.1, 1., 1.),
+                6.80798,
+                7.08872,
+            ),
+        ];
+        for (origin, direction, t0, t1) in cases {
+            let r = Ray::new(origin, direction.normalize());
+            let xs = cyl.local_intersect(&r);
+            assert_eq!(xs.len(), 2);
+            assert!(approx_eq!(f32, xs[0].t, t0, epsilon = 0.0001));
+            assert!(approx_eq!(f32, xs[1].t, t1, epsilon = 0.0001));
+        }
+    }
+
+    #[test]
+    fn normal_on_cylinder() {
+        let cyl = Cylinder::new();
+        let cases = [
+            (Tuple::point(1., 0., 0.), Tuple::vector(1., 0., 0.)),
+            (Tuple::point(0., 5., -1.), Tuple::vector(0., 0., -1.)),
+            (Tuple::point(0., -2., 1.), Tuple::vector(0., 0., 1.)),
+            (Tuple::point(-1., 1., 0.), Tuple::vector(-1., 0., 0.)),
+        ];
+        for (point, normal) in cases {
+            assert_eq!(cyl.local_normal_at(&point), normal);
+        }
+    }
+
+    #[test]
+    fn default_cylinder_is_infinite_and_open() {
+        let cyl = Cylinder::new();
+        assert_eq!(cyl.minimum, f32::NEG_INFINITY);
+        assert_eq!(cyl.maximum, f32::INFINITY);
+        assert!(!cyl.closed);
+    }
+
+    #[test]
+    fn intersect_constrained_cylinder() {
+        let cyl = Cylinder::truncated(1., 2., false);
+        let cases = [
+            (Tuple::point(0., 1.5, 0.), Tuple::vector(0.1, 1., 0.), 0),
+            (Tuple::point(0., 3., -5.), Tuple::vector(0., 0., 1.), 0),
+            (Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.), 0),
+            (Tuple::point(0., 2., -5.), Tuple::vector(0., 0., 1.), 0),
+            (Tuple::point(0., 1., -5.), Tuple::vector(0., 0., 1.), 0),
+            (Tuple::point(0., 1.5, -2.), Tuple::vector(0., 0., 1.), 2),
+        ];
+        for (origin, direction, count) in cases {
+            let r = Ray::new(origin, direction.normalize());
+            assert_eq!(cyl.local_intersect(&r).len(), count);
+        }
+    }
+
+    #[test]
+    fn intersect_caps_of_closed_cylinder() {
+        let cyl = Cylinder::truncated(1., 2., true);
+        let cases = [
+            (Tuple::point(0., 3., 0.), Tuple::vector(0., -1., 0.), 2),
+            (Tuple::point(0., 3., -2.), Tuple::vector(0., -1., 2.), 2),
+            (Tuple::point(0., 4., -2.), Tuple::vector(0., -1., 1.), 2),
+            (Tuple::point(0., 0., -2.), Tuple::vector(0., 1., 2.), 2),
+            (Tuple::point(0., -1., -2.), Tuple::vector(0., 1., 1.), 2),
+        ];
+        for (origin, direction, count) in cases {
+            let r = Ray::new(origin, direction.normalize());
+            assert_eq!(cyl.local_intersect(&r).len(), count);
+        }
+    }
+
+    #[test]
+    fn normal_on_cylinder_end_caps() {
+        let cyl = Cylinder::truncated(1., 2., true);
+        let cases = [
+            (Tuple::point(0., 1., 0.), Tuple::vector(0., -1., 0.)),
+            (Tuple::point(0.5, 1., 0.), Tuple::vector(0., -1., 0.)),
+            (Tuple::point(0., 1., 0.5), Tuple::vector(0., -1., 0.)),
+            (Tuple::point(0., 2., 0.), Tuple::vector(0., 1., 0.)),
+            (Tuple::point(0.5, 2., 0.), Tuple::vector(0., 1., 0.)),
+            (Tuple::point(0., 2., 0.5), Tuple::vector(0., 1., 0.)),
+        ];
+        for (point, normal) in cases {
+            assert_eq!(cyl.local_normal_at(&point), normal);
+        }
+    }
+
+    #[test]
+    fn cylinder_has_bounding_box() {
+        let b = Cylinder::new().bounds();
+        assert_eq!(b.min, Tuple::point(-1., f32::NEG_INFINITY, -1.));
+        assert_eq!(b.max, Tuple::point(1., f32::INFINITY, 1.));
+
+        let b = Cylinder::truncated(-5., 3., false).bounds();
+        assert_eq!(b.min, Tuple::point(-1., -5., -1.));
+        assert_eq!(b.max, Tuple::point(1., 3., 1.));
+    }
+}
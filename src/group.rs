@@ -0,0 +1,395 @@
+use std::sync::OnceLock;
+
+use crate::bounds::BoundingBox;
+use crate::intersection::Intersection;
+use crate::matrix::Matrix4;
+use crate::ray::Ray;
+use crate::shape::{Shape, ShapeBase};
+use crate::stats::RayStats;
+use crate::tuple::Tuple;
+
+type Children = Vec<Box<dyn Shape>>;
+
+// Collection of shapes that are transformed together, children are intersected
+// in the group's object space
+#[derive(Debug)]
+pub struct Group {
+    base: ShapeBase,
+    children: Children,
+    // Computed on first use and reset whenever the children may have changed
+    bounds: OnceLock<BoundingBox>,
+}
+
+impl Group {
+    pub fn new() -> Self {
+        Group {
+            base: ShapeBase::new(),
+            children: vec![],
+            bounds: OnceLock::new(),
+        }
+    }
+
+    pub fn add_child(&mut self, mut child: Box<dyn Shape>) {
+        child.set_parent_inverse(*self.base.get_world_inverse_transform());
+        self.children.push(child);
+        self.bounds = OnceLock::new();
+    }
+
+    pub fn len(&self) -> usize {
+        self.children.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    // Sorts children into the halves of the group's bounds, children that
+    // straddle both halves stay in the group
+    fn partition_children(&mut self) -> (Children, Children) {
+        let (left_bounds, right_bounds) = self.bounds().split();
+        let mut left = vec![];
+        let mut right = vec![];
+        let mut remaining = vec![];
+        for child in self.children.drain(..) {
+            let child_bounds = child.parent_space_bounds();
+            if left_bounds.contains_box(&child_bounds) {
+                left.push(child);
+            } else if right_bounds.contains_box(&child_bounds) {
+                right.push(child);
+            } else {
+                remaining.push(child);
+            }
+        }
+        self.children = remaining;
+        self.bounds = OnceLock::new();
+        (left, right)
+    }
+
+    fn make_subgroup(&mut self, children: Children) {
+        let mut subgroup = Group::new();
+        subgroup.set_parent_inverse(*self.base.get_world_inverse_transform());
+        for child in children {
+            subgroup.add_child(child);
+        }
+        self.add_child(Box::new(subgroup));
+    }
+
+    fn update_children(&mut self) {
+        let world_inverse = self.base.get_world_inverse_transform();
+        for child in &mut self.children {
+            child.set_parent_inverse(*world_inverse);
+        }
+    }
+}
+
+impl Default for Group {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shape for Group {
+    fn get_base(&self) -> &ShapeBase {
+        &self.base
+    }
+
+    fn get_base_mut(&mut self) -> &mut ShapeBase {
+        &mut self.base
+    }
+
+    fn get_children(&self) -> &[Box<dyn Shape>] {
+        &self.children
+    }
+
+    fn get_children_mut(&mut self) -> &mut [Box<dyn Shape>] {
+        self.bounds = OnceLock::new();
+        &mut self.children
+    }
+
+    fn set_transform(&mut self, transform: Matrix4) -> Result<(), String> {
+        self.base.set_transform(transform)?;
+        self.update_children();
+        Ok(())
+    }
+
+    fn set_parent_inverse(&mut self, parent_inverse: Matrix4) {
+        self.base.set_parent_inverse(parent_inverse);
+        self.update_children();
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        RayStats::record(|stats| stats.bvh_node_visits += 1);
+        if !self.bounds().intersects(ray) {
+            return vec![];
+        }
+        self.children
+            .iter()
+            .flat_map(|child| child.intersect(ray))
+            .collect()
+    }
+
+    fn local_normal_at(&self, _point: &Tuple) -> Tuple {
+        unreachable!("Groups have no surface, normals come from their children")
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        self.bounds
+            .get_or_init(|| {
+                let mut bounds = BoundingBox::empty();
+                for child in &self.children {
+                    bounds.merge(&child.parent_space_bounds());
+                }
+                bounds
+            })
+            .clone()
+    }
+
+    fn divide(&mut self, threshold: usize) {
+        if threshold <= self.children.len() {
+            let (left, right) = self.partition_children();
+            if !left.is_empty() {
+                self.make_subgroup(left);
+            }
+            if !right.is_empty() {
+                self.make_subgroup(right);
+            }
+        }
+        for child in self.get_children_mut() {
+            child.divide(threshold);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use super::*;
+    use crate::cylinder::Cylinder;
+    use crate::intersection::Intersections;
+    use crate::sphere::Sphere;
+
+    #[test]
+    fn create_group() {
+        let g = Group::new();
+        assert_eq!(g.get_transform(), &Matrix4::identity());
+        assert!(g.is_empty());
+    }
+
+    #[test]
+    fn add_child_to_group() {
+        let mut g = Group::new();
+        g.add_child(Box::new(Sphere::new()));
+        assert_eq!(g.len(), 1);
+        assert_eq!(g.get_children().len(), 1);
+    }
+
+    #[test]
+    fn intersect_ray_with_empty_group() {
+        let g = Group::new();
+        let r = Ray::new(Tuple::point(0., 0., 0.), Tuple::vector(0., 0., 1.));
+        assert!(g.local_intersect(&r).is_empty());
+    }
+
+    #[test]
+    fn intersect_ray_with_nonempty_group() {
+        let mut g = Group::new();
+        let s1 = Sphere::new();
+        let mut s2 = Sphere::new();
+        s2.set_transform(Matrix4::translation(0., 0., -3.)).unwrap();
+        let mut s3 = Sphere::new();
+        s3.set_transform(Matrix4::translation(5., 0., 0.)).unwrap();
+        g.add_child(Box::new(s1));
+        g.add_child(Box::new(s2));
+        g.add_child(Box::new(s3));
+
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let xs = Intersections::new(g.local_intersect(&r));
+        let children = g.get_children();
+        assert_eq!(xs.len(), 4);
+        assert!(std::ptr::addr_eq(xs[0].object, children[1].as_ref()));
+        assert!(std::ptr::addr_eq(xs[1].object, children[1].as_ref()));
+        assert!(std::ptr::addr_eq(xs[2].object, children[0].as_ref()));
+        assert!(std::ptr::addr_eq(xs[3].object, children[0].as_ref()));
+    }
+
+    #[test]
+    fn intersect_transformed_group() {
+        let mut g = Group::new();
+        g.set_transform(Matrix4::scaling(2., 2., 2.)).unwrap();
+        let mut s = Sphere::new();
+        s.set_transform(Matrix4::translation(5., 0., 0.)).unwrap();
+        g.add_child(Box::new(s));
+        let r = Ray::new(Tuple::point(10., 0., -10.), Tuple::vector(0., 0., 1.));
+        assert_eq!(g.intersect(&r).len(), 2);
+    }
+
+    fn sphere_at(x: f32, y: f32, z: f32) -> Box<dyn Shape> {
+        let mut s = Sphere::new();
+        s.set_transform(Matrix4::translation(x, y, z)).unwrap();
+        Box::new(s)
+    }
+
+    #[test]
+    fn group_bounds_contain_children() {
+        let mut s = Sphere::new();
+        s.set_transform(Matrix4::translation(2., 5., -3.) * Matrix4::scaling(2., 2., 2.))
+            .unwrap();
+        let mut c = Cylinder::truncated(-2., 2., false);
+        c.set_transform(Matrix4::translation(-4., -1., 4.) * Matrix4::scaling(0.5, 1., 0.5))
+            .unwrap();
+        let mut g = Group::new();
+        g.add_child(Box::new(s));
+        g.add_child(Box::new(c));
+        let b = g.bounds();
+        assert_eq!(b.min, Tuple::point(-4.5, -3., -5.));
+        assert_eq!(b.max, Tuple::point(4., 7., 4.5));
+    }
+
+    #[test]
+    fn group_bounds_follow_child_changes() {
+        let mut g = Group::new();
+        g.add_child(sphere_at(0., 0., 0.));
+        assert_eq!(g.bounds().max, Tuple::point(1., 1., 1.));
+        g.get_children_mut()[0]
+            .set_transform(Matrix4::translation(5., 0., 0.))
+            .unwrap();
+        assert_eq!(g.bounds().max, Tuple::point(6., 1., 1.));
+    }
+
+    #[test]
+    fn ray_missing_bounds_skips_children() {
+        let mut g = Group::new();
+        g.add_child(sphere_at(0., 0., 0.));
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 1., 0.));
+        assert!(g.intersect(&r).is_empty());
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        assert_eq!(g.intersect(&r).len(), 2);
+    }
+
+    #[test]
+    fn partition_children_of_group() {
+        let mut g = Group::new();
+        g.add_child(sphere_at(-2., 0., 0.));
+        g.add_child(sphere_at(2., 0., 0.));
+        g.add_child(Box::new(Sphere::new()));
+        let (left, right) = g.partition_children();
+        assert_eq!(g.len(), 1);
+        assert_eq!(left.len(), 1);
+        assert_eq!(right.len(), 1);
+        assert_eq!(left[0].get_transform(), &Matrix4::translation(-2., 0., 0.));
+        assert_eq!(right[0].get_transform(), &Matrix4::translation(2., 0., 0.));
+    }
+
+    #[test]
+    fn divide_partitions_children() {
+        let mut g = Group::new();
+        g.add_child(sphere_at(-2., -2., 0.));
+        g.add_child(sphere_at(-2., 2., 0.));
+        let mut s3 = Sphere::new();
+        s3.set_transform(Matrix4::scaling(4., 4., 4.)).unwrap();
+        g.add_child(Box::new(s3));
+        g.divide(1);
+
+        let children = g.get_children();
+        assert_eq!(children.len(), 2);
+        assert_eq!(children[0].get_transform(), &Matrix4::scaling(4., 4., 4.));
+        let subgroup = children[1].get_children();
+        assert_eq!(subgroup.len(), 2);
+        assert_eq!(subgroup[0].get_children().len(), 1);
+        assert_eq!(subgroup[1].get_children().len(), 1);
+    }
+
+    #[test]
+    fn divide_with_too_few_children() {
+        let mut g = Group::new();
+        g.add_child(sphere_at(-2., 0., 0.));
+        g.add_child(sphere_at(2., 1., 0.));
+        g.add_child(sphere_at(2., -1., 0.));
+        g.add_child(Box::new(Sphere::new()));
+        g.divide(3);
+
+        let children = g.get_children();
+        assert_eq!(children.len(), 3);
+        assert_eq!(children[0].get_transform(), &Matrix4::identity());
+        assert_eq!(children[1].get_children().len(), 1);
+        // The right half still had two children, below the threshold
+        assert_eq!(children[2].get_children().len(), 2);
+    }
+
+    #[test]
+    fn divide_keeps_intersections_and_normals() {
+        let mut g = Group::new();
+        g.set_transform(Matrix4::translation(0., 0., 3.)).unwrap();
+        for i in 0..8 {
+            g.add_child(sphere_at(i as f32 * 3., 0., 0.));
+        }
+        let r = Ray::new(Tuple::point(9., 0., -5.), Tuple::vector(0., 0., 1.));
+        let before: Vec<f32> = Intersections::new(g.intersect(&r))
+            .iter()
+            .map(|i| i.t)
+            .collect();
+        g.divide(2);
+        let xs = Intersections::new(g.intersect(&r));
+        let after: Vec<f32> = xs.iter().map(|i| i.t).collect();
+        assert_eq!(before, vec![7., 9.]);
+        assert_eq!(after, before);
+        let n = xs[0].object.normal_at(&r.position(xs[0].t));
+        assert!(n.approx_eq(&Tuple::vector(0., 0., -1.)));
+    }
+
+    #[test]
+    fn intersect_counts_bvh_node_visits() {
+        let mut g = Group::new();
+        for i in 0..4 {
+            g.add_child(sphere_at(i as f32 * 3., 0., 0.));
+        }
+        g.divide(2);
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        RayStats::take();
+        g.intersect(&r);
+        // The root, both halves, and the quarter containing the first sphere
+        assert_eq!(RayStats::take().bvh_node_visits, 5);
+    }
+
+    fn nested_sphere() -> Group {
+        let mut g1 = Group::new();
+        g1.set_transform(Matrix4::rotation_y(FRAC_PI_2)).unwrap();
+        let mut g2 = Group::new();
+        g2.set_transform(Matrix4::scaling(1., 2., 3.)).unwrap();
+        let mut s = Sphere::new();
+        s.set_transform(Matrix4::translation(5., 0., 0.)).unwrap();
+        g2.add_child(Box::new(s));
+        g1.add_child(Box::new(g2));
+        g1
+    }
+
+    fn leaf(g: &Group) -> &dyn Shape {
+        g.get_children()[0].get_children()[0].as_ref()
+    }
+
+    #[test]
+    fn normal_on_child_object() {
+        let g = nested_sphere();
+        let n = leaf(&g).normal_at(&Tuple::point(1.7321, 1.1547, -5.5774));
+        assert!(n.approx_eq(&Tuple::vector(0.2857, 0.42854, -0.85716)));
+    }
+
+    #[test]
+    fn group_transform_applies_to_existing_children() {
+        let mut g1 = Group::new();
+        let mut g2 = Group::new();
+        g2.set_transform(Matrix4::scaling(1., 2., 3.)).unwrap();
+        let mut s = Sphere::new();
+        s.set_transform(Matrix4::translation(5., 0., 0.)).unwrap();
+        g2.add_child(Box::new(s));
+        g1.add_child(Box::new(g2));
+        g1.set_transform(Matrix4::rotation_y(FRAC_PI_2)).unwrap();
+
+        let expected = nested_sphere();
+        assert_eq!(
+            leaf(&g1).get_base().get_world_inverse_transform(),
+            leaf(&expected).get_base().get_world_inverse_transform()
+        );
+    }
+}
@@ -0,0 +1,485 @@
+use std::ops;
+
+use crate::medium::MediumStack;
+use crate::ray::Ray;
+use crate::shape::Shape;
+use crate::tuple::Tuple;
+use crate::EPSILON;
+
+#[derive(Debug, Clone)]
+pub struct Intersection<'a> {
+    pub t: f32,
+    pub object: &'a dyn Shape,
+    // Where on the surface the hit lies, for shapes that parametrize it (triangles)
+    pub uv: Option<(f32, f32)>,
+    // How far the copy that was hit is moved from the original in world space,
+    // for shapes repeated without being duplicated
+    pub offset: Option<Tuple>,
+}
+
+impl<'a> Intersection<'a> {
+    pub fn new(t: f32, object: &'a dyn Shape) -> Self {
+        Intersection {
+            t,
+            object,
+            uv: None,
+            offset: None,
+        }
+    }
+
+    pub fn with_uv(t: f32, object: &'a dyn Shape, u: f32, v: f32) -> Self {
+        Intersection {
+            t,
+            object,
+            uv: Some((u, v)),
+            offset: None,
+        }
+    }
+
+    // Reconstructs the media in front of the hit from the intersections before
+    // it, which only works when xs holds every surface along the whole ray
+    pub fn prepare_computations(&self, ray: &Ray, xs: &Intersections<'a>) -> Computations<'a> {
+        let mut media = MediumStack::new();
+        for i in xs.iter() {
+            if i == self {
+                break;
+            }
+            media.cross(i.object);
+        }
+        self.prepare_computations_in(ray, media)
+    }
+
+    // For rays that know which objects they're travelling through
+    pub fn prepare_computations_in(&self, ray: &Ray, media: MediumStack<'a>) -> Computations<'a> {
+        let point = ray.position(self.t);
+        let eyev = -ray.direction.clone();
+        let mut normalv = match &self.offset {
+            Some(offset) => self
+                .object
+                .normal_at_hit(&(point.clone() - offset.clone()), self),
+            None => self.object.normal_at_hit(&point, self),
+        };
+
+        let inside = normalv.dot(&eyev) < 0.;
+        if inside {
+            normalv = -normalv;
+        }
+
+        let reflectv = ray.direction.reflect(&normalv);
+
+        // Nudge the point off the surface so shadow rays don't hit the surface itself
+        let over_point = point.clone() + normalv.clone() * EPSILON;
+        let under_point = point.clone() - normalv.clone() * EPSILON;
+        let pattern_point = match &self.offset {
+            Some(offset) => over_point.clone() - offset.clone(),
+            None => over_point.clone(),
+        };
+
+        let n1 = media.refractive_index();
+        let n2 = media.crossing(self.object).refractive_index();
+
+        Computations {
+            t: self.t,
+            object: self.object,
+            point,
+            over_point,
+            under_point,
+            pattern_point,
+            viewpoint: ray.viewpoint.clone(),
+            eyev,
+            normalv,
+            reflectv,
+            inside,
+            n1,
+            n2,
+            media,
+        }
+    }
+}
+
+// Intersections are the same if they hit the very same object at the same t
+impl<'a> PartialEq for Intersection<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        self.t == other.t && std::ptr::addr_eq(self.object, other.object)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Computations<'a> {
+    pub t: f32,
+    pub object: &'a dyn Shape,
+    pub point: Tuple,
+    pub over_point: Tuple,
+    pub under_point: Tuple,
+    // Over point moved back onto the original for copies made by a Repeat,
+    // where patterns are looked up
+    pub pattern_point: Tuple,
+    // Viewpoint of the incoming ray, handed on to the rays leaving the hit
+    pub viewpoint: Option<Tuple>,
+    pub eyev: Tuple,
+    pub normalv: Tuple,
+    pub reflectv: Tuple,
+    pub inside: bool,
+    pub n1: f32,
+    pub n2: f32,
+    // What the incoming ray travelled through, the reflected ray stays in it
+    pub media: MediumStack<'a>,
+}
+
+impl<'a> Computations<'a> {
+    // Schlick's approximation of the Fresnel reflectance
+    pub fn schlick(&self) -> f32 {
+        let mut cos = self.eyev.dot(&self.normalv);
+
+        if self.n1 > self.n2 {
+            let n = self.n1 / self.n2;
+            let sin2_t = n.powf(2.) * (1. - cos.powf(2.));
+            if sin2_t > 1. {
+                return 1.;
+            }
+            cos = (1. - sin2_t).sqrt();
+        }
+
+        let r0 = ((self.n1 - self.n2) / (self.n1 + self.n2)).powf(2.);
+        r0 + (1. - r0) * (1. - cos).powf(5.)
+    }
+}
+
+// Intersections are kept sorted by t so the hit is a binary search away
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct Intersections<'a> {
+    items: Vec<Intersection<'a>>,
+}
+
+impl<'a> Intersections<'a> {
+    pub fn new(mut items: Vec<Intersection<'a>>) -> Self {
+        items.sort_by(|a, b| a.t.total_cmp(&b.t));
+        Intersections { items }
+    }
+
+    pub fn push(&mut self, intersection: Intersection<'a>) {
+        let index = self.items.partition_point(|i| i.t <= intersection.t);
+        self.items.insert(index, intersection);
+    }
+
+    pub fn extend(&mut self, intersections: Vec<Intersection<'a>>) {
+        // Appending sorted runs and re-sorting is cheap for the stable merge sort
+        self.items.extend(intersections);
+        self.items.sort_by(|a, b| a.t.total_cmp(&b.t));
+    }
+
+    pub fn hit(&self) -> Option<&Intersection<'a>> {
+        let index = self.items.partition_point(|i| i.t < 0.);
+        self.items.get(index)
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Intersection<'a>> {
+        self.items.iter()
+    }
+}
+
+impl<'a> ops::Index<usize> for Intersections<'a> {
+    type Output = Intersection<'a>;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.items[index]
+    }
+}
+
+impl<'a> From<Vec<Intersection<'a>>> for Intersections<'a> {
+    fn from(items: Vec<Intersection<'a>>) -> Self {
+        Intersections::new(items)
+    }
+}
+
+impl<'a> IntoIterator for Intersections<'a> {
+    type Item = Intersection<'a>;
+    type IntoIter = std::vec::IntoIter<Intersection<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matrix::Matrix4;
+    use crate::plane::Plane;
+    use crate::sphere::Sphere;
+
+    #[test]
+    fn intersection_has_t_and_object() {
+        let s = Sphere::new();
+        let i = Intersection::new(3.5, &s);
+        assert_eq!(i.t, 3.5);
+        assert!(std::ptr::addr_eq(i.object, &s));
+    }
+
+    #[test]
+    fn intersection_can_carry_uv() {
+        let s = Sphere::new();
+        assert_eq!(Intersection::new(3.5, &s).uv, None);
+        let i = Intersection::with_uv(3.5, &s, 0.2, 0.4);
+        assert_eq!(i.uv, Some((0.2, 0.4)));
+    }
+
+    #[test]
+    fn aggregate_intersections() {
+        let s = Sphere::new();
+        let xs = Intersections::new(vec![Intersection::new(2., &s), Intersection::new(1., &s)]);
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].t, 1.);
+        assert_eq!(xs[1].t, 2.);
+    }
+
+    #[test]
+    fn hit_when_all_intersections_positive() {
+        let s = Sphere::new();
+        let i1 = Intersection::new(1., &s);
+        let i2 = Intersection::new(2., &s);
+        let xs = Intersections::new(vec![i2, i1.clone()]);
+        assert_eq!(xs.hit(), Some(&i1));
+    }
+
+    #[test]
+    fn hit_when_some_intersections_negative() {
+        let s = Sphere::new();
+        let i1 = Intersection::new(-1., &s);
+        let i2 = Intersection::new(1., &s);
+        let xs = Intersections::new(vec![i2.clone(), i1]);
+        assert_eq!(xs.hit(), Some(&i2));
+    }
+
+    #[test]
+    fn hit_when_all_intersections_negative() {
+        let s = Sphere::new();
+        let xs = Intersections::new(vec![Intersection::new(-2., &s), Intersection::new(-1., &s)]);
+        assert_eq!(xs.hit(), None);
+    }
+
+    #[test]
+    fn hit_is_lowest_nonnegative_intersection() {
+        let s = Sphere::new();
+        let i4 = Intersection::new(2., &s);
+        let xs = Intersections::new(vec![
+            Intersection::new(5., &s),
+            Intersection::new(7., &s),
+            Intersection::new(-3., &s),
+            i4.clone(),
+        ]);
+        assert_eq!(xs.hit(), Some(&i4));
+    }
+
+    #[test]
+    fn push_keeps_intersections_sorted() {
+        let s = Sphere::new();
+        let mut xs = Intersections::default();
+        xs.push(Intersection::new(3., &s));
+        xs.push(Intersection::new(-1., &s));
+        xs.push(Intersection::new(2., &s));
+        let ts: Vec<f32> = xs.iter().map(|i| i.t).collect();
+        assert_eq!(ts, vec![-1., 2., 3.]);
+    }
+
+    #[test]
+    fn hit_across_multiple_objects() {
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let near = Sphere::new();
+        let mut far = Sphere::new();
+        far.set_transform(Matrix4::translation(0., 0., 3.)).unwrap();
+
+        let mut xs = Intersections::default();
+        xs.extend(far.intersect(&r));
+        xs.extend(near.intersect(&r));
+
+        let ts: Vec<f32> = xs.iter().map(|i| i.t).collect();
+        assert_eq!(ts, vec![4., 6., 7., 9.]);
+        let hit = xs.hit().unwrap();
+        assert_eq!(hit.t, 4.);
+        assert!(std::ptr::addr_eq(hit.object, &near));
+    }
+
+    #[test]
+    fn precompute_state_of_intersection() {
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let shape = Sphere::new();
+        let i = Intersection::new(4., &shape);
+        let comps = i.prepare_computations(&r, &Intersections::new(vec![i.clone()]));
+        assert_eq!(comps.t, i.t);
+        assert!(std::ptr::addr_eq(comps.object, &shape));
+        assert_eq!(comps.point, Tuple::point(0., 0., -1.));
+        assert_eq!(comps.eyev, Tuple::vector(0., 0., -1.));
+        assert_eq!(comps.normalv, Tuple::vector(0., 0., -1.));
+    }
+
+    #[test]
+    fn hit_occurs_on_outside() {
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let shape = Sphere::new();
+        let comps =
+            Intersection::new(4., &shape).prepare_computations(&r, &Intersections::default());
+        assert!(!comps.inside);
+    }
+
+    #[test]
+    fn hit_occurs_on_inside() {
+        let r = Ray::new(Tuple::point(0., 0., 0.), Tuple::vector(0., 0., 1.));
+        let shape = Sphere::new();
+        let comps =
+            Intersection::new(1., &shape).prepare_computations(&r, &Intersections::default());
+        assert_eq!(comps.point, Tuple::point(0., 0., 1.));
+        assert_eq!(comps.eyev, Tuple::vector(0., 0., -1.));
+        assert!(comps.inside);
+        assert_eq!(comps.normalv, Tuple::vector(0., 0., -1.));
+    }
+
+    #[test]
+    fn hit_offsets_point() {
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let mut shape = Sphere::new();
+        shape
+            .set_transform(Matrix4::translation(0., 0., 1.))
+            .unwrap();
+        let comps =
+            Intersection::new(5., &shape).prepare_computations(&r, &Intersections::default());
+        assert!(comps.over_point.2 < -EPSILON / 2.);
+        assert!(comps.point.2 > comps.over_point.2);
+    }
+
+    #[test]
+    fn precompute_reflection_vector() {
+        let shape = Sphere::new();
+        let v = 2_f32.sqrt() / 2.;
+        let r = Ray::new(Tuple::point(0., v, -5.), Tuple::vector(0., 0., 1.));
+        let i = Intersection::new(5. - v, &shape);
+        let comps = i.prepare_computations(&r, &Intersections::new(vec![i.clone()]));
+        assert!(comps.reflectv.approx_eq(&Tuple::vector(0., 1., 0.)));
+    }
+
+    #[test]
+    fn find_n1_and_n2_at_various_intersections() {
+        let mut a = Sphere::glass();
+        a.set_transform(Matrix4::scaling(2., 2., 2.)).unwrap();
+        a.get_material_mut().refractive_index = 1.5;
+        let mut b = Sphere::glass();
+        b.set_transform(Matrix4::translation(0., 0., -0.25))
+            .unwrap();
+        b.get_material_mut().refractive_index = 2.;
+        let mut c = Sphere::glass();
+        c.set_transform(Matrix4::translation(0., 0., 0.25)).unwrap();
+        c.get_material_mut().refractive_index = 2.5;
+
+        let r = Ray::new(Tuple::point(0., 0., -4.), Tuple::vector(0., 0., 1.));
+        let xs = Intersections::new(vec![
+            Intersection::new(2., &a),
+            Intersection::new(2.75, &b),
+            Intersection::new(3.25, &c),
+            Intersection::new(4.75, &b),
+            Intersection::new(5.25, &c),
+            Intersection::new(6., &a),
+        ]);
+        let expected = [
+            (1.0, 1.5),
+            (1.5, 2.0),
+            (2.0, 2.5),
+            (2.5, 2.5),
+            (2.5, 1.5),
+            (1.5, 1.0),
+        ];
+        for (index, (n1, n2)) in expected.iter().enumerate() {
+            let comps = xs[index].prepare_computations(&r, &xs);
+            assert_eq!(comps.n1, *n1);
+            assert_eq!(comps.n2, *n2);
+        }
+    }
+
+    #[test]
+    fn find_n1_and_n2_from_enclosing_media() {
+        // A ray coming up from under a water surface sees only the surface itself
+        let mut water = Plane::new();
+        water.get_material_mut().transparency = 1.;
+        water.get_material_mut().refractive_index = 1.333;
+        let r = Ray::new(Tuple::point(0., -1., 0.), Tuple::vector(0., 1., 0.));
+        let i = Intersection::new(1., &water);
+
+        let comps = i.prepare_computations_in(&r, MediumStack::new().crossing(&water));
+        assert_eq!(comps.n1, 1.333);
+        assert_eq!(comps.n2, 1.);
+        assert!(comps.media.contains(&water));
+
+        let comps = i.prepare_computations_in(&r, MediumStack::new());
+        assert_eq!(comps.n1, 1.);
+        assert_eq!(comps.n2, 1.333);
+    }
+
+    #[test]
+    fn media_from_intersections_before_hit() {
+        let a = Sphere::glass();
+        let b = Sphere::glass();
+        let r = Ray::new(Tuple::point(0., 0., -4.), Tuple::vector(0., 0., 1.));
+        let xs = Intersections::new(vec![
+            Intersection::new(2., &a),
+            Intersection::new(2.75, &b),
+            Intersection::new(3.25, &b),
+            Intersection::new(4., &a),
+        ]);
+        let comps = xs[2].prepare_computations(&r, &xs);
+        assert_eq!(comps.media, MediumStack::new().crossing(&a).crossing(&b));
+    }
+
+    #[test]
+    fn under_point_is_below_surface() {
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let mut shape = Sphere::glass();
+        shape
+            .set_transform(Matrix4::translation(0., 0., 1.))
+            .unwrap();
+        let i = Intersection::new(5., &shape);
+        let comps = i.prepare_computations(&r, &Intersections::new(vec![i.clone()]));
+        assert!(comps.under_point.2 > EPSILON / 2.);
+        assert!(comps.point.2 < comps.under_point.2);
+    }
+
+    #[test]
+    fn schlick_under_total_internal_reflection() {
+        let shape = Sphere::glass();
+        let v = 2_f32.sqrt() / 2.;
+        let r = Ray::new(Tuple::point(0., 0., v), Tuple::vector(0., 1., 0.));
+        let xs = Intersections::new(vec![
+            Intersection::new(-v, &shape),
+            Intersection::new(v, &shape),
+        ]);
+        let comps = xs[1].prepare_computations(&r, &xs);
+        assert_eq!(comps.schlick(), 1.);
+    }
+
+    #[test]
+    fn schlick_with_perpendicular_viewing_angle() {
+        let shape = Sphere::glass();
+        let r = Ray::new(Tuple::point(0., 0., 0.), Tuple::vector(0., 1., 0.));
+        let xs = Intersections::new(vec![
+            Intersection::new(-1., &shape),
+            Intersection::new(1., &shape),
+        ]);
+        let comps = xs[1].prepare_computations(&r, &xs);
+        assert!((comps.schlick() - 0.04).abs() < EPSILON);
+    }
+
+    #[test]
+    fn schlick_with_small_angle_and_n2_greater_than_n1() {
+        let shape = Sphere::glass();
+        let r = Ray::new(Tuple::point(0., 0.99, -2.), Tuple::vector(0., 0., 1.));
+        let xs = Intersections::new(vec![Intersection::new(1.8589, &shape)]);
+        let comps = xs[0].prepare_computations(&r, &xs);
+        assert!((comps.schlick() - 0.48873).abs() < 0.001);
+    }
+}
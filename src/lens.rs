@@ -0,0 +1,167 @@
+use std::f32::consts::TAU;
+
+use crate::canvas::Canvas;
+use crate::random::Rng;
+
+// Tries before a mask sample gives up and uses the center of the lens
+const MASK_MAX_ATTEMPTS: usize = 64;
+
+// Shape of the lens opening, out of focus highlights take on this shape
+#[derive(PartialEq, Debug, Clone, Default)]
+pub enum Aperture {
+    #[default]
+    Disc,
+    // Regular polygon with one corner per blade, rotated counterclockwise.
+    // Fewer than three blades can't enclose anything and act like a disc.
+    Polygon {
+        blades: usize,
+        rotation: f32,
+    },
+    // Image spanning the square around the lens, brighter pixels let more light through
+    Mask(Canvas),
+}
+
+impl Aperture {
+    // Point on the lens with both coordinates in [-1, 1], to be scaled by the lens radius
+    pub fn sample(&self, rng: &mut Rng) -> (f32, f32) {
+        match self {
+            Aperture::Polygon { blades, rotation } if *blades >= 3 => {
+                sample_polygon(*blades, *rotation, rng)
+            }
+            Aperture::Mask(mask) => sample_mask(mask, rng),
+            _ => sample_disc(rng),
+        }
+    }
+}
+
+fn sample_disc(rng: &mut Rng) -> (f32, f32) {
+    // The square root keeps the points evenly spread instead of bunched in the middle
+    let radius = rng.next_f32().sqrt();
+    let (sin, cos) = (rng.next_f32() * TAU).sin_cos();
+    (radius * cos, radius * sin)
+}
+
+// Picks one of the triangles between the center and two neighbouring corners,
+// then a uniform point inside it
+fn sample_polygon(blades: usize, rotation: f32, rng: &mut Rng) -> (f32, f32) {
+    let blade = ((rng.next_f32() * blades as f32) as usize).min(blades - 1);
+    let corner = |i: usize| {
+        let (sin, cos) = (rotation + i as f32 * TAU / blades as f32).sin_cos();
+        (cos, sin)
+    };
+    let (a, b) = (corner(blade), corner(blade + 1));
+    let (mut u, mut v) = (rng.next_f32(), rng.next_f32());
+    if u + v > 1. {
+        (u, v) = (1. - u, 1. - v);
+    }
+    (u * a.0 + v * b.0, u * a.1 + v * b.1)
+}
+
+fn sample_mask(mask: &Canvas, rng: &mut Rng) -> (f32, f32) {
+    let (width, height) = (mask.get_width(), mask.get_height());
+    for _ in 0..MASK_MAX_ATTEMPTS {
+        let (x, y) = (rng.range(-1., 1.), rng.range(-1., 1.));
+        let acceptance = rng.next_f32();
+        let px = (((x + 1.) / 2. * width as f32) as usize).min(width.saturating_sub(1));
+        let py = (((1. - y) / 2. * height as f32) as usize).min(height.saturating_sub(1));
+        let Ok(pixel) = mask.get_pixel_at(px, py) else {
+            break;
+        };
+        let brightness = ((pixel.0 + pixel.1 + pixel.2) / 3.).clamp(0., 1.);
+        if acceptance < brightness {
+            return (x, y);
+        }
+    }
+    (0., 0.)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use super::*;
+    use crate::tuple::Tuple;
+
+    #[test]
+    fn disc_samples_stay_on_the_lens() {
+        let mut rng = Rng::new(1);
+        let aperture = Aperture::Disc;
+        let mut outer = 0;
+        for _ in 0..1000 {
+            let (x, y) = aperture.sample(&mut rng);
+            let r = (x * x + y * y).sqrt();
+            assert!(r <= 1.);
+            if r > 0.5 {
+                outer += 1;
+            }
+        }
+        // Three quarters of the area lies outside half the radius
+        assert!((700..800).contains(&outer));
+    }
+
+    #[test]
+    fn polygon_samples_stay_inside_the_polygon() {
+        let mut rng = Rng::new(2);
+        let aperture = Aperture::Polygon {
+            blades: 6,
+            rotation: 0.,
+        };
+        // Distance from the center to the middle of each edge
+        let apothem = (std::f32::consts::PI / 6.).cos();
+        for _ in 0..1000 {
+            let (x, y) = aperture.sample(&mut rng);
+            for edge in 0..6 {
+                let (sin, cos) = ((edge as f32 + 0.5) * TAU / 6.).sin_cos();
+                assert!(x * cos + y * sin <= apothem + 0.0001);
+            }
+        }
+    }
+
+    #[test]
+    fn rotated_square_aperture() {
+        let mut rng = Rng::new(3);
+        let aperture = Aperture::Polygon {
+            blades: 4,
+            rotation: FRAC_PI_2 / 2.,
+        };
+        for _ in 0..1000 {
+            let (x, y) = aperture.sample(&mut rng);
+            let limit = std::f32::consts::FRAC_1_SQRT_2 + 0.0001;
+            assert!(x.abs() <= limit && y.abs() <= limit);
+        }
+    }
+
+    #[test]
+    fn polygon_with_too_few_blades_is_a_disc() {
+        let aperture = Aperture::Polygon {
+            blades: 2,
+            rotation: 0.,
+        };
+        let a = aperture.sample(&mut Rng::new(4));
+        let b = Aperture::Disc.sample(&mut Rng::new(4));
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn mask_samples_only_bright_pixels() {
+        let mut mask = Canvas::new(4, 4);
+        for y in 0..4 {
+            for x in 0..2 {
+                mask.write_pixel_at(x, y, Tuple::color(1., 1., 1.)).unwrap();
+            }
+        }
+        let aperture = Aperture::Mask(mask);
+        let mut rng = Rng::new(5);
+        for _ in 0..1000 {
+            let (x, y) = aperture.sample(&mut rng);
+            assert!(x <= 0.);
+            assert!((-1. ..=1.).contains(&y));
+        }
+    }
+
+    #[test]
+    fn black_mask_samples_the_center() {
+        let aperture = Aperture::Mask(Canvas::new(3, 3));
+        assert_eq!(aperture.sample(&mut Rng::new(6)), (0., 0.));
+    }
+}
@@ -1,2 +1,41 @@
-mod canvas;
-mod tuple;
+pub mod background;
+pub mod bounds;
+pub mod camera;
+pub mod camera_path;
+pub mod canvas;
+pub mod cone;
+pub mod cube;
+pub mod cylinder;
+pub mod group;
+pub mod intersection;
+pub mod lens;
+pub mod light;
+pub mod lod;
+pub mod material;
+pub mod matrix;
+pub mod medium;
+pub mod obj;
+pub mod pattern;
+pub mod plane;
+pub mod portal;
+pub mod prelude;
+pub mod random;
+pub mod ray;
+pub mod repeat;
+pub mod sampler;
+pub mod scatter;
+pub mod scene;
+pub mod shape;
+pub mod smooth_triangle;
+pub mod sphere;
+pub mod stats;
+pub mod texture;
+pub mod triangle;
+pub mod tuple;
+pub mod world;
+
+pub use canvas::Canvas;
+pub use matrix::{Matrix2, Matrix3, Matrix4};
+pub use tuple::Tuple;
+
+pub const EPSILON: f32 = 0.0001;
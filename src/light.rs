@@ -0,0 +1,237 @@
+use crate::random::{seed_from, Rng};
+use crate::tuple::Tuple;
+
+#[derive(PartialEq, Debug, Clone)]
+pub enum Light {
+    Point(PointLight),
+    Area(AreaLight),
+}
+
+impl Light {
+    pub fn get_intensity(&self) -> &Tuple {
+        match self {
+            Light::Point(light) => &light.intensity,
+            Light::Area(light) => &light.intensity,
+        }
+    }
+
+    // Positions the light is sampled at when shading the point, shadows and
+    // shading have to use the same ones
+    pub fn sample_points(&self, point: &Tuple) -> Vec<Tuple> {
+        match self {
+            Light::Point(light) => vec![light.position.clone()],
+            Light::Area(light) => light.sample_points(point),
+        }
+    }
+}
+
+impl From<PointLight> for Light {
+    fn from(light: PointLight) -> Self {
+        Light::Point(light)
+    }
+}
+
+impl From<AreaLight> for Light {
+    fn from(light: AreaLight) -> Self {
+        Light::Area(light)
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct PointLight {
+    pub position: Tuple,
+    pub intensity: Tuple,
+}
+
+impl PointLight {
+    pub fn new(position: Tuple, intensity: Tuple) -> Self {
+        PointLight {
+            position,
+            intensity,
+        }
+    }
+}
+
+// Rectangular light split into usteps by vsteps cells with one sample each,
+// partly covered points get soft shadows
+#[derive(PartialEq, Debug, Clone)]
+pub struct AreaLight {
+    corner: Tuple,
+    uvec: Tuple,
+    usteps: usize,
+    vvec: Tuple,
+    vsteps: usize,
+    pub intensity: Tuple,
+    // Seed for placing samples randomly inside their cells instead of at the
+    // centers, which trades banding in the penumbra for noise
+    pub jitter: Option<u64>,
+}
+
+impl AreaLight {
+    pub fn new(
+        corner: Tuple,
+        full_uvec: Tuple,
+        usteps: usize,
+        full_vvec: Tuple,
+        vsteps: usize,
+        intensity: Tuple,
+    ) -> Self {
+        let (usteps, vsteps) = (usteps.max(1), vsteps.max(1));
+        AreaLight {
+            corner,
+            uvec: full_uvec / usteps as f32,
+            usteps,
+            vvec: full_vvec / vsteps as f32,
+            vsteps,
+            intensity,
+            jitter: None,
+        }
+    }
+
+    pub fn get_corner(&self) -> &Tuple {
+        &self.corner
+    }
+
+    // Edge of a single cell along u
+    pub fn get_uvec(&self) -> &Tuple {
+        &self.uvec
+    }
+
+    pub fn get_usteps(&self) -> usize {
+        self.usteps
+    }
+
+    // Edge of a single cell along v
+    pub fn get_vvec(&self) -> &Tuple {
+        &self.vvec
+    }
+
+    pub fn get_vsteps(&self) -> usize {
+        self.vsteps
+    }
+
+    pub fn get_samples(&self) -> usize {
+        self.usteps * self.vsteps
+    }
+
+    pub fn get_position(&self) -> Tuple {
+        self.corner.clone()
+            + self.uvec.clone() * (self.usteps as f32 / 2.)
+            + self.vvec.clone() * (self.vsteps as f32 / 2.)
+    }
+
+    // Point in cell (u, v), at its center or jittered by rng
+    pub fn point_on_light(&self, u: usize, v: usize, rng: Option<&mut Rng>) -> Tuple {
+        let (du, dv) = match rng {
+            Some(rng) => (rng.next_f32(), rng.next_f32()),
+            None => (0.5, 0.5),
+        };
+        self.corner.clone()
+            + self.uvec.clone() * (u as f32 + du)
+            + self.vvec.clone() * (v as f32 + dv)
+    }
+
+    // Jitter depends on the shaded point so neighbouring points see different
+    // samples and the noise averages out
+    pub fn sample_points(&self, point: &Tuple) -> Vec<Tuple> {
+        let mut rng = self
+            .jitter
+            .map(|seed| Rng::new(seed ^ seed_from(&[point.0, point.1, point.2])));
+        let mut points = Vec::with_capacity(self.get_samples());
+        for v in 0..self.vsteps {
+            for u in 0..self.usteps {
+                points.push(self.point_on_light(u, v, rng.as_mut()));
+            }
+        }
+        points
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn point_light_has_position_and_intensity() {
+        let intensity = Tuple::color(1., 1., 1.);
+        let position = Tuple::point(0., 0., 0.);
+        let light = PointLight::new(position.clone(), intensity.clone());
+        assert_eq!(light.position, position);
+        assert_eq!(light.intensity, intensity);
+    }
+
+    #[test]
+    fn point_light_is_sampled_at_its_position() {
+        let light: Light =
+            PointLight::new(Tuple::point(1., 2., 3.), Tuple::color(1., 1., 1.)).into();
+        assert_eq!(
+            light.sample_points(&Tuple::point(0., 0., 0.)),
+            vec![Tuple::point(1., 2., 3.)]
+        );
+        assert_eq!(light.get_intensity(), &Tuple::color(1., 1., 1.));
+    }
+
+    #[test]
+    fn create_area_light() {
+        let light = AreaLight::new(
+            Tuple::point(0., 0., 0.),
+            Tuple::vector(2., 0., 0.),
+            4,
+            Tuple::vector(0., 0., 1.),
+            2,
+            Tuple::color(1., 1., 1.),
+        );
+        assert_eq!(light.get_corner(), &Tuple::point(0., 0., 0.));
+        assert_eq!(light.get_uvec(), &Tuple::vector(0.5, 0., 0.));
+        assert_eq!(light.get_usteps(), 4);
+        assert_eq!(light.get_vvec(), &Tuple::vector(0., 0., 0.5));
+        assert_eq!(light.get_vsteps(), 2);
+        assert_eq!(light.get_samples(), 8);
+        assert_eq!(light.get_position(), Tuple::point(1., 0., 0.5));
+    }
+
+    #[test]
+    fn single_point_on_area_light() {
+        let light = AreaLight::new(
+            Tuple::point(0., 0., 0.),
+            Tuple::vector(2., 0., 0.),
+            4,
+            Tuple::vector(0., 0., 1.),
+            2,
+            Tuple::color(1., 1., 1.),
+        );
+        let cases = [
+            (0, 0, Tuple::point(0.25, 0., 0.25)),
+            (1, 0, Tuple::point(0.75, 0., 0.25)),
+            (0, 1, Tuple::point(0.25, 0., 0.75)),
+            (2, 0, Tuple::point(1.25, 0., 0.25)),
+            (3, 1, Tuple::point(1.75, 0., 0.75)),
+        ];
+        for (u, v, expected) in cases {
+            assert_eq!(light.point_on_light(u, v, None), expected);
+        }
+    }
+
+    #[test]
+    fn jittered_samples_stay_in_their_cells() {
+        let mut light = AreaLight::new(
+            Tuple::point(0., 0., 0.),
+            Tuple::vector(2., 0., 0.),
+            4,
+            Tuple::vector(0., 0., 1.),
+            2,
+            Tuple::color(1., 1., 1.),
+        );
+        light.jitter = Some(7);
+        let point = Tuple::point(0., 5., 0.);
+        let samples = light.sample_points(&point);
+        assert_eq!(samples.len(), 8);
+        for (i, sample) in samples.iter().enumerate() {
+            let (u, v) = ((i % 4) as f32, (i / 4) as f32);
+            assert!(u * 0.5 <= sample.0 && sample.0 < (u + 1.) * 0.5);
+            assert!(v * 0.5 <= sample.2 && sample.2 < (v + 1.) * 0.5);
+        }
+        assert_eq!(samples, light.sample_points(&point));
+        assert_ne!(samples, light.sample_points(&Tuple::point(1., 5., 0.)));
+    }
+}
@@ -0,0 +1,275 @@
+use crate::bounds::BoundingBox;
+use crate::intersection::Intersection;
+use crate::matrix::Matrix4;
+use crate::ray::Ray;
+use crate::shape::{Shape, ShapeBase};
+use crate::tuple::Tuple;
+
+// Versions of the same shape from most to least detailed. Each ray only sees
+// the level matching how big the shape looks from the ray's viewpoint, so
+// distant copies cost no more than their coarsest mesh. Rays from a camera and
+// all rays they lead to share the camera's viewpoint and so the level on
+// screen, rays without one pick by where they start. Inside a Repeat every
+// copy picks its own level.
+#[derive(Debug)]
+pub struct Lod {
+    base: ShapeBase,
+    levels: Vec<Box<dyn Shape>>,
+    // Smallest apparent size each level is still used at, the last level is
+    // used for anything smaller
+    min_sizes: Vec<f32>,
+}
+
+impl Lod {
+    pub fn new() -> Self {
+        Lod {
+            base: ShapeBase::new(),
+            levels: vec![],
+            min_sizes: vec![],
+        }
+    }
+
+    // Levels have to be added from the most detailed one down
+    pub fn add_level(&mut self, mut level: Box<dyn Shape>, min_size: f32) {
+        level.set_parent_inverse(*self.base.get_world_inverse_transform());
+        self.levels.push(level);
+        self.min_sizes.push(min_size);
+    }
+
+    pub fn len(&self) -> usize {
+        self.levels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.levels.is_empty()
+    }
+
+    // Radius of the most detailed level's bounds over their distance from the
+    // point, roughly the angle in radians the shape covers. Scaling the Lod
+    // doesn't change it, so it works in object space.
+    pub fn apparent_size(&self, point: &Tuple) -> f32 {
+        let Some(level) = self.levels.first() else {
+            return 0.;
+        };
+        let bounds = level.parent_space_bounds();
+        if bounds.is_empty() {
+            return 0.;
+        }
+        let center = (bounds.min.clone() + bounds.max.clone()) * 0.5;
+        let radius = (bounds.max.clone() - bounds.min.clone()).mag() / 2.;
+        let distance = (point.clone() - center).mag();
+        if distance <= radius {
+            return f32::INFINITY;
+        }
+        radius / distance
+    }
+
+    // Index of the level seen from the point in object space
+    pub fn select(&self, point: &Tuple) -> Option<usize> {
+        if self.levels.is_empty() {
+            return None;
+        }
+        let size = self.apparent_size(point);
+        let level = self
+            .min_sizes
+            .iter()
+            .position(|&min_size| size >= min_size)
+            .unwrap_or(self.levels.len() - 1);
+        Some(level)
+    }
+
+    fn update_levels(&mut self) {
+        let world_inverse = *self.base.get_world_inverse_transform();
+        for level in &mut self.levels {
+            level.set_parent_inverse(world_inverse);
+        }
+    }
+}
+
+impl Default for Lod {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shape for Lod {
+    fn get_base(&self) -> &ShapeBase {
+        &self.base
+    }
+
+    fn get_base_mut(&mut self) -> &mut ShapeBase {
+        &mut self.base
+    }
+
+    fn get_children(&self) -> &[Box<dyn Shape>] {
+        &self.levels
+    }
+
+    fn get_children_mut(&mut self) -> &mut [Box<dyn Shape>] {
+        &mut self.levels
+    }
+
+    fn set_transform(&mut self, transform: Matrix4) -> Result<(), String> {
+        self.base.set_transform(transform)?;
+        self.update_levels();
+        Ok(())
+    }
+
+    fn set_parent_inverse(&mut self, parent_inverse: Matrix4) {
+        self.base.set_parent_inverse(parent_inverse);
+        self.update_levels();
+    }
+
+    fn divide(&mut self, threshold: usize) {
+        for level in &mut self.levels {
+            level.divide(threshold);
+        }
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        match self.select(ray.viewpoint.as_ref().unwrap_or(&ray.origin)) {
+            Some(level) => self.levels[level].intersect(ray),
+            None => vec![],
+        }
+    }
+
+    fn local_normal_at(&self, _point: &Tuple) -> Tuple {
+        unreachable!("Lods have no surface, normals come from their levels")
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        let mut bounds = BoundingBox::empty();
+        for level in &self.levels {
+            bounds.merge(&level.parent_space_bounds());
+        }
+        bounds
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::background::Background;
+    use crate::cube::Cube;
+    use crate::material::Material;
+    use crate::repeat::Repeat;
+    use crate::sphere::Sphere;
+    use crate::world::World;
+
+    // Sphere up close, a cube standing in for it from further than about 17 units
+    fn sphere_lod() -> Lod {
+        let mut lod = Lod::new();
+        lod.add_level(Box::new(Sphere::new()), 0.1);
+        lod.add_level(Box::new(Cube::new()), 0.);
+        lod
+    }
+
+    #[test]
+    fn empty_lod_is_never_hit() {
+        let lod = Lod::new();
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        assert!(lod.is_empty());
+        assert_eq!(lod.select(&r.origin), None);
+        assert!(lod.local_intersect(&r).is_empty());
+    }
+
+    #[test]
+    fn apparent_size_shrinks_with_distance() {
+        let lod = sphere_lod();
+        let radius = 3_f32.sqrt();
+        assert_eq!(lod.apparent_size(&Tuple::point(0., 0., 0.5)), f32::INFINITY);
+        assert!((lod.apparent_size(&Tuple::point(0., 0., -10.)) - radius / 10.).abs() < 0.0001);
+        assert!((lod.apparent_size(&Tuple::point(0., 20., 0.)) - radius / 20.).abs() < 0.0001);
+    }
+
+    #[test]
+    fn rays_see_the_level_for_their_distance() {
+        let lod = sphere_lod();
+        let near = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let far = Ray::new(Tuple::point(0., 0., -50.), Tuple::vector(0., 0., 1.));
+        assert_eq!(lod.select(&near.origin), Some(0));
+        assert_eq!(lod.select(&far.origin), Some(1));
+
+        let xs = lod.local_intersect(&near);
+        assert!(std::ptr::addr_eq(xs[0].object, lod.levels[0].as_ref()));
+        let xs = lod.local_intersect(&far);
+        assert!(std::ptr::addr_eq(xs[0].object, lod.levels[1].as_ref()));
+
+        // A corner of the cube sticks out where the sphere isn't
+        let corner = |z: f32| Ray::new(Tuple::point(0.9, 0.9, z), Tuple::vector(0., 0., 1.));
+        assert!(lod.local_intersect(&corner(-5.)).is_empty());
+        assert!(!lod.local_intersect(&corner(-50.)).is_empty());
+    }
+
+    #[test]
+    fn scaling_keeps_the_switch_distance_proportional() {
+        let mut lod = sphere_lod();
+        lod.set_transform(Matrix4::scaling(10., 10., 10.)).unwrap();
+        let r = Ray::new(Tuple::point(0., 0., -50.), Tuple::vector(0., 0., 1.));
+        let xs = lod.intersect(&r);
+        assert_eq!(xs[0].t, 40.);
+        assert!(std::ptr::addr_eq(xs[0].object, lod.levels[0].as_ref()));
+        let n = xs[0].object.normal_at(&Tuple::point(0., 0., -10.));
+        assert!(n.approx_eq(&Tuple::vector(0., 0., -1.)));
+    }
+
+    #[test]
+    fn rays_see_the_level_of_their_viewpoint() {
+        let lod = sphere_lod();
+        let far = Tuple::point(0., 0., -50.);
+        // A refracted ray starting just inside the corner of the cube seen from afar
+        let inside = Ray::new(Tuple::point(0.95, 0.95, -0.99), Tuple::vector(0., 0., 1.));
+        assert!(lod.local_intersect(&inside).is_empty());
+        let xs = lod.local_intersect(&inside.with_viewpoint(Some(far)));
+        assert_eq!(xs.len(), 2);
+        assert!(std::ptr::addr_eq(xs[1].object, lod.levels[1].as_ref()));
+        assert!((xs[1].t - 1.99).abs() < 0.0001);
+    }
+
+    #[test]
+    fn copies_in_a_repeat_see_their_own_level() {
+        let repeat = Repeat::new(Box::new(sphere_lod()), Tuple::vector(0., 0., 40.));
+        let seen = |x: f32, z: f32| {
+            let r = Ray::new(Tuple::point(x, 0., z), Tuple::vector(0., 0., 1.))
+                .with_viewpoint(Some(Tuple::point(0., 0., -5.)));
+            let xs = repeat.intersect(&r);
+            let lod = repeat.get_child().get_children();
+            let level = lod
+                .iter()
+                .position(|l| std::ptr::addr_eq(xs[0].object, l.as_ref()));
+            level.unwrap()
+        };
+        // Rays starting right in front of each copy still pick by the camera's distance
+        assert_eq!(seen(0., -2.), 0);
+        assert_eq!(seen(0., 38.), 1);
+    }
+
+    #[test]
+    fn refracted_rays_leave_through_the_level_on_screen() {
+        let mut material = Material::new();
+        material.diffuse = 0.;
+        material.specular = 0.;
+        material.transparency = 1.;
+        material.absorption = Tuple::color(0.5, 0.5, 0.5);
+        let mut lod = Lod::new();
+        for (mut level, min_size) in [
+            (Box::new(Sphere::new()) as Box<dyn Shape>, 0.1),
+            (Box::new(Cube::new()), 0.),
+        ] {
+            level.set_material(material.clone());
+            lod.add_level(level, min_size);
+        }
+        let mut world = World::new();
+        world.background = Background::Solid(Tuple::color(1., 1., 1.));
+        world.objects.push(Box::new(lod));
+
+        // Seen from afar the ray crosses 2 units of the cube, inside it the
+        // refracted ray would see the sphere and never get out
+        let r = Ray::new(Tuple::point(0.95, 0.95, -50.), Tuple::vector(0., 0., 1.))
+            .with_viewpoint(Some(Tuple::point(0., 0., -50.)));
+        let fade = (-1_f32).exp();
+        assert!(world
+            .color_at(&r)
+            .approx_eq(&Tuple::color(fade, fade, fade)));
+    }
+}
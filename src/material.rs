@@ -0,0 +1,347 @@
+use std::sync::Arc;
+
+use crate::light::Light;
+use crate::pattern::Pattern;
+use crate::shape::Shape;
+use crate::tuple::Tuple;
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct Material {
+    pub color: Tuple,
+    pub ambient: f32,
+    pub diffuse: f32,
+    pub specular: f32,
+    pub shininess: f32,
+    pub reflective: f32,
+    pub transparency: f32,
+    pub refractive_index: f32,
+    // Blurs reflections and refractions, 0 is a perfect mirror or clear glass
+    pub roughness: f32,
+    // How much of each channel is absorbed per unit of distance travelled inside,
+    // black absorbs nothing. Density scales it, e.g. for diluted liquids.
+    pub absorption: Tuple,
+    pub density: f32,
+    // Overrides color when set, shared so materials stay cheap to clone
+    pub pattern: Option<Arc<dyn Pattern>>,
+}
+
+impl Material {
+    pub fn new() -> Self {
+        Material {
+            color: Tuple::color(1., 1., 1.),
+            ambient: 0.1,
+            diffuse: 0.9,
+            specular: 0.9,
+            shininess: 200.,
+            reflective: 0.,
+            transparency: 0.,
+            refractive_index: 1.,
+            roughness: 0.,
+            absorption: Tuple::color(0., 0., 0.),
+            density: 1.,
+            pattern: None,
+        }
+    }
+
+    // Share of the light that makes it through distance units of the material,
+    // following the Beer-Lambert law
+    pub fn transmittance(&self, distance: f32) -> Tuple {
+        let fade = |absorption: f32| {
+            let coefficient = absorption * self.density;
+            // Also keeps an infinite distance through a clear material at 1 instead of NaN
+            if coefficient == 0. {
+                1.
+            } else {
+                (-coefficient * distance).exp()
+            }
+        };
+        Tuple::color(
+            fade(self.absorption.0),
+            fade(self.absorption.1),
+            fade(self.absorption.2),
+        )
+    }
+
+    // Color of the surface at a world space point, from the pattern if there is one
+    pub fn color_at(&self, object: &dyn Shape, point: &Tuple) -> Tuple {
+        match &self.pattern {
+            Some(pattern) => pattern.pattern_at_shape(object, point),
+            None => self.color.clone(),
+        }
+    }
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Intensity is the fraction of the light reaching the point, 0 in full shadow.
+// Diffuse and specular are averaged over every sample point of the light.
+pub fn lighting(
+    material: &Material,
+    object: &dyn Shape,
+    light: &Light,
+    point: &Tuple,
+    eyev: &Tuple,
+    normalv: &Tuple,
+    intensity: f32,
+) -> Tuple {
+    let color = material.color_at(object, point);
+    lighting_with_color(material, &color, light, point, eyev, normalv, intensity)
+}
+
+// Lighting for a surface color looked up somewhere other than the lit point
+pub fn lighting_with_color(
+    material: &Material,
+    color: &Tuple,
+    light: &Light,
+    point: &Tuple,
+    eyev: &Tuple,
+    normalv: &Tuple,
+    intensity: f32,
+) -> Tuple {
+    let effective_color = color.hadamard(light.get_intensity());
+    let ambient = effective_color.clone() * material.ambient;
+
+    if intensity <= 0. {
+        return Tuple::color(ambient.0, ambient.1, ambient.2);
+    }
+
+    let mut sum = Tuple::color(0., 0., 0.);
+    let samples = light.sample_points(point);
+    for position in &samples {
+        let lightv = (position.clone() - point.clone()).normalize();
+
+        // A negative dot product means the light is on the other side of the surface
+        let light_dot_normal = lightv.dot(normalv);
+        if light_dot_normal < 0. {
+            continue;
+        }
+        sum = sum + effective_color.clone() * material.diffuse * light_dot_normal;
+
+        let reflectv = (-lightv).reflect(normalv);
+        let reflect_dot_eye = reflectv.dot(eyev);
+        if reflect_dot_eye > 0. {
+            let factor = reflect_dot_eye.powf(material.shininess);
+            sum = sum + light.get_intensity().clone() * material.specular * factor;
+        }
+    }
+
+    let result = ambient + sum * (intensity / samples.len() as f32);
+    Tuple::color(result.0, result.1, result.2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_1_SQRT_2;
+
+    use crate::light::{AreaLight, PointLight};
+    use crate::pattern::StripePattern;
+    use crate::sphere::Sphere;
+
+    #[test]
+    fn default_material() {
+        let m = Material::new();
+        assert_eq!(m.color, Tuple::color(1., 1., 1.));
+        assert_eq!(m.ambient, 0.1);
+        assert_eq!(m.diffuse, 0.9);
+        assert_eq!(m.specular, 0.9);
+        assert_eq!(m.shininess, 200.);
+        assert_eq!(m.reflective, 0.);
+        assert_eq!(m.transparency, 0.);
+        assert_eq!(m.refractive_index, 1.);
+        assert_eq!(m.roughness, 0.);
+        assert_eq!(m.absorption, Tuple::color(0., 0., 0.));
+        assert_eq!(m.density, 1.);
+    }
+
+    #[test]
+    fn clear_material_transmits_everything() {
+        let m = Material::new();
+        assert_eq!(m.transmittance(3.), Tuple::color(1., 1., 1.));
+        assert_eq!(m.transmittance(f32::INFINITY), Tuple::color(1., 1., 1.));
+    }
+
+    #[test]
+    fn transmittance_fades_with_distance() {
+        let mut m = Material::new();
+        m.absorption = Tuple::color(1., 0., 0.5);
+        let t = m.transmittance(2.);
+        assert!(t.approx_eq(&Tuple::color((-2_f32).exp(), 1., (-1_f32).exp())));
+
+        m.density = 0.5;
+        let t = m.transmittance(2.);
+        assert!(t.approx_eq(&Tuple::color((-1_f32).exp(), 1., (-0.5_f32).exp())));
+        assert_eq!(m.transmittance(f32::INFINITY), Tuple::color(0., 1., 0.));
+    }
+
+    #[test]
+    fn lighting_with_eye_between_light_and_surface() {
+        let m = Material::new();
+        let position = Tuple::point(0., 0., 0.);
+        let eyev = Tuple::vector(0., 0., -1.);
+        let normalv = Tuple::vector(0., 0., -1.);
+        let light: Light =
+            PointLight::new(Tuple::point(0., 0., -10.), Tuple::color(1., 1., 1.)).into();
+        let result = lighting(&m, &Sphere::new(), &light, &position, &eyev, &normalv, 1.);
+        assert!(result.approx_eq(&Tuple::color(1.9, 1.9, 1.9)));
+    }
+
+    #[test]
+    fn lighting_with_eye_offset_45_degrees() {
+        let m = Material::new();
+        let position = Tuple::point(0., 0., 0.);
+        let v = 2_f32.sqrt() / 2.;
+        let eyev = Tuple::vector(0., v, -v);
+        let normalv = Tuple::vector(0., 0., -1.);
+        let light: Light =
+            PointLight::new(Tuple::point(0., 0., -10.), Tuple::color(1., 1., 1.)).into();
+        let result = lighting(&m, &Sphere::new(), &light, &position, &eyev, &normalv, 1.);
+        assert!(result.approx_eq(&Tuple::color(1., 1., 1.)));
+    }
+
+    #[test]
+    fn lighting_with_light_offset_45_degrees() {
+        let m = Material::new();
+        let position = Tuple::point(0., 0., 0.);
+        let eyev = Tuple::vector(0., 0., -1.);
+        let normalv = Tuple::vector(0., 0., -1.);
+        let light: Light =
+            PointLight::new(Tuple::point(0., 10., -10.), Tuple::color(1., 1., 1.)).into();
+        let result = lighting(&m, &Sphere::new(), &light, &position, &eyev, &normalv, 1.);
+        assert!(result.approx_eq(&Tuple::color(0.7364, 0.7364, 0.7364)));
+    }
+
+    #[test]
+    fn lighting_with_eye_in_path_of_reflection() {
+        let m = Material::new();
+        let position = Tuple::point(0., 0., 0.);
+        let v = 2_f32.sqrt() / 2.;
+        let eyev = Tuple::vector(0., -v, -v);
+        let normalv = Tuple::vector(0., 0., -1.);
+        let light: Light =
+            PointLight::new(Tuple::point(0., 10., -10.), Tuple::color(1., 1., 1.)).into();
+        let result = lighting(&m, &Sphere::new(), &light, &position, &eyev, &normalv, 1.);
+        assert!(result.approx_eq(&Tuple::color(1.6364, 1.6364, 1.6364)));
+    }
+
+    #[test]
+    fn lighting_with_light_behind_surface() {
+        let m = Material::new();
+        let position = Tuple::point(0., 0., 0.);
+        let eyev = Tuple::vector(0., 0., -1.);
+        let normalv = Tuple::vector(0., 0., -1.);
+        let light: Light =
+            PointLight::new(Tuple::point(0., 0., 10.), Tuple::color(1., 1., 1.)).into();
+        let result = lighting(&m, &Sphere::new(), &light, &position, &eyev, &normalv, 1.);
+        assert!(result.approx_eq(&Tuple::color(0.1, 0.1, 0.1)));
+    }
+
+    #[test]
+    fn lighting_with_surface_in_shadow() {
+        let m = Material::new();
+        let position = Tuple::point(0., 0., 0.);
+        let eyev = Tuple::vector(0., 0., -1.);
+        let normalv = Tuple::vector(0., 0., -1.);
+        let light: Light =
+            PointLight::new(Tuple::point(0., 0., -10.), Tuple::color(1., 1., 1.)).into();
+        let result = lighting(&m, &Sphere::new(), &light, &position, &eyev, &normalv, 0.);
+        assert!(result.approx_eq(&Tuple::color(0.1, 0.1, 0.1)));
+    }
+
+    #[test]
+    fn lighting_with_pattern_applied() {
+        let mut m = Material::new();
+        m.pattern = Some(Arc::new(StripePattern::new(
+            Tuple::color(1., 1., 1.),
+            Tuple::color(0., 0., 0.),
+        )));
+        m.ambient = 1.;
+        m.diffuse = 0.;
+        m.specular = 0.;
+        let eyev = Tuple::vector(0., 0., -1.);
+        let normalv = Tuple::vector(0., 0., -1.);
+        let light: Light =
+            PointLight::new(Tuple::point(0., 0., -10.), Tuple::color(1., 1., 1.)).into();
+        let object = Sphere::new();
+        let c1 = lighting(
+            &m,
+            &object,
+            &light,
+            &Tuple::point(0.9, 0., 0.),
+            &eyev,
+            &normalv,
+            1.,
+        );
+        let c2 = lighting(
+            &m,
+            &object,
+            &light,
+            &Tuple::point(1.1, 0., 0.),
+            &eyev,
+            &normalv,
+            1.,
+        );
+        assert_eq!(c1, Tuple::color(1., 1., 1.));
+        assert_eq!(c2, Tuple::color(0., 0., 0.));
+    }
+
+    #[test]
+    fn lighting_uses_light_intensity_to_attenuate_color() {
+        let mut m = Material::new();
+        m.ambient = 0.1;
+        m.diffuse = 0.9;
+        m.specular = 0.;
+        let light: Light =
+            PointLight::new(Tuple::point(0., 0., -10.), Tuple::color(1., 1., 1.)).into();
+        let position = Tuple::point(0., 0., -1.);
+        let eyev = Tuple::vector(0., 0., -1.);
+        let normalv = Tuple::vector(0., 0., -1.);
+        let cases = [(1., 1.), (0.5, 0.55), (0., 0.1)];
+        for (intensity, expected) in cases {
+            let result = lighting(
+                &m,
+                &Sphere::new(),
+                &light,
+                &position,
+                &eyev,
+                &normalv,
+                intensity,
+            );
+            assert!(result.approx_eq(&Tuple::color(expected, expected, expected)));
+        }
+    }
+
+    #[test]
+    fn lighting_samples_the_area_light() {
+        let light: Light = AreaLight::new(
+            Tuple::point(-0.5, -0.5, -5.),
+            Tuple::vector(1., 0., 0.),
+            2,
+            Tuple::vector(0., 1., 0.),
+            2,
+            Tuple::color(1., 1., 1.),
+        )
+        .into();
+        let mut m = Material::new();
+        m.ambient = 0.1;
+        m.diffuse = 0.9;
+        m.specular = 0.;
+        m.color = Tuple::color(1., 1., 1.);
+        let object = Sphere::new();
+        let eye = Tuple::point(0., 0., -5.);
+        let cases = [
+            (Tuple::point(0., 0., -1.), 0.9965),
+            (Tuple::point(0., FRAC_1_SQRT_2, -FRAC_1_SQRT_2), 0.6232),
+        ];
+        for (point, expected) in cases {
+            let eyev = (eye.clone() - point.clone()).normalize();
+            let normalv = Tuple::vector(point.0, point.1, point.2);
+            let result = lighting(&m, &object, &light, &point, &eyev, &normalv, 1.);
+            assert!(result.approx_eq(&Tuple::color(expected, expected, expected)));
+        }
+    }
+}
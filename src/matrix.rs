@@ -0,0 +1,698 @@
+use std::ops;
+
+use crate::tuple::Tuple;
+use crate::EPSILON;
+
+// Fixed-size matrices kept on the stack, only 4x4 is used for transforms, the
+// smaller ones exist for computing cofactors
+#[derive(Debug, Clone, Copy)]
+pub struct Matrix4([[f32; 4]; 4]);
+
+#[derive(Debug, Clone, Copy)]
+pub struct Matrix3([[f32; 3]; 3]);
+
+#[derive(Debug, Clone, Copy)]
+pub struct Matrix2([[f32; 2]; 2]);
+
+impl Matrix4 {
+    pub fn new(data: [[f32; 4]; 4]) -> Self {
+        Matrix4(data)
+    }
+
+    pub fn identity() -> Self {
+        Matrix4([
+            [1., 0., 0., 0.],
+            [0., 1., 0., 0.],
+            [0., 0., 1., 0.],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    pub fn translation(x: f32, y: f32, z: f32) -> Self {
+        Matrix4([
+            [1., 0., 0., x],
+            [0., 1., 0., y],
+            [0., 0., 1., z],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    pub fn scaling(x: f32, y: f32, z: f32) -> Self {
+        Matrix4([
+            [x, 0., 0., 0.],
+            [0., y, 0., 0.],
+            [0., 0., z, 0.],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    pub fn rotation_x(radians: f32) -> Self {
+        let (sin, cos) = radians.sin_cos();
+        Matrix4([
+            [1., 0., 0., 0.],
+            [0., cos, -sin, 0.],
+            [0., sin, cos, 0.],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    pub fn rotation_y(radians: f32) -> Self {
+        let (sin, cos) = radians.sin_cos();
+        Matrix4([
+            [cos, 0., sin, 0.],
+            [0., 1., 0., 0.],
+            [-sin, 0., cos, 0.],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    pub fn rotation_z(radians: f32) -> Self {
+        let (sin, cos) = radians.sin_cos();
+        Matrix4([
+            [cos, -sin, 0., 0.],
+            [sin, cos, 0., 0.],
+            [0., 0., 1., 0.],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    // Moves each coordinate in proportion to the other two, e.g. x by xy * y
+    pub fn shearing(xy: f32, xz: f32, yx: f32, yz: f32, zx: f32, zy: f32) -> Self {
+        Matrix4([
+            [1., xy, xz, 0.],
+            [yx, 1., yz, 0.],
+            [zx, zy, 1., 0.],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    // Orients the world relative to an eye at from looking towards to
+    pub fn view_transform(from: &Tuple, to: &Tuple, up: &Tuple) -> Self {
+        let forward = (to.clone() - from.clone()).normalize();
+        let left = forward.cross(&up.normalize());
+        let true_up = left.cross(&forward);
+        let orientation = Matrix4([
+            [left.0, left.1, left.2, 0.],
+            [true_up.0, true_up.1, true_up.2, 0.],
+            [-forward.0, -forward.1, -forward.2, 0.],
+            [0., 0., 0., 1.],
+        ]);
+        orientation * Matrix4::translation(-from.0, -from.1, -from.2)
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> f32 {
+        self.0[row][col]
+    }
+
+    pub fn transpose(&self) -> Matrix4 {
+        let mut data = [[0.; 4]; 4];
+        for (row, values) in self.0.iter().enumerate() {
+            for (col, value) in values.iter().enumerate() {
+                data[col][row] = *value;
+            }
+        }
+        Matrix4(data)
+    }
+
+    pub fn submatrix(&self, row: usize, col: usize) -> Matrix3 {
+        Matrix3(submatrix(&self.0, row, col))
+    }
+
+    pub fn minor(&self, row: usize, col: usize) -> f32 {
+        self.submatrix(row, col).determinant()
+    }
+
+    pub fn cofactor(&self, row: usize, col: usize) -> f32 {
+        cofactor(self.minor(row, col), row, col)
+    }
+
+    pub fn determinant(&self) -> f32 {
+        (0..4)
+            .map(|col| self.0[0][col] * self.cofactor(0, col))
+            .sum()
+    }
+
+    pub fn is_invertible(&self) -> bool {
+        self.determinant() != 0.
+    }
+
+    pub fn inverse(&self) -> Option<Matrix4> {
+        let det = self.determinant();
+        if det == 0. {
+            return None;
+        }
+        let mut data = [[0.; 4]; 4];
+        for (row, values) in data.iter_mut().enumerate() {
+            for (col, value) in values.iter_mut().enumerate() {
+                // Writing to [row][col] the cofactor of (col, row) transposes the result
+                *value = self.cofactor(col, row) / det;
+            }
+        }
+        Some(Matrix4(data))
+    }
+}
+
+impl Default for Matrix4 {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Matrix3 {
+    pub fn new(data: [[f32; 3]; 3]) -> Self {
+        Matrix3(data)
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> f32 {
+        self.0[row][col]
+    }
+
+    pub fn submatrix(&self, row: usize, col: usize) -> Matrix2 {
+        Matrix2(submatrix(&self.0, row, col))
+    }
+
+    pub fn minor(&self, row: usize, col: usize) -> f32 {
+        self.submatrix(row, col).determinant()
+    }
+
+    pub fn cofactor(&self, row: usize, col: usize) -> f32 {
+        cofactor(self.minor(row, col), row, col)
+    }
+
+    pub fn determinant(&self) -> f32 {
+        (0..3)
+            .map(|col| self.0[0][col] * self.cofactor(0, col))
+            .sum()
+    }
+}
+
+impl Matrix2 {
+    pub fn new(data: [[f32; 2]; 2]) -> Self {
+        Matrix2(data)
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> f32 {
+        self.0[row][col]
+    }
+
+    pub fn determinant(&self) -> f32 {
+        self.0[0][0] * self.0[1][1] - self.0[0][1] * self.0[1][0]
+    }
+}
+
+// Copies everything except the given row and column, M must be N - 1
+fn submatrix<const N: usize, const M: usize>(
+    data: &[[f32; N]; N],
+    row: usize,
+    col: usize,
+) -> [[f32; M]; M] {
+    let mut result = [[0.; M]; M];
+    let rows = (0..N).filter(|r| *r != row);
+    for (target, r) in result.iter_mut().zip(rows) {
+        let cols = (0..N).filter(|c| *c != col);
+        for (value, c) in target.iter_mut().zip(cols) {
+            *value = data[r][c];
+        }
+    }
+    result
+}
+
+fn cofactor(minor: f32, row: usize, col: usize) -> f32 {
+    if (row + col).is_multiple_of(2) {
+        minor
+    } else {
+        -minor
+    }
+}
+
+// Indexing by (row, col) and comparing within EPSILON, the same for every size
+macro_rules! impl_matrix_ops {
+    ($matrix:ident) => {
+        impl ops::Index<(usize, usize)> for $matrix {
+            type Output = f32;
+
+            fn index(&self, (row, col): (usize, usize)) -> &f32 {
+                &self.0[row][col]
+            }
+        }
+
+        impl ops::IndexMut<(usize, usize)> for $matrix {
+            fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut f32 {
+                &mut self.0[row][col]
+            }
+        }
+
+        impl PartialEq for $matrix {
+            fn eq(&self, other: &Self) -> bool {
+                self.0
+                    .iter()
+                    .flatten()
+                    .zip(other.0.iter().flatten())
+                    .all(|(a, b)| (a - b).abs() < EPSILON)
+            }
+        }
+    };
+}
+
+impl_matrix_ops!(Matrix4);
+impl_matrix_ops!(Matrix3);
+impl_matrix_ops!(Matrix2);
+
+impl ops::Mul<Matrix4> for Matrix4 {
+    type Output = Matrix4;
+
+    fn mul(self, rhs: Matrix4) -> Matrix4 {
+        let mut data = [[0.; 4]; 4];
+        for (row, values) in data.iter_mut().enumerate() {
+            for (col, value) in values.iter_mut().enumerate() {
+                *value = (0..4).map(|i| self.0[row][i] * rhs.0[i][col]).sum();
+            }
+        }
+        Matrix4(data)
+    }
+}
+
+impl ops::Mul<&Tuple> for Matrix4 {
+    type Output = Tuple;
+
+    fn mul(self, rhs: &Tuple) -> Tuple {
+        let row = |r: [f32; 4]| r[0] * rhs.0 + r[1] * rhs.1 + r[2] * rhs.2 + r[3] * rhs.3;
+        Tuple(
+            row(self.0[0]),
+            row(self.0[1]),
+            row(self.0[2]),
+            row(self.0[3]),
+        )
+    }
+}
+
+impl ops::Mul<Tuple> for Matrix4 {
+    type Output = Tuple;
+
+    fn mul(self, rhs: Tuple) -> Tuple {
+        self * &rhs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_cmp::approx_eq;
+
+    use super::*;
+
+    fn assert_matrix_approx_eq(a: &Matrix4, b: &Matrix4) {
+        for row in 0..4 {
+            for col in 0..4 {
+                assert!(
+                    approx_eq!(f32, a.get(row, col), b.get(row, col), epsilon = 0.00001),
+                    "{:?} != {:?}",
+                    a,
+                    b
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn create_matrix() {
+        let m = Matrix4::new([
+            [1., 2., 3., 4.],
+            [5.5, 6.5, 7.5, 8.5],
+            [9., 10., 11., 12.],
+            [13.5, 14.5, 15.5, 16.5],
+        ]);
+        assert_eq!(m.get(0, 0), 1.);
+        assert_eq!(m.get(0, 3), 4.);
+        assert_eq!(m.get(1, 0), 5.5);
+        assert_eq!(m.get(1, 2), 7.5);
+        assert_eq!(m.get(2, 2), 11.);
+        assert_eq!(m.get(3, 0), 13.5);
+        assert_eq!(m.get(3, 2), 15.5);
+    }
+
+    #[test]
+    fn multiply_two_matrices() {
+        let a = Matrix4::new([
+            [1., 2., 3., 4.],
+            [5., 6., 7., 8.],
+            [9., 8., 7., 6.],
+            [5., 4., 3., 2.],
+        ]);
+        let b = Matrix4::new([
+            [-2., 1., 2., 3.],
+            [3., 2., 1., -1.],
+            [4., 3., 6., 5.],
+            [1., 2., 7., 8.],
+        ]);
+        assert_eq!(
+            a * b,
+            Matrix4::new([
+                [20., 22., 50., 48.],
+                [44., 54., 114., 108.],
+                [40., 58., 110., 102.],
+                [16., 26., 46., 42.],
+            ])
+        );
+    }
+
+    #[test]
+    fn index_matrix() {
+        let mut m = Matrix4::identity();
+        assert_eq!(m[(0, 0)], 1.);
+        assert_eq!(m[(2, 3)], 0.);
+        m[(2, 3)] = 7.5;
+        assert_eq!(m[(2, 3)], 7.5);
+        assert_eq!(m.get(2, 3), 7.5);
+
+        let mut m = Matrix2::new([[1., 5.], [-3., 2.]]);
+        m[(1, 0)] = 3.;
+        assert_eq!(m.determinant(), -13.);
+    }
+
+    #[test]
+    fn compare_matrices_approximately() {
+        let a = Matrix4::scaling(2., 3., 4.);
+        let mut b = a;
+        b[(1, 1)] += EPSILON / 2.;
+        assert_eq!(a, b);
+        b[(1, 1)] += EPSILON;
+        assert_ne!(a, b);
+        assert_ne!(a, Matrix4::identity());
+    }
+
+    #[test]
+    fn multiply_matrix_by_tuple() {
+        let a = Matrix4::new([
+            [1., 2., 3., 4.],
+            [2., 4., 4., 2.],
+            [8., 6., 4., 1.],
+            [0., 0., 0., 1.],
+        ]);
+        let b = Tuple(1., 2., 3., 1.);
+        assert_eq!(a * &b, Tuple(18., 24., 33., 1.));
+        assert_eq!(a * b, Tuple(18., 24., 33., 1.));
+    }
+
+    #[test]
+    fn multiply_by_identity() {
+        let a = Matrix4::new([
+            [0., 1., 2., 4.],
+            [1., 2., 4., 8.],
+            [2., 4., 8., 16.],
+            [4., 8., 16., 32.],
+        ]);
+        assert_eq!(a * Matrix4::identity(), a);
+    }
+
+    #[test]
+    fn transpose_matrix() {
+        let a = Matrix4::new([
+            [0., 9., 3., 0.],
+            [9., 8., 0., 8.],
+            [1., 8., 5., 3.],
+            [0., 0., 5., 8.],
+        ]);
+        assert_eq!(
+            a.transpose(),
+            Matrix4::new([
+                [0., 9., 1., 0.],
+                [9., 8., 8., 0.],
+                [3., 0., 5., 5.],
+                [0., 8., 3., 8.],
+            ])
+        );
+        assert_eq!(Matrix4::identity().transpose(), Matrix4::identity());
+    }
+
+    #[test]
+    fn determinant_of_2x2_matrix() {
+        let a = Matrix2::new([[1., 5.], [-3., 2.]]);
+        assert_eq!(a.determinant(), 17.);
+    }
+
+    #[test]
+    fn submatrix_of_3x3_and_4x4() {
+        let a = Matrix3::new([[1., 5., 0.], [-3., 2., 7.], [0., 6., -3.]]);
+        assert_eq!(a.submatrix(0, 2), Matrix2::new([[-3., 2.], [0., 6.]]));
+
+        let a = Matrix4::new([
+            [-6., 1., 1., 6.],
+            [-8., 5., 8., 6.],
+            [-1., 0., 8., 2.],
+            [-7., 1., -1., 1.],
+        ]);
+        assert_eq!(
+            a.submatrix(2, 1),
+            Matrix3::new([[-6., 1., 6.], [-8., 8., 6.], [-7., -1., 1.],])
+        );
+    }
+
+    #[test]
+    fn minor_and_cofactor_of_3x3_matrix() {
+        let a = Matrix3::new([[3., 5., 0.], [2., -1., -7.], [6., -1., 5.]]);
+        assert_eq!(a.submatrix(1, 0).determinant(), 25.);
+        assert_eq!(a.minor(1, 0), 25.);
+
+        assert_eq!(a.minor(0, 0), -12.);
+        assert_eq!(a.cofactor(0, 0), -12.);
+        assert_eq!(a.minor(1, 0), 25.);
+        assert_eq!(a.cofactor(1, 0), -25.);
+    }
+
+    #[test]
+    fn determinant_of_3x3_matrix() {
+        let a = Matrix3::new([[1., 2., 6.], [-5., 8., -4.], [2., 6., 4.]]);
+        assert_eq!(a.cofactor(0, 0), 56.);
+        assert_eq!(a.cofactor(0, 1), 12.);
+        assert_eq!(a.cofactor(0, 2), -46.);
+        assert_eq!(a.determinant(), -196.);
+    }
+
+    #[test]
+    fn determinant_of_4x4_matrix() {
+        let a = Matrix4::new([
+            [-2., -8., 3., 5.],
+            [-3., 1., 7., 3.],
+            [1., 2., -9., 6.],
+            [-6., 7., 7., -9.],
+        ]);
+        assert_eq!(a.cofactor(0, 0), 690.);
+        assert_eq!(a.cofactor(0, 1), 447.);
+        assert_eq!(a.cofactor(0, 2), 210.);
+        assert_eq!(a.cofactor(0, 3), 51.);
+        assert_eq!(a.determinant(), -4071.);
+    }
+
+    #[test]
+    fn test_invertibility() {
+        let a = Matrix4::new([
+            [6., 4., 4., 4.],
+            [5., 5., 7., 6.],
+            [4., -9., 3., -7.],
+            [9., 1., 7., -6.],
+        ]);
+        assert_eq!(a.determinant(), -2120.);
+        assert!(a.is_invertible());
+
+        let a = Matrix4::new([
+            [-4., 2., -2., -3.],
+            [9., 6., 2., 6.],
+            [0., -5., 1., -5.],
+            [0., 0., 0., 0.],
+        ]);
+        assert_eq!(a.determinant(), 0.);
+        assert!(!a.is_invertible());
+        assert_eq!(a.inverse(), None);
+    }
+
+    #[test]
+    fn inverse_of_matrix() {
+        let a = Matrix4::new([
+            [-5., 2., 6., -8.],
+            [1., -5., 1., 8.],
+            [7., 7., -6., -7.],
+            [1., -3., 7., 4.],
+        ]);
+        let b = a.inverse().expect("Matrix should be invertible");
+        assert_eq!(a.determinant(), 532.);
+        assert_eq!(a.cofactor(2, 3), -160.);
+        assert!(approx_eq!(f32, b.get(3, 2), -160. / 532., ulps = 2));
+        assert_eq!(a.cofactor(3, 2), 105.);
+        assert!(approx_eq!(f32, b.get(2, 3), 105. / 532., ulps = 2));
+        assert_matrix_approx_eq(
+            &b,
+            &Matrix4::new([
+                [0.21805, 0.45113, 0.24060, -0.04511],
+                [-0.80827, -1.45677, -0.44361, 0.52068],
+                [-0.07895, -0.22368, -0.05263, 0.19737],
+                [-0.52256, -0.81391, -0.30075, 0.30639],
+            ]),
+        );
+
+        let a = Matrix4::new([
+            [8., -5., 9., 2.],
+            [7., 5., 6., 1.],
+            [-6., 0., 9., 6.],
+            [-3., 0., -9., -4.],
+        ]);
+        assert_matrix_approx_eq(
+            &a.inverse().unwrap(),
+            &Matrix4::new([
+                [-0.15385, -0.15385, -0.28205, -0.53846],
+                [-0.07692, 0.12308, 0.02564, 0.03077],
+                [0.35897, 0.35897, 0.43590, 0.92308],
+                [-0.69231, -0.69231, -0.76923, -1.92308],
+            ]),
+        );
+    }
+
+    #[test]
+    fn multiply_product_by_inverse() {
+        let a = Matrix4::new([
+            [3., -9., 7., 3.],
+            [3., -8., 2., -9.],
+            [-4., 4., 4., 1.],
+            [-6., 5., -1., 1.],
+        ]);
+        let b = Matrix4::new([
+            [8., 2., 2., 2.],
+            [3., -1., 7., 0.],
+            [7., 0., 5., 4.],
+            [6., -2., 0., 5.],
+        ]);
+        let c = a * b;
+        assert_matrix_approx_eq(&(c * b.inverse().unwrap()), &a);
+    }
+
+    fn apply(m: &Matrix4, t: Tuple) -> Tuple {
+        *m * t
+    }
+
+    #[test]
+    fn translate_point_and_vector() {
+        let transform = Matrix4::translation(5., -3., 2.);
+        let p = Tuple::point(-3., 4., 5.);
+        assert_eq!(apply(&transform, p.clone()), Tuple::point(2., 1., 7.));
+
+        let inv = transform.inverse().unwrap();
+        assert_eq!(apply(&inv, p), Tuple::point(-8., 7., 3.));
+
+        let v = Tuple::vector(-3., 4., 5.);
+        assert_eq!(apply(&transform, v.clone()), v);
+    }
+
+    #[test]
+    fn scale_point_and_vector() {
+        let transform = Matrix4::scaling(2., 3., 4.);
+        assert_eq!(
+            apply(&transform, Tuple::point(-4., 6., 8.)),
+            Tuple::point(-8., 18., 32.)
+        );
+        assert_eq!(
+            apply(&transform, Tuple::vector(-4., 6., 8.)),
+            Tuple::vector(-8., 18., 32.)
+        );
+
+        let inv = transform.inverse().unwrap();
+        assert_eq!(
+            apply(&inv, Tuple::vector(-4., 6., 8.)),
+            Tuple::vector(-2., 2., 2.)
+        );
+
+        let reflection = Matrix4::scaling(-1., 1., 1.);
+        assert_eq!(
+            apply(&reflection, Tuple::point(2., 3., 4.)),
+            Tuple::point(-2., 3., 4.)
+        );
+    }
+
+    #[test]
+    fn rotate_point_around_axes() {
+        let half_quarter = std::f32::consts::FRAC_PI_4;
+        let full_quarter = std::f32::consts::FRAC_PI_2;
+        let v = 2_f32.sqrt() / 2.;
+
+        let p = Tuple::point(0., 1., 0.);
+        assert!(
+            apply(&Matrix4::rotation_x(half_quarter), p.clone()).approx_eq(&Tuple::point(0., v, v))
+        );
+        assert!(apply(&Matrix4::rotation_x(full_quarter), p.clone())
+            .approx_eq(&Tuple::point(0., 0., 1.)));
+        let inv = Matrix4::rotation_x(half_quarter).inverse().unwrap();
+        assert!(apply(&inv, p).approx_eq(&Tuple::point(0., v, -v)));
+
+        let p = Tuple::point(0., 0., 1.);
+        assert!(
+            apply(&Matrix4::rotation_y(half_quarter), p.clone()).approx_eq(&Tuple::point(v, 0., v))
+        );
+        assert!(apply(&Matrix4::rotation_y(full_quarter), p).approx_eq(&Tuple::point(1., 0., 0.)));
+
+        let p = Tuple::point(0., 1., 0.);
+        assert!(apply(&Matrix4::rotation_z(half_quarter), p.clone())
+            .approx_eq(&Tuple::point(-v, v, 0.)));
+        assert!(apply(&Matrix4::rotation_z(full_quarter), p).approx_eq(&Tuple::point(-1., 0., 0.)));
+    }
+
+    #[test]
+    fn shear_point() {
+        let p = Tuple::point(2., 3., 4.);
+        let cases = [
+            ((1., 0., 0., 0., 0., 0.), Tuple::point(5., 3., 4.)),
+            ((0., 1., 0., 0., 0., 0.), Tuple::point(6., 3., 4.)),
+            ((0., 0., 1., 0., 0., 0.), Tuple::point(2., 5., 4.)),
+            ((0., 0., 0., 1., 0., 0.), Tuple::point(2., 7., 4.)),
+            ((0., 0., 0., 0., 1., 0.), Tuple::point(2., 3., 6.)),
+            ((0., 0., 0., 0., 0., 1.), Tuple::point(2., 3., 7.)),
+        ];
+        for ((xy, xz, yx, yz, zx, zy), expected) in cases {
+            let transform = Matrix4::shearing(xy, xz, yx, yz, zx, zy);
+            assert_eq!(apply(&transform, p.clone()), expected);
+        }
+    }
+
+    #[test]
+    fn view_transform_for_default_orientation() {
+        let t = Matrix4::view_transform(
+            &Tuple::point(0., 0., 0.),
+            &Tuple::point(0., 0., -1.),
+            &Tuple::vector(0., 1., 0.),
+        );
+        assert_eq!(t, Matrix4::identity());
+    }
+
+    #[test]
+    fn view_transform_looking_in_positive_z() {
+        let t = Matrix4::view_transform(
+            &Tuple::point(0., 0., 0.),
+            &Tuple::point(0., 0., 1.),
+            &Tuple::vector(0., 1., 0.),
+        );
+        assert_eq!(t, Matrix4::scaling(-1., 1., -1.));
+    }
+
+    #[test]
+    fn view_transform_moves_the_world() {
+        let t = Matrix4::view_transform(
+            &Tuple::point(0., 0., 8.),
+            &Tuple::point(0., 0., 0.),
+            &Tuple::vector(0., 1., 0.),
+        );
+        assert_eq!(t, Matrix4::translation(0., 0., -8.));
+    }
+
+    #[test]
+    fn arbitrary_view_transform() {
+        let t = Matrix4::view_transform(
+            &Tuple::point(1., 3., 2.),
+            &Tuple::point(4., -2., 8.),
+            &Tuple::vector(1., 1., 0.),
+        );
+        let expected = Matrix4::new([
+            [-0.50709, 0.50709, 0.67612, -2.36643],
+            [0.76772, 0.60609, 0.12122, -2.82843],
+            [-0.35857, 0.59761, -0.71714, 0.],
+            [0., 0., 0., 1.],
+        ]);
+        assert_matrix_approx_eq(&t, &expected);
+    }
+}
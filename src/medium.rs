@@ -0,0 +1,123 @@
+use crate::shape::Shape;
+
+// Objects a ray is travelling through, innermost last. Secondary rays inherit
+// the stack of the ray that spawned them, so the refractive indices on both
+// sides of a surface don't depend on which intersections a ray happens to see.
+#[derive(Debug, Clone, Default)]
+pub struct MediumStack<'a> {
+    objects: Vec<&'a dyn Shape>,
+}
+
+impl<'a> MediumStack<'a> {
+    pub fn new() -> Self {
+        MediumStack { objects: vec![] }
+    }
+
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
+    pub fn contains(&self, object: &dyn Shape) -> bool {
+        self.objects.iter().any(|o| std::ptr::addr_eq(*o, object))
+    }
+
+    pub fn innermost(&self) -> Option<&'a dyn Shape> {
+        self.objects.last().copied()
+    }
+
+    // Index of the innermost medium, vacuum/air outside of everything
+    pub fn refractive_index(&self) -> f32 {
+        self.objects
+            .last()
+            .map_or(1., |object| object.get_material().refractive_index)
+    }
+
+    // Passing through the object's surface leaves it if the ray was inside it
+    // and enters it otherwise
+    pub fn cross(&mut self, object: &'a dyn Shape) {
+        match self
+            .objects
+            .iter()
+            .position(|o| std::ptr::addr_eq(*o, object))
+        {
+            Some(index) => {
+                self.objects.remove(index);
+            }
+            None => self.objects.push(object),
+        }
+    }
+
+    pub fn crossing(&self, object: &'a dyn Shape) -> MediumStack<'a> {
+        let mut media = self.clone();
+        media.cross(object);
+        media
+    }
+}
+
+// Stacks are the same if they hold the very same objects in the same order
+impl<'a> PartialEq for MediumStack<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        self.objects.len() == other.objects.len()
+            && self
+                .objects
+                .iter()
+                .zip(&other.objects)
+                .all(|(a, b)| std::ptr::addr_eq(*a, *b))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sphere::Sphere;
+
+    #[test]
+    fn empty_stack_is_air() {
+        let media = MediumStack::new();
+        assert!(media.is_empty());
+        assert_eq!(media.refractive_index(), 1.);
+    }
+
+    #[test]
+    fn crossing_enters_and_leaves_objects() {
+        let glass = Sphere::glass();
+        let mut water = Sphere::glass();
+        water.get_material_mut().refractive_index = 1.333;
+
+        let mut media = MediumStack::new();
+        media.cross(&water);
+        media.cross(&glass);
+        assert_eq!(media.len(), 2);
+        assert_eq!(media.refractive_index(), 1.5);
+        assert!(std::ptr::addr_eq(media.innermost().unwrap(), &glass));
+
+        // Leaving the outer object out of order keeps the inner one on top
+        let left_water = media.crossing(&water);
+        assert_eq!(left_water.len(), 1);
+        assert!(!left_water.contains(&water));
+        assert_eq!(left_water.refractive_index(), 1.5);
+
+        let left_glass = media.crossing(&glass);
+        assert!(left_glass.contains(&water));
+        assert_eq!(left_glass.refractive_index(), 1.333);
+        assert_eq!(media.len(), 2);
+    }
+
+    #[test]
+    fn stacks_compare_by_identity() {
+        let a = Sphere::glass();
+        let b = Sphere::glass();
+        assert_eq!(
+            MediumStack::new().crossing(&a),
+            MediumStack::new().crossing(&a)
+        );
+        assert_ne!(
+            MediumStack::new().crossing(&a),
+            MediumStack::new().crossing(&b)
+        );
+    }
+}
@@ -0,0 +1,314 @@
+use std::fs;
+use std::path::Path;
+
+use crate::group::Group;
+use crate::shape::Shape;
+use crate::smooth_triangle::SmoothTriangle;
+use crate::triangle::Triangle;
+use crate::tuple::Tuple;
+
+// Parsed Wavefront OBJ file, vertices and normals are 1-indexed like in the file.
+// Faces are fan triangulated and collected per named group.
+#[derive(Debug)]
+pub struct ObjFile {
+    vertices: Vec<Tuple>,
+    normals: Vec<Tuple>,
+    default_group: Group,
+    groups: Vec<(String, Group)>,
+    ignored: usize,
+}
+
+impl ObjFile {
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let mut obj = ObjFile {
+            vertices: vec![],
+            normals: vec![],
+            default_group: Group::new(),
+            groups: vec![],
+            ignored: 0,
+        };
+        let mut current: Option<usize> = None;
+
+        for (index, line) in input.lines().enumerate() {
+            let line_number = index + 1;
+            let mut tokens = line.split_whitespace();
+            match tokens.next() {
+                Some("v") => {
+                    let (x, y, z) = parse_xyz(tokens, line_number)?;
+                    obj.vertices.push(Tuple::point(x, y, z));
+                }
+                Some("vn") => {
+                    let (x, y, z) = parse_xyz(tokens, line_number)?;
+                    obj.normals.push(Tuple::vector(x, y, z));
+                }
+                Some("f") => {
+                    let corners = tokens
+                        .map(|token| obj.parse_corner(token, line_number))
+                        .collect::<Result<Vec<_>, String>>()?;
+                    if corners.len() < 3 {
+                        return Err(format!("line {}: face needs 3 vertices", line_number));
+                    }
+                    let group = match current {
+                        Some(index) => &mut obj.groups[index].1,
+                        None => &mut obj.default_group,
+                    };
+                    for triangle in fan_triangulation(&corners) {
+                        group.add_child(triangle);
+                    }
+                }
+                Some("g") => {
+                    let name = tokens.collect::<Vec<_>>().join(" ");
+                    current = match obj.groups.iter().position(|(n, _)| *n == name) {
+                        Some(index) => Some(index),
+                        None => {
+                            obj.groups.push((name, Group::new()));
+                            Some(obj.groups.len() - 1)
+                        }
+                    };
+                }
+                None => {}
+                Some(_) => obj.ignored += 1,
+            }
+        }
+
+        Ok(obj)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let input = fs::read_to_string(path)
+            .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+        Self::parse(&input)
+    }
+
+    pub fn get_vertex(&self, index: usize) -> Option<&Tuple> {
+        self.vertices.get(index.checked_sub(1)?)
+    }
+
+    pub fn get_normal(&self, index: usize) -> Option<&Tuple> {
+        self.normals.get(index.checked_sub(1)?)
+    }
+
+    pub fn get_ignored(&self) -> usize {
+        self.ignored
+    }
+
+    pub fn get_default_group(&self) -> &Group {
+        &self.default_group
+    }
+
+    pub fn get_group(&self, name: &str) -> Option<&Group> {
+        self.groups
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, group)| group)
+    }
+
+    // Named groups become child groups next to the ungrouped triangles
+    pub fn into_group(self) -> Group {
+        let mut group = self.default_group;
+        for (_, named) in self.groups {
+            group.add_child(Box::new(named));
+        }
+        group
+    }
+
+    // Corners look like "v", "v/vt" or "v/vt/vn", texture coordinates are skipped
+    fn parse_corner(
+        &self,
+        token: &str,
+        line_number: usize,
+    ) -> Result<(Tuple, Option<Tuple>), String> {
+        let mut parts = token.split('/');
+        let vertex = parts
+            .next()
+            .and_then(|index| index.parse().ok())
+            .and_then(|index| self.get_vertex(index))
+            .ok_or(format!("line {}: invalid vertex '{}'", line_number, token))?;
+        let normal = match parts.nth(1) {
+            Some(index) if !index.is_empty() => Some(
+                index
+                    .parse()
+                    .ok()
+                    .and_then(|index| self.get_normal(index))
+                    .ok_or(format!("line {}: invalid normal '{}'", line_number, token))?,
+            ),
+            _ => None,
+        };
+        Ok((vertex.clone(), normal.cloned()))
+    }
+}
+
+fn parse_xyz<'a>(
+    tokens: impl Iterator<Item = &'a str>,
+    line_number: usize,
+) -> Result<(f32, f32, f32), String> {
+    let values = tokens
+        .take(3)
+        .map(|token| token.parse::<f32>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("line {}: {}", line_number, e))?;
+    match values[..] {
+        [x, y, z] => Ok((x, y, z)),
+        _ => Err(format!("line {}: expected 3 coordinates", line_number)),
+    }
+}
+
+// Faces are assumed to be convex polygons, so a fan around the first vertex covers them
+fn fan_triangulation(corners: &[(Tuple, Option<Tuple>)]) -> Vec<Box<dyn Shape>> {
+    let (p1, n1) = &corners[0];
+    corners[1..]
+        .windows(2)
+        .map(|pair| -> Box<dyn Shape> {
+            let (p2, n2) = &pair[0];
+            let (p3, n3) = &pair[1];
+            match (n1, n2, n3) {
+                (Some(n1), Some(n2), Some(n3)) => Box::new(SmoothTriangle::new(
+                    p1.clone(),
+                    p2.clone(),
+                    p3.clone(),
+                    n1.clone(),
+                    n2.clone(),
+                    n3.clone(),
+                )),
+                _ => Box::new(Triangle::new(p1.clone(), p2.clone(), p3.clone())),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ignore_unrecognized_lines() {
+        let gibberish = "There was a young lady named Bright\n\
+                         who traveled much faster than light.\n\
+                         She set out one day\n\
+                         in a relative way,\n\
+                         and came back the previous night.";
+        let obj = ObjFile::parse(gibberish).unwrap();
+        assert_eq!(obj.get_ignored(), 5);
+    }
+
+    #[test]
+    fn parse_vertex_records() {
+        let obj = ObjFile::parse("v -1 1 0\nv -1.0000 0.5000 0.0000\nv 1 0 0\nv 1 1 0").unwrap();
+        assert_eq!(obj.get_vertex(1), Some(&Tuple::point(-1., 1., 0.)));
+        assert_eq!(obj.get_vertex(2), Some(&Tuple::point(-1., 0.5, 0.)));
+        assert_eq!(obj.get_vertex(3), Some(&Tuple::point(1., 0., 0.)));
+        assert_eq!(obj.get_vertex(4), Some(&Tuple::point(1., 1., 0.)));
+        assert_eq!(obj.get_vertex(0), None);
+    }
+
+    // Shapes can't be downcast, but their Debug output covers every field
+    fn assert_shape_eq(shape: &dyn Shape, expected: &dyn Shape) {
+        assert_eq!(format!("{:?}", shape), format!("{:?}", expected));
+    }
+
+    fn vertex(obj: &ObjFile, index: usize) -> Tuple {
+        obj.get_vertex(index).unwrap().clone()
+    }
+
+    #[test]
+    fn parse_triangle_faces() {
+        let obj =
+            ObjFile::parse("v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\n\nf 1 2 3\nf 1 3 4").unwrap();
+        let children = obj.get_default_group().get_children();
+        assert_eq!(children.len(), 2);
+        assert_shape_eq(
+            children[0].as_ref(),
+            &Triangle::new(vertex(&obj, 1), vertex(&obj, 2), vertex(&obj, 3)),
+        );
+        assert_shape_eq(
+            children[1].as_ref(),
+            &Triangle::new(vertex(&obj, 1), vertex(&obj, 3), vertex(&obj, 4)),
+        );
+    }
+
+    #[test]
+    fn triangulate_polygons() {
+        let obj =
+            ObjFile::parse("v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\nv 0 2 0\n\nf 1 2 3 4 5").unwrap();
+        let children = obj.get_default_group().get_children();
+        assert_eq!(children.len(), 3);
+        for (child, (b, c)) in children.iter().zip([(2, 3), (3, 4), (4, 5)]) {
+            assert_shape_eq(
+                child.as_ref(),
+                &Triangle::new(vertex(&obj, 1), vertex(&obj, b), vertex(&obj, c)),
+            );
+        }
+    }
+
+    #[test]
+    fn triangles_in_named_groups() {
+        let obj = ObjFile::parse(
+            "v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\n\
+             g FirstGroup\nf 1 2 3\ng SecondGroup\nf 1 3 4",
+        )
+        .unwrap();
+        assert!(obj.get_default_group().is_empty());
+        let first = obj.get_group("FirstGroup").unwrap();
+        let second = obj.get_group("SecondGroup").unwrap();
+        assert_shape_eq(
+            first.get_children()[0].as_ref(),
+            &Triangle::new(vertex(&obj, 1), vertex(&obj, 2), vertex(&obj, 3)),
+        );
+        assert_shape_eq(
+            second.get_children()[0].as_ref(),
+            &Triangle::new(vertex(&obj, 1), vertex(&obj, 3), vertex(&obj, 4)),
+        );
+        assert!(obj.get_group("ThirdGroup").is_none());
+    }
+
+    #[test]
+    fn convert_obj_file_to_group() {
+        let obj = ObjFile::parse(
+            "v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\n\
+             f 1 2 3\ng FirstGroup\nf 1 2 3\ng SecondGroup\nf 1 3 4\ng FirstGroup\nf 1 3 4",
+        )
+        .unwrap();
+        let g = obj.into_group();
+        let children = g.get_children();
+        assert_eq!(children.len(), 3);
+        assert_eq!(children[1].get_children().len(), 2);
+        assert_eq!(children[2].get_children().len(), 1);
+    }
+
+    #[test]
+    fn parse_vertex_normals() {
+        let obj = ObjFile::parse("vn 0 0 1\nvn 0.707 0 -0.707\nvn 1 2 3").unwrap();
+        assert_eq!(obj.get_normal(1), Some(&Tuple::vector(0., 0., 1.)));
+        assert_eq!(obj.get_normal(2), Some(&Tuple::vector(0.707, 0., -0.707)));
+        assert_eq!(obj.get_normal(3), Some(&Tuple::vector(1., 2., 3.)));
+    }
+
+    #[test]
+    fn faces_with_normals() {
+        let obj = ObjFile::parse(
+            "v 0 1 0\nv -1 0 0\nv 1 0 0\n\nvn -1 0 0\nvn 1 0 0\nvn 0 1 0\n\n\
+             f 1//3 2//1 3//2\nf 1/0/3 2/102/1 3/14/2",
+        )
+        .unwrap();
+        let expected = SmoothTriangle::new(
+            vertex(&obj, 1),
+            vertex(&obj, 2),
+            vertex(&obj, 3),
+            obj.get_normal(3).unwrap().clone(),
+            obj.get_normal(1).unwrap().clone(),
+            obj.get_normal(2).unwrap().clone(),
+        );
+        let children = obj.get_default_group().get_children();
+        assert_shape_eq(children[0].as_ref(), &expected);
+        assert_shape_eq(children[1].as_ref(), &expected);
+    }
+
+    #[test]
+    fn reject_malformed_input() {
+        assert!(ObjFile::parse("v 1 2").is_err());
+        assert!(ObjFile::parse("v 1 x 3").is_err());
+        assert!(ObjFile::parse("v 0 0 0\nv 1 0 0\nf 1 2").is_err());
+        assert!(ObjFile::parse("v 0 0 0\nv 1 0 0\nf 1 2 3").is_err());
+        assert!(ObjFile::parse("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1//1 2//1 3//1").is_err());
+    }
+}
@@ -0,0 +1,369 @@
+use std::fmt;
+
+use crate::matrix::Matrix4;
+use crate::shape::Shape;
+use crate::tuple::Tuple;
+
+// Transform every pattern carries, relative to the object it's applied to
+#[derive(PartialEq, Debug, Clone)]
+pub struct PatternBase {
+    transform: Matrix4,
+    inverse_transform: Matrix4,
+}
+
+impl PatternBase {
+    pub fn new() -> Self {
+        PatternBase {
+            transform: Matrix4::identity(),
+            inverse_transform: Matrix4::identity(),
+        }
+    }
+
+    pub fn get_transform(&self) -> &Matrix4 {
+        &self.transform
+    }
+
+    pub fn get_inverse_transform(&self) -> &Matrix4 {
+        &self.inverse_transform
+    }
+
+    pub fn set_transform(&mut self, transform: Matrix4) -> Result<(), String> {
+        self.inverse_transform = transform.inverse().ok_or("transform is not invertible")?;
+        self.transform = transform;
+        Ok(())
+    }
+}
+
+impl Default for PatternBase {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub trait Pattern: fmt::Debug + Send + Sync {
+    fn get_base(&self) -> &PatternBase;
+    fn get_base_mut(&mut self) -> &mut PatternBase;
+
+    // Operates in pattern space, i.e. with object and pattern transforms undone
+    fn local_pattern_at(&self, point: &Tuple) -> Tuple;
+
+    fn get_transform(&self) -> &Matrix4 {
+        self.get_base().get_transform()
+    }
+
+    fn set_transform(&mut self, transform: Matrix4) -> Result<(), String> {
+        self.get_base_mut().set_transform(transform)
+    }
+
+    fn pattern_at_shape(&self, object: &dyn Shape, world_point: &Tuple) -> Tuple {
+        let object_point = *object.get_base().get_world_inverse_transform() * world_point;
+        let pattern_point = *self.get_base().get_inverse_transform() * object_point;
+        self.local_pattern_at(&pattern_point)
+    }
+}
+
+// Materials are compared by which pattern they share, like intersections compare objects
+impl PartialEq for dyn Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::addr_eq(self, other)
+    }
+}
+
+fn is_even(value: f32) -> bool {
+    (value.floor() as i64).rem_euclid(2) == 0
+}
+
+// Alternates between a and b every unit along x
+#[derive(PartialEq, Debug, Clone)]
+pub struct StripePattern {
+    base: PatternBase,
+    pub a: Tuple,
+    pub b: Tuple,
+}
+
+impl StripePattern {
+    pub fn new(a: Tuple, b: Tuple) -> Self {
+        StripePattern {
+            base: PatternBase::new(),
+            a,
+            b,
+        }
+    }
+}
+
+impl Pattern for StripePattern {
+    fn get_base(&self) -> &PatternBase {
+        &self.base
+    }
+
+    fn get_base_mut(&mut self) -> &mut PatternBase {
+        &mut self.base
+    }
+
+    fn local_pattern_at(&self, point: &Tuple) -> Tuple {
+        if is_even(point.0) {
+            self.a.clone()
+        } else {
+            self.b.clone()
+        }
+    }
+}
+
+// Blends linearly from a to b across every unit along x
+#[derive(PartialEq, Debug, Clone)]
+pub struct GradientPattern {
+    base: PatternBase,
+    pub a: Tuple,
+    pub b: Tuple,
+}
+
+impl GradientPattern {
+    pub fn new(a: Tuple, b: Tuple) -> Self {
+        GradientPattern {
+            base: PatternBase::new(),
+            a,
+            b,
+        }
+    }
+}
+
+impl Pattern for GradientPattern {
+    fn get_base(&self) -> &PatternBase {
+        &self.base
+    }
+
+    fn get_base_mut(&mut self) -> &mut PatternBase {
+        &mut self.base
+    }
+
+    fn local_pattern_at(&self, point: &Tuple) -> Tuple {
+        let fraction = point.0 - point.0.floor();
+        let color = self.a.clone() + (self.b.clone() - self.a.clone()) * fraction;
+        Tuple::color(color.0, color.1, color.2)
+    }
+}
+
+// Concentric rings around the y axis, alternating every unit of distance
+#[derive(PartialEq, Debug, Clone)]
+pub struct RingPattern {
+    base: PatternBase,
+    pub a: Tuple,
+    pub b: Tuple,
+}
+
+impl RingPattern {
+    pub fn new(a: Tuple, b: Tuple) -> Self {
+        RingPattern {
+            base: PatternBase::new(),
+            a,
+            b,
+        }
+    }
+}
+
+impl Pattern for RingPattern {
+    fn get_base(&self) -> &PatternBase {
+        &self.base
+    }
+
+    fn get_base_mut(&mut self) -> &mut PatternBase {
+        &mut self.base
+    }
+
+    fn local_pattern_at(&self, point: &Tuple) -> Tuple {
+        if is_even((point.0.powf(2.) + point.2.powf(2.)).sqrt()) {
+            self.a.clone()
+        } else {
+            self.b.clone()
+        }
+    }
+}
+
+// Unit cubes alternating between a and b in all three dimensions
+#[derive(PartialEq, Debug, Clone)]
+pub struct CheckersPattern {
+    base: PatternBase,
+    pub a: Tuple,
+    pub b: Tuple,
+}
+
+impl CheckersPattern {
+    pub fn new(a: Tuple, b: Tuple) -> Self {
+        CheckersPattern {
+            base: PatternBase::new(),
+            a,
+            b,
+        }
+    }
+}
+
+impl Pattern for CheckersPattern {
+    fn get_base(&self) -> &PatternBase {
+        &self.base
+    }
+
+    fn get_base_mut(&mut self) -> &mut PatternBase {
+        &mut self.base
+    }
+
+    fn local_pattern_at(&self, point: &Tuple) -> Tuple {
+        if is_even(point.0.floor() + point.1.floor() + point.2.floor()) {
+            self.a.clone()
+        } else {
+            self.b.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sphere::Sphere;
+
+    fn white() -> Tuple {
+        Tuple::color(1., 1., 1.)
+    }
+
+    fn black() -> Tuple {
+        Tuple::color(0., 0., 0.)
+    }
+
+    #[derive(Debug)]
+    struct TestPattern {
+        base: PatternBase,
+    }
+
+    impl Pattern for TestPattern {
+        fn get_base(&self) -> &PatternBase {
+            &self.base
+        }
+
+        fn get_base_mut(&mut self) -> &mut PatternBase {
+            &mut self.base
+        }
+
+        fn local_pattern_at(&self, point: &Tuple) -> Tuple {
+            Tuple::color(point.0, point.1, point.2)
+        }
+    }
+
+    fn test_pattern() -> TestPattern {
+        TestPattern {
+            base: PatternBase::new(),
+        }
+    }
+
+    #[test]
+    fn default_pattern_transformation() {
+        assert_eq!(test_pattern().get_transform(), &Matrix4::identity());
+    }
+
+    #[test]
+    fn assign_pattern_transformation() {
+        let mut p = test_pattern();
+        p.set_transform(Matrix4::translation(1., 2., 3.)).unwrap();
+        assert_eq!(p.get_transform(), &Matrix4::translation(1., 2., 3.));
+        assert!(p.set_transform(Matrix4::scaling(0., 1., 1.)).is_err());
+    }
+
+    #[test]
+    fn pattern_with_object_transformation() {
+        let mut shape = Sphere::new();
+        shape.set_transform(Matrix4::scaling(2., 2., 2.)).unwrap();
+        let c = test_pattern().pattern_at_shape(&shape, &Tuple::point(2., 3., 4.));
+        assert_eq!(c, Tuple::color(1., 1.5, 2.));
+    }
+
+    #[test]
+    fn pattern_with_pattern_transformation() {
+        let shape = Sphere::new();
+        let mut p = test_pattern();
+        p.set_transform(Matrix4::scaling(2., 2., 2.)).unwrap();
+        let c = p.pattern_at_shape(&shape, &Tuple::point(2., 3., 4.));
+        assert_eq!(c, Tuple::color(1., 1.5, 2.));
+    }
+
+    #[test]
+    fn pattern_with_object_and_pattern_transformation() {
+        let mut shape = Sphere::new();
+        shape.set_transform(Matrix4::scaling(2., 2., 2.)).unwrap();
+        let mut p = test_pattern();
+        p.set_transform(Matrix4::translation(0.5, 1., 1.5)).unwrap();
+        let c = p.pattern_at_shape(&shape, &Tuple::point(2.5, 3., 3.5));
+        assert_eq!(c, Tuple::color(0.75, 0.5, 0.25));
+    }
+
+    #[test]
+    fn stripe_pattern_is_constant_in_y_and_z() {
+        let p = StripePattern::new(white(), black());
+        assert_eq!(p.local_pattern_at(&Tuple::point(0., 0., 0.)), white());
+        assert_eq!(p.local_pattern_at(&Tuple::point(0., 1., 0.)), white());
+        assert_eq!(p.local_pattern_at(&Tuple::point(0., 2., 0.)), white());
+        assert_eq!(p.local_pattern_at(&Tuple::point(0., 0., 1.)), white());
+        assert_eq!(p.local_pattern_at(&Tuple::point(0., 0., 2.)), white());
+    }
+
+    #[test]
+    fn stripe_pattern_alternates_in_x() {
+        let p = StripePattern::new(white(), black());
+        let cases = [
+            (0., white()),
+            (0.9, white()),
+            (1., black()),
+            (-0.1, black()),
+            (-1., black()),
+            (-1.1, white()),
+        ];
+        for (x, expected) in cases {
+            assert_eq!(p.local_pattern_at(&Tuple::point(x, 0., 0.)), expected);
+        }
+    }
+
+    #[test]
+    fn gradient_linearly_interpolates_between_colors() {
+        let p = GradientPattern::new(white(), black());
+        let cases = [
+            (0., white()),
+            (0.25, Tuple::color(0.75, 0.75, 0.75)),
+            (0.5, Tuple::color(0.5, 0.5, 0.5)),
+            (0.75, Tuple::color(0.25, 0.25, 0.25)),
+        ];
+        for (x, expected) in cases {
+            assert_eq!(p.local_pattern_at(&Tuple::point(x, 0., 0.)), expected);
+        }
+    }
+
+    #[test]
+    fn ring_extends_in_x_and_z() {
+        let p = RingPattern::new(white(), black());
+        assert_eq!(p.local_pattern_at(&Tuple::point(0., 0., 0.)), white());
+        assert_eq!(p.local_pattern_at(&Tuple::point(1., 0., 0.)), black());
+        assert_eq!(p.local_pattern_at(&Tuple::point(0., 0., 1.)), black());
+        // 0.708 = just slightly more than sqrt(2)/2
+        assert_eq!(p.local_pattern_at(&Tuple::point(0.708, 0., 0.708)), black());
+    }
+
+    #[test]
+    fn checkers_repeat_in_every_dimension() {
+        let p = CheckersPattern::new(white(), black());
+        let cases = [
+            ((0.99, 0., 0.), white()),
+            ((1.01, 0., 0.), black()),
+            ((0., 0.99, 0.), white()),
+            ((0., 1.01, 0.), black()),
+            ((0., 0., 0.99), white()),
+            ((0., 0., 1.01), black()),
+        ];
+        for ((x, y, z), expected) in cases {
+            assert_eq!(p.local_pattern_at(&Tuple::point(x, y, z)), expected);
+        }
+    }
+
+    #[test]
+    fn patterns_compare_by_identity() {
+        let a: &dyn Pattern = &StripePattern::new(white(), black());
+        let b: &dyn Pattern = &StripePattern::new(white(), black());
+        assert!(a == a);
+        assert!(a != b);
+    }
+}
@@ -0,0 +1,122 @@
+use crate::bounds::BoundingBox;
+use crate::intersection::Intersection;
+use crate::ray::Ray;
+use crate::shape::{Shape, ShapeBase};
+use crate::tuple::Tuple;
+use crate::EPSILON;
+
+// The xz plane, extending infinitely in both directions
+#[derive(PartialEq, Debug, Clone)]
+pub struct Plane {
+    base: ShapeBase,
+}
+
+impl Plane {
+    pub fn new() -> Self {
+        Plane {
+            base: ShapeBase::new(),
+        }
+    }
+}
+
+impl Default for Plane {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shape for Plane {
+    fn get_base(&self) -> &ShapeBase {
+        &self.base
+    }
+
+    fn get_base_mut(&mut self) -> &mut ShapeBase {
+        &mut self.base
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        // Rays parallel to the plane never hit it, coplanar ones are treated as misses
+        if ray.direction.1.abs() < EPSILON {
+            return vec![];
+        }
+        let t = -ray.origin.1 / ray.direction.1;
+        vec![Intersection::new(t, self)]
+    }
+
+    fn local_normal_at(&self, _point: &Tuple) -> Tuple {
+        Tuple::vector(0., 1., 0.)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(
+            Tuple::point(f32::NEG_INFINITY, 0., f32::NEG_INFINITY),
+            Tuple::point(f32::INFINITY, 0., f32::INFINITY),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normal_of_plane_is_constant() {
+        let p = Plane::new();
+        assert_eq!(
+            p.local_normal_at(&Tuple::point(0., 0., 0.)),
+            Tuple::vector(0., 1., 0.)
+        );
+        assert_eq!(
+            p.local_normal_at(&Tuple::point(10., 0., -10.)),
+            Tuple::vector(0., 1., 0.)
+        );
+        assert_eq!(
+            p.local_normal_at(&Tuple::point(-5., 0., 150.)),
+            Tuple::vector(0., 1., 0.)
+        );
+    }
+
+    #[test]
+    fn intersect_with_parallel_ray() {
+        let p = Plane::new();
+        let r = Ray::new(Tuple::point(0., 10., 0.), Tuple::vector(0., 0., 1.));
+        assert!(p.local_intersect(&r).is_empty());
+    }
+
+    #[test]
+    fn intersect_with_coplanar_ray() {
+        let p = Plane::new();
+        let r = Ray::new(Tuple::point(0., 0., 0.), Tuple::vector(0., 0., 1.));
+        assert!(p.local_intersect(&r).is_empty());
+    }
+
+    #[test]
+    fn ray_intersecting_plane_from_above() {
+        let p = Plane::new();
+        let r = Ray::new(Tuple::point(0., 1., 0.), Tuple::vector(0., -1., 0.));
+        let xs = p.local_intersect(&r);
+        assert_eq!(xs.len(), 1);
+        assert_eq!(xs[0].t, 1.);
+        assert!(std::ptr::addr_eq(xs[0].object, &p));
+    }
+
+    #[test]
+    fn ray_intersecting_plane_from_below() {
+        let p = Plane::new();
+        let r = Ray::new(Tuple::point(0., -1., 0.), Tuple::vector(0., 1., 0.));
+        let xs = p.local_intersect(&r);
+        assert_eq!(xs.len(), 1);
+        assert_eq!(xs[0].t, 1.);
+        assert!(std::ptr::addr_eq(xs[0].object, &p));
+    }
+
+    #[test]
+    fn plane_has_bounding_box() {
+        let b = Plane::new().bounds();
+        assert_eq!(
+            b.min,
+            Tuple::point(f32::NEG_INFINITY, 0., f32::NEG_INFINITY)
+        );
+        assert_eq!(b.max, Tuple::point(f32::INFINITY, 0., f32::INFINITY));
+    }
+}
@@ -0,0 +1,147 @@
+use crate::bounds::BoundingBox;
+use crate::intersection::Intersection;
+use crate::matrix::Matrix4;
+use crate::ray::Ray;
+use crate::shape::{Shape, ShapeBase};
+use crate::tuple::Tuple;
+use crate::EPSILON;
+
+// Square from -1 to 1 in the xz plane. Rays hitting a linked portal carry on
+// from the same spot on its partner, moving the same way relative to it, so
+// going in through the front of one comes out of the back of the other. An
+// unlinked portal is shaded like any other surface.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Portal {
+    base: ShapeBase,
+    // World transform of the partner when it was linked, moving either portal
+    // or a group holding it needs the portals to be linked again
+    target: Option<Matrix4>,
+}
+
+impl Portal {
+    pub fn new() -> Self {
+        Portal {
+            base: ShapeBase::new(),
+            target: None,
+        }
+    }
+
+    // One way link, rays entering this portal come out of the other
+    pub fn link_to(&mut self, other: &Portal) {
+        self.target = other.get_base().get_world_inverse_transform().inverse();
+    }
+
+    pub fn link(a: &mut Portal, b: &mut Portal) {
+        a.link_to(b);
+        b.link_to(a);
+    }
+
+    pub fn get_target(&self) -> Option<&Matrix4> {
+        self.target.as_ref()
+    }
+}
+
+impl Default for Portal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shape for Portal {
+    fn get_base(&self) -> &ShapeBase {
+        &self.base
+    }
+
+    fn get_base_mut(&mut self) -> &mut ShapeBase {
+        &mut self.base
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        if ray.direction.1.abs() < EPSILON {
+            return vec![];
+        }
+        let t = -ray.origin.1 / ray.direction.1;
+        let x = ray.origin.0 + t * ray.direction.0;
+        let z = ray.origin.2 + t * ray.direction.2;
+        if x.abs() > 1. || z.abs() > 1. {
+            return vec![];
+        }
+        vec![Intersection::new(t, self)]
+    }
+
+    fn local_normal_at(&self, _point: &Tuple) -> Tuple {
+        Tuple::vector(0., 1., 0.)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(Tuple::point(-1., 0., -1.), Tuple::point(1., 0., 1.))
+    }
+
+    fn get_portal_exit(&self) -> Option<Matrix4> {
+        self.target
+            .map(|target| target * *self.get_base().get_world_inverse_transform())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ray_hits_inside_the_square() {
+        let p = Portal::new();
+        let r = Ray::new(Tuple::point(0.5, 1., -0.5), Tuple::vector(0., -1., 0.));
+        let xs = p.local_intersect(&r);
+        assert_eq!(xs.len(), 1);
+        assert_eq!(xs[0].t, 1.);
+    }
+
+    #[test]
+    fn ray_misses_outside_the_square() {
+        let p = Portal::new();
+        let cases = [
+            Ray::new(Tuple::point(1.5, 1., 0.), Tuple::vector(0., -1., 0.)),
+            Ray::new(Tuple::point(0., 1., -1.5), Tuple::vector(0., -1., 0.)),
+            Ray::new(Tuple::point(0., 1., 0.), Tuple::vector(0., 0., 1.)),
+        ];
+        for r in cases {
+            assert!(p.local_intersect(&r).is_empty());
+        }
+    }
+
+    #[test]
+    fn unlinked_portal_has_no_exit() {
+        assert_eq!(Portal::new().get_portal_exit(), None);
+        assert_eq!(Portal::new().get_target(), None);
+    }
+
+    #[test]
+    fn linked_portals_map_onto_each_other() {
+        let mut a = Portal::new();
+        a.set_transform(Matrix4::translation(0., 0., 5.)).unwrap();
+        let mut b = Portal::new();
+        b.set_transform(Matrix4::translation(10., 0., 0.) * Matrix4::rotation_y(1.))
+            .unwrap();
+        Portal::link(&mut a, &mut b);
+
+        let exit = a.get_portal_exit().unwrap();
+        assert!((exit * Tuple::point(0., 0., 5.)).approx_eq(&Tuple::point(10., 0., 0.)));
+        let moved = *b.get_transform() * Tuple::point(1., 0., 0.);
+        assert!((exit * Tuple::point(1., 0., 5.)).approx_eq(&moved));
+        let back = b.get_portal_exit().unwrap();
+        assert_eq!(back * exit, Matrix4::identity());
+    }
+
+    #[test]
+    fn linking_uses_the_partner_world_transform() {
+        let mut a = Portal::new();
+        let mut b = Portal::new();
+        b.set_transform(Matrix4::translation(10., 0., 0.)).unwrap();
+        // As if b was the child of a group moved up by 3
+        b.set_parent_inverse(Matrix4::translation(0., -3., 0.));
+        a.link_to(&b);
+
+        let exit = a.get_portal_exit().unwrap();
+        assert!((exit * Tuple::point(0., 0., 0.)).approx_eq(&Tuple::point(10., 3., 0.)));
+    }
+}
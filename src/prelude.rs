@@ -0,0 +1,25 @@
+// Everything needed to build and render a typical scene, for a single glob import
+pub use crate::background::Background;
+pub use crate::camera::Camera;
+pub use crate::canvas::{Canvas, Tile};
+pub use crate::cone::Cone;
+pub use crate::cube::Cube;
+pub use crate::cylinder::Cylinder;
+pub use crate::group::Group;
+pub use crate::light::{AreaLight, Light, PointLight};
+pub use crate::lod::Lod;
+pub use crate::material::Material;
+pub use crate::matrix::Matrix4;
+pub use crate::obj::ObjFile;
+pub use crate::pattern::{CheckersPattern, GradientPattern, Pattern, RingPattern, StripePattern};
+pub use crate::plane::Plane;
+pub use crate::portal::Portal;
+pub use crate::ray::Ray;
+pub use crate::repeat::Repeat;
+pub use crate::scene::Scene;
+pub use crate::shape::Shape;
+pub use crate::smooth_triangle::SmoothTriangle;
+pub use crate::sphere::Sphere;
+pub use crate::triangle::Triangle;
+pub use crate::tuple::Tuple;
+pub use crate::world::World;
@@ -0,0 +1,86 @@
+use crate::matrix::Matrix4;
+use crate::tuple::Tuple;
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct Ray {
+    pub origin: Tuple,
+    pub direction: Tuple,
+    // Where the scene is seen from, camera rays and every ray they lead to
+    // carry the camera's position so all of them see the same level of a Lod
+    pub viewpoint: Option<Tuple>,
+}
+
+impl Ray {
+    pub fn new(origin: Tuple, direction: Tuple) -> Self {
+        Ray {
+            origin,
+            direction,
+            viewpoint: None,
+        }
+    }
+
+    pub fn with_viewpoint(mut self, viewpoint: Option<Tuple>) -> Self {
+        self.viewpoint = viewpoint;
+        self
+    }
+
+    pub fn position(&self, t: f32) -> Tuple {
+        self.origin.clone() + self.direction.clone() * t
+    }
+
+    pub fn transform(&self, m: &Matrix4) -> Ray {
+        Ray::new(*m * &self.origin, *m * &self.direction)
+            .with_viewpoint(self.viewpoint.as_ref().map(|viewpoint| *m * viewpoint))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn create_ray() {
+        let origin = Tuple::point(1., 2., 3.);
+        let direction = Tuple::vector(4., 5., 6.);
+        let r = Ray::new(origin.clone(), direction.clone());
+        assert_eq!(r.origin, origin);
+        assert_eq!(r.direction, direction);
+    }
+
+    #[test]
+    fn compute_point_from_distance() {
+        let r = Ray::new(Tuple::point(2., 3., 4.), Tuple::vector(1., 0., 0.));
+        assert_eq!(r.position(0.), Tuple::point(2., 3., 4.));
+        assert_eq!(r.position(1.), Tuple::point(3., 3., 4.));
+        assert_eq!(r.position(-1.), Tuple::point(1., 3., 4.));
+        assert_eq!(r.position(2.5), Tuple::point(4.5, 3., 4.));
+    }
+
+    #[test]
+    fn translate_ray() {
+        let r = Ray::new(Tuple::point(1., 2., 3.), Tuple::vector(0., 1., 0.));
+        let r2 = r.transform(&Matrix4::translation(3., 4., 5.));
+        assert_eq!(r2.origin, Tuple::point(4., 6., 8.));
+        assert_eq!(r2.direction, Tuple::vector(0., 1., 0.));
+    }
+
+    #[test]
+    fn scale_ray() {
+        let r = Ray::new(Tuple::point(1., 2., 3.), Tuple::vector(0., 1., 0.));
+        let r2 = r.transform(&Matrix4::scaling(2., 3., 4.));
+        assert_eq!(r2.origin, Tuple::point(2., 6., 12.));
+        assert_eq!(r2.direction, Tuple::vector(0., 3., 0.));
+    }
+
+    #[test]
+    fn viewpoint_moves_with_the_ray() {
+        let r = Ray::new(Tuple::point(1., 2., 3.), Tuple::vector(0., 1., 0.))
+            .with_viewpoint(Some(Tuple::point(0., 0., -5.)));
+        let r2 = r.transform(&Matrix4::translation(3., 4., 5.));
+        assert_eq!(r2.viewpoint, Some(Tuple::point(3., 4., 0.)));
+        assert_eq!(
+            Ray::new(Tuple::point(0., 0., 0.), Tuple::vector(0., 1., 0.)).viewpoint,
+            None
+        );
+    }
+}
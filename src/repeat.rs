@@ -0,0 +1,369 @@
+use crate::bounds::{check_axis, BoundingBox};
+use crate::intersection::Intersection;
+use crate::matrix::Matrix4;
+use crate::ray::Ray;
+use crate::shape::{Shape, ShapeBase};
+use crate::tuple::Tuple;
+
+// Cells a ray walks through before giving up, endless lattices never run out
+const REPEAT_MAX_CELLS: usize = 1024;
+
+// Copies of a shape on a lattice with the given spacing, one per cell with the
+// original in the cell around the origin. Only the original exists, rays are
+// moved into each cell they pass through instead, so an endless lattice costs
+// no more memory than a single copy. Copies are cut off at the cell walls, the
+// shape should fit into its cell. Axes with a spacing of 0 aren't repeated.
+#[derive(Debug)]
+pub struct Repeat {
+    base: ShapeBase,
+    // Always exactly one shape, kept in a vec so it can be handed out as children
+    child: Vec<Box<dyn Shape>>,
+    spacing: Tuple,
+    // Copies along each axis counting the original, None for endless repetition
+    counts: Option<[usize; 3]>,
+}
+
+impl Repeat {
+    pub fn new(child: Box<dyn Shape>, spacing: Tuple) -> Self {
+        let mut repeat = Repeat {
+            base: ShapeBase::new(),
+            child: vec![child],
+            spacing,
+            counts: None,
+        };
+        repeat.update_child();
+        repeat
+    }
+
+    // Finite n by n by n style lattice, growing from the original towards +x, +y and +z
+    pub fn lattice(child: Box<dyn Shape>, spacing: Tuple, counts: [usize; 3]) -> Self {
+        let mut repeat = Self::new(child, spacing);
+        repeat.counts = Some(counts.map(|n| n.max(1)));
+        repeat
+    }
+
+    pub fn get_child(&self) -> &dyn Shape {
+        self.child[0].as_ref()
+    }
+
+    pub fn get_spacing(&self) -> &Tuple {
+        &self.spacing
+    }
+
+    pub fn get_counts(&self) -> Option<[usize; 3]> {
+        self.counts
+    }
+
+    fn update_child(&mut self) {
+        let world_inverse = *self.base.get_world_inverse_transform();
+        self.child[0].set_parent_inverse(world_inverse);
+    }
+
+    fn axis_spacing(&self) -> [f32; 3] {
+        [self.spacing.0, self.spacing.1, self.spacing.2]
+    }
+
+    // Copies along the axis, None if there's no end to them
+    fn axis_count(&self, axis: usize) -> Option<usize> {
+        if self.axis_spacing()[axis] <= 0. {
+            return Some(1);
+        }
+        self.counts.map(|counts| counts[axis])
+    }
+}
+
+impl Shape for Repeat {
+    fn get_base(&self) -> &ShapeBase {
+        &self.base
+    }
+
+    fn get_base_mut(&mut self) -> &mut ShapeBase {
+        &mut self.base
+    }
+
+    fn get_children(&self) -> &[Box<dyn Shape>] {
+        &self.child
+    }
+
+    fn get_children_mut(&mut self) -> &mut [Box<dyn Shape>] {
+        &mut self.child
+    }
+
+    fn set_transform(&mut self, transform: Matrix4) -> Result<(), String> {
+        self.base.set_transform(transform)?;
+        self.update_child();
+        Ok(())
+    }
+
+    fn set_parent_inverse(&mut self, parent_inverse: Matrix4) {
+        self.base.set_parent_inverse(parent_inverse);
+        self.update_child();
+    }
+
+    fn divide(&mut self, threshold: usize) {
+        self.child[0].divide(threshold);
+    }
+
+    // Walks the cells along the ray, intersecting the copy in each, and stops
+    // at the first cell with a hit in front of the ray's origin
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        let bounds = self.bounds();
+        if bounds.is_empty() {
+            return vec![];
+        }
+        let origin = [ray.origin.0, ray.origin.1, ray.origin.2];
+        let direction = [ray.direction.0, ray.direction.1, ray.direction.2];
+        let (min, max) = (
+            [bounds.min.0, bounds.min.1, bounds.min.2],
+            [bounds.max.0, bounds.max.1, bounds.max.2],
+        );
+        let (mut t_min, mut t_max) = (f32::NEG_INFINITY, f32::INFINITY);
+        for axis in 0..3 {
+            let (t0, t1) = check_axis(origin[axis], direction[axis], min[axis], max[axis]);
+            (t_min, t_max) = (t_min.max(t0), t_max.min(t1));
+        }
+        if t_min > t_max || t_max < 0. {
+            return vec![];
+        }
+
+        let spacing = self.axis_spacing();
+        let start = t_min.max(0.);
+        let mut cell = [0_i64; 3];
+        let mut step = [0_i64; 3];
+        let mut t_next = [f32::INFINITY; 3];
+        let mut t_delta = [f32::INFINITY; 3];
+        for axis in 0..3 {
+            let s = spacing[axis];
+            if s <= 0. {
+                continue;
+            }
+            let position = origin[axis] + start * direction[axis];
+            cell[axis] = (position / s).round() as i64;
+            if let Some(count) = self.axis_count(axis) {
+                cell[axis] = cell[axis].clamp(0, count as i64 - 1);
+            }
+            if direction[axis] != 0. {
+                step[axis] = direction[axis].signum() as i64;
+                let wall = (cell[axis] as f32 + 0.5 * step[axis] as f32) * s;
+                t_next[axis] = (wall - origin[axis]) / direction[axis];
+                t_delta[axis] = s / direction[axis].abs();
+            }
+        }
+
+        let mut to_world = None;
+        let mut xs = vec![];
+        // Hits behind the origin only count in the cell the ray starts in
+        let mut enter = f32::NEG_INFINITY;
+        for _ in 0..REPEAT_MAX_CELLS {
+            let exit = t_next[0].min(t_next[1]).min(t_next[2]).min(t_max);
+            let center = Tuple::vector(
+                cell[0] as f32 * spacing[0].max(0.),
+                cell[1] as f32 * spacing[1].max(0.),
+                cell[2] as f32 * spacing[2].max(0.),
+            );
+            // Each copy is seen from the viewpoint as if it were the original
+            let local_ray = Ray::new(ray.origin.clone() - center.clone(), ray.direction.clone())
+                .with_viewpoint(ray.viewpoint.clone().map(|v| v - center.clone()));
+            let hits: Vec<_> = self.child[0]
+                .intersect(&local_ray)
+                .into_iter()
+                .filter(|i| enter <= i.t && i.t <= exit)
+                .collect();
+            if !hits.is_empty() {
+                let to_world: &Matrix4 = to_world.get_or_insert_with(|| {
+                    self.base
+                        .get_world_inverse_transform()
+                        .inverse()
+                        .expect("Inverse transforms are invertible")
+                });
+                let offset = *to_world * &center;
+                let in_front = hits.iter().any(|i| i.t >= 0.);
+                xs.extend(hits.into_iter().map(|mut i| {
+                    i.offset = Some(match i.offset.take() {
+                        Some(inner) => inner + offset.clone(),
+                        None => offset.clone(),
+                    });
+                    i
+                }));
+                if in_front {
+                    break;
+                }
+            }
+            if exit >= t_max {
+                break;
+            }
+
+            let axis = (0..3)
+                .min_by(|&a, &b| t_next[a].total_cmp(&t_next[b]))
+                .expect("There are three axes");
+            cell[axis] += step[axis];
+            if let Some(count) = self.axis_count(axis) {
+                if cell[axis] < 0 || cell[axis] >= count as i64 {
+                    break;
+                }
+            }
+            enter = t_next[axis];
+            t_next[axis] += t_delta[axis];
+        }
+        xs
+    }
+
+    fn local_normal_at(&self, _point: &Tuple) -> Tuple {
+        unreachable!("Repeats have no surface, normals come from the repeated shape")
+    }
+
+    // The child's bounds clipped to its cell and stretched over every copy
+    fn bounds(&self) -> BoundingBox {
+        let child = self.child[0].parent_space_bounds();
+        let (child_min, child_max) = (
+            [child.min.0, child.min.1, child.min.2],
+            [child.max.0, child.max.1, child.max.2],
+        );
+        let spacing = self.axis_spacing();
+        let mut min = [0.; 3];
+        let mut max = [0.; 3];
+        for axis in 0..3 {
+            let s = spacing[axis];
+            (min[axis], max[axis]) = match self.axis_count(axis) {
+                _ if s <= 0. => (child_min[axis], child_max[axis]),
+                Some(count) => (
+                    child_min[axis].max(-s / 2.),
+                    child_max[axis].min(s / 2.) + (count - 1) as f32 * s,
+                ),
+                None => (f32::NEG_INFINITY, f32::INFINITY),
+            };
+        }
+        BoundingBox::new(
+            Tuple::point(min[0], min[1], min[2]),
+            Tuple::point(max[0], max[1], max[2]),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intersection::Intersections;
+    use crate::light::PointLight;
+    use crate::material::Material;
+    use crate::pattern::StripePattern;
+    use crate::sphere::Sphere;
+    use crate::world::World;
+    use std::sync::Arc;
+
+    fn spheres() -> Repeat {
+        Repeat::new(Box::new(Sphere::new()), Tuple::vector(4., 0., 4.))
+    }
+
+    #[test]
+    fn ray_hits_the_copy_in_its_cell() {
+        let r = spheres();
+        let ray = Ray::new(Tuple::point(8., 0., -17.5), Tuple::vector(0., 0., 1.));
+        let xs = r.local_intersect(&ray);
+        assert_eq!(xs.len(), 2);
+        assert!(std::ptr::addr_eq(xs[0].object, r.get_child()));
+        assert_eq!(xs[0].t, 0.5);
+        assert_eq!(xs[1].t, 2.5);
+        assert_eq!(xs[0].offset, Some(Tuple::vector(8., 0., -16.)));
+    }
+
+    #[test]
+    fn ray_between_copies_misses() {
+        let r = spheres();
+        let ray = Ray::new(Tuple::point(2., 0., -20.), Tuple::vector(0., 0., 1.));
+        assert!(r.local_intersect(&ray).is_empty());
+        // Outside the single layer of copies along y
+        let ray = Ray::new(Tuple::point(0., 2., -20.), Tuple::vector(0., 0., 1.));
+        assert!(r.local_intersect(&ray).is_empty());
+    }
+
+    #[test]
+    fn far_copies_are_found() {
+        let r = spheres();
+        let ray = Ray::new(Tuple::point(40., 0., 1.5), Tuple::vector(0., 0., -1.));
+        let xs = r.local_intersect(&ray);
+        assert_eq!(xs[0].t, 0.5);
+        let ray = Ray::new(
+            Tuple::point(-2., 0., -40.),
+            Tuple::vector(1., 0., 20.).normalize(),
+        );
+        let xs = r.local_intersect(&ray);
+        assert!(!xs.is_empty());
+        let hit = ray.position(xs[0].t);
+        let center = xs[0].offset.clone().unwrap();
+        let local = hit - center;
+        assert!((Tuple::vector(local.0, local.1, local.2).mag() - 1.).abs() < 0.001);
+    }
+
+    #[test]
+    fn lattice_ends() {
+        let r = Repeat::lattice(
+            Box::new(Sphere::new()),
+            Tuple::vector(4., 0., 0.),
+            [3, 1, 1],
+        );
+        let bounds = r.bounds();
+        assert_eq!(bounds.min, Tuple::point(-1., -1., -1.));
+        assert_eq!(bounds.max, Tuple::point(9., 1., 1.));
+        let hit = |x: f32| {
+            let ray = Ray::new(Tuple::point(x, 0., -5.), Tuple::vector(0., 0., 1.));
+            !r.local_intersect(&ray).is_empty()
+        };
+        assert!(hit(0.) && hit(4.) && hit(8.));
+        assert!(!hit(-4.) && !hit(12.));
+    }
+
+    #[test]
+    fn normals_follow_the_copy() {
+        let mut r = spheres();
+        r.set_transform(Matrix4::translation(0., 1., 0.)).unwrap();
+        let ray = Ray::new(Tuple::point(-16., 1.6, -30.), Tuple::vector(0., 0., 1.));
+        let xs = Intersections::new(r.intersect(&ray));
+        let comps = xs.hit().unwrap().prepare_computations(&ray, &xs);
+        assert!(comps.point.approx_eq(&Tuple::point(-16., 1.6, -28.8)));
+        assert!(comps.normalv.approx_eq(&Tuple::vector(0., 0.6, -0.8)));
+    }
+
+    #[test]
+    fn ray_starting_inside_a_copy_sees_its_exit() {
+        let r = spheres();
+        let ray = Ray::new(Tuple::point(4., 0., 4.), Tuple::vector(1., 0., 0.));
+        let xs = r.local_intersect(&ray);
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].t, -1.);
+        assert_eq!(xs[1].t, 1.);
+    }
+
+    #[test]
+    fn copies_share_the_pattern_of_the_original() {
+        let mut sphere = Sphere::new();
+        let mut material = Material::new();
+        material.pattern = Some(Arc::new(StripePattern::new(
+            Tuple::color(1., 1., 1.),
+            Tuple::color(0., 0., 0.),
+        )));
+        material.ambient = 1.;
+        material.diffuse = 0.;
+        material.specular = 0.;
+        sphere.set_material(material);
+        let mut world = World::new();
+        world.objects.push(Box::new(Repeat::new(
+            Box::new(sphere),
+            Tuple::vector(3., 0., 3.),
+        )));
+        world
+            .lights
+            .push(PointLight::new(Tuple::point(0., 10., -10.), Tuple::color(1., 1., 1.)).into());
+
+        // The copy 3 units over would be in a black stripe at its world position
+        let color = |x: f32| {
+            world.color_at(&Ray::new(
+                Tuple::point(x, 0., -5.),
+                Tuple::vector(0., 0., 1.),
+            ))
+        };
+        assert_eq!(color(0.5), Tuple::color(1., 1., 1.));
+        assert_eq!(color(3.5), Tuple::color(1., 1., 1.));
+        assert_eq!(color(1.5 - 3.), Tuple::color(0., 0., 0.));
+    }
+}
@@ -0,0 +1,174 @@
+use crate::random::Rng;
+
+// Where inside a pixel the camera sends its rays, all colors are averaged.
+// Random samplers are seeded per pixel so every render of a scene is the same.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub enum Sampler {
+    // One ray through the center
+    #[default]
+    Single,
+    // Centers of an n by n grid of sub-pixels
+    Grid(usize),
+    // Anywhere in the pixel
+    Jittered {
+        samples: usize,
+        seed: u64,
+    },
+    // Anywhere in each cell of an n by n grid, evenly spread but without the
+    // regular pattern of a grid
+    Stratified {
+        per_side: usize,
+        seed: u64,
+    },
+}
+
+impl Sampler {
+    pub fn samples(&self) -> usize {
+        match self {
+            Sampler::Single => 1,
+            Sampler::Grid(n) | Sampler::Stratified { per_side: n, .. } => (n * n).max(1),
+            Sampler::Jittered { samples, .. } => (*samples).max(1),
+        }
+    }
+
+    // Same kind of sampler taking at least the given number of samples. Grids
+    // grow to the next square, a single centered ray becomes jittered ones.
+    pub fn at_least(&self, samples: usize) -> Sampler {
+        if self.samples() >= samples {
+            return self.clone();
+        }
+        let per_side = (1..)
+            .find(|n| n * n >= samples)
+            .expect("Some square is large enough");
+        match self {
+            Sampler::Single => Sampler::Jittered { samples, seed: 0 },
+            Sampler::Grid(_) => Sampler::Grid(per_side),
+            Sampler::Jittered { seed, .. } => Sampler::Jittered {
+                samples,
+                seed: *seed,
+            },
+            Sampler::Stratified { seed, .. } => Sampler::Stratified {
+                per_side,
+                seed: *seed,
+            },
+        }
+    }
+
+    // Offsets in [0, 1) from the top left corner of the pixel
+    pub fn offsets(&self, x: usize, y: usize) -> Vec<(f32, f32)> {
+        let cells = |n: usize| {
+            let n = n.max(1);
+            (0..n * n).map(move |i| ((i % n) as f32, (i / n) as f32, n as f32))
+        };
+        match self {
+            Sampler::Single => vec![(0.5, 0.5)],
+            Sampler::Grid(n) => cells(*n)
+                .map(|(cx, cy, n)| ((cx + 0.5) / n, (cy + 0.5) / n))
+                .collect(),
+            Sampler::Jittered { samples, seed } => {
+                let mut rng = pixel_rng(*seed, x, y);
+                (0..(*samples).max(1))
+                    .map(|_| (rng.next_f32(), rng.next_f32()))
+                    .collect()
+            }
+            Sampler::Stratified { per_side, seed } => {
+                let mut rng = pixel_rng(*seed, x, y);
+                cells(*per_side)
+                    .map(|(cx, cy, n)| ((cx + rng.next_f32()) / n, (cy + rng.next_f32()) / n))
+                    .collect()
+            }
+        }
+    }
+}
+
+fn pixel_rng(seed: u64, x: usize, y: usize) -> Rng {
+    Rng::new(seed ^ (y as u64) << 32 ^ x as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_sample_hits_the_center() {
+        assert_eq!(Sampler::Single.offsets(3, 4), vec![(0.5, 0.5)]);
+        assert_eq!(Sampler::default().samples(), 1);
+    }
+
+    #[test]
+    fn grid_samples_sub_pixel_centers() {
+        let s = Sampler::Grid(2);
+        assert_eq!(s.samples(), 4);
+        assert_eq!(
+            s.offsets(0, 0),
+            vec![(0.25, 0.25), (0.75, 0.25), (0.25, 0.75), (0.75, 0.75)]
+        );
+        assert_eq!(Sampler::Grid(0).offsets(0, 0), vec![(0.5, 0.5)]);
+    }
+
+    #[test]
+    fn jittered_samples_are_seeded_per_pixel() {
+        let s = Sampler::Jittered {
+            samples: 8,
+            seed: 1,
+        };
+        let offsets = s.offsets(2, 3);
+        assert_eq!(offsets.len(), 8);
+        assert!(offsets
+            .iter()
+            .all(|(u, v)| (0. ..1.).contains(u) && (0. ..1.).contains(v)));
+        assert_eq!(offsets, s.offsets(2, 3));
+        assert_ne!(offsets, s.offsets(3, 2));
+
+        let reseeded = Sampler::Jittered {
+            samples: 8,
+            seed: 2,
+        };
+        assert_ne!(offsets, reseeded.offsets(2, 3));
+    }
+
+    #[test]
+    fn stratified_samples_stay_in_their_cells() {
+        let s = Sampler::Stratified {
+            per_side: 3,
+            seed: 5,
+        };
+        let offsets = s.offsets(7, 1);
+        assert_eq!(offsets.len(), s.samples());
+        for (i, (u, v)) in offsets.into_iter().enumerate() {
+            let (cx, cy) = ((i % 3) as f32, (i / 3) as f32);
+            assert!(cx / 3. <= u && u < (cx + 1.) / 3.);
+            assert!(cy / 3. <= v && v < (cy + 1.) / 3.);
+        }
+    }
+
+    #[test]
+    fn samplers_grow_to_at_least_a_count() {
+        assert_eq!(
+            Sampler::Single.at_least(4),
+            Sampler::Jittered {
+                samples: 4,
+                seed: 0
+            }
+        );
+        assert_eq!(Sampler::Single.at_least(1), Sampler::Single);
+        assert_eq!(Sampler::Grid(2).at_least(5), Sampler::Grid(3));
+        assert_eq!(Sampler::Grid(4).at_least(5), Sampler::Grid(4));
+        let s = Sampler::Stratified {
+            per_side: 2,
+            seed: 9,
+        };
+        assert_eq!(
+            s.at_least(16),
+            Sampler::Stratified {
+                per_side: 4,
+                seed: 9
+            }
+        );
+        let s = Sampler::Jittered {
+            samples: 2,
+            seed: 3,
+        };
+        assert_eq!(s.at_least(8).samples(), 8);
+    }
+}
@@ -0,0 +1,294 @@
+use crate::intersection::Intersections;
+use crate::matrix::Matrix4;
+use crate::random::Rng;
+use crate::ray::Ray;
+use crate::shape::Shape;
+use crate::tuple::Tuple;
+
+// Places copies of a template shape at seeded random positions, either inside
+// a box or dropped onto a surface from above
+#[derive(PartialEq, Debug, Clone)]
+pub struct Scatter {
+    pub count: usize,
+    pub seed: u64,
+    pub min_scale: f32,
+    pub max_scale: f32,
+    pub random_rotation: bool,
+    pub align_to_normal: bool,
+    // Radius of one instance at scale 1, enables overlap rejection when set
+    pub spacing: Option<f32>,
+    pub max_attempts: usize,
+}
+
+impl Scatter {
+    pub fn new(count: usize, seed: u64) -> Self {
+        Scatter {
+            count,
+            seed,
+            min_scale: 1.,
+            max_scale: 1.,
+            random_rotation: true,
+            align_to_normal: false,
+            spacing: None,
+            max_attempts: 100,
+        }
+    }
+
+    pub fn in_region<S: Shape + Clone>(
+        &self,
+        template: &S,
+        min: &Tuple,
+        max: &Tuple,
+    ) -> Result<Vec<S>, String> {
+        self.place(template, |rng| {
+            let position = Tuple::point(
+                rng.range(min.0, max.0),
+                rng.range(min.1, max.1),
+                rng.range(min.2, max.2),
+            );
+            Some((position, Tuple::vector(0., 1., 0.)))
+        })
+    }
+
+    // Candidates are found by casting rays straight down from the top of the box,
+    // positions where the surface isn't inside the box are skipped
+    pub fn on_surface<S: Shape + Clone>(
+        &self,
+        template: &S,
+        surface: &dyn Shape,
+        min: &Tuple,
+        max: &Tuple,
+    ) -> Result<Vec<S>, String> {
+        self.place(template, |rng| {
+            let origin = Tuple::point(rng.range(min.0, max.0), max.1, rng.range(min.2, max.2));
+            let ray = Ray::new(origin, Tuple::vector(0., -1., 0.));
+            let xs = Intersections::new(surface.intersect(&ray));
+            let point = ray.position(xs.hit()?.t);
+            if point.1 < min.1 {
+                return None;
+            }
+            let normal = surface.normal_at(&point);
+            Some((point, normal))
+        })
+    }
+
+    fn place<S, F>(&self, template: &S, mut sample: F) -> Result<Vec<S>, String>
+    where
+        S: Shape + Clone,
+        F: FnMut(&mut Rng) -> Option<(Tuple, Tuple)>,
+    {
+        let mut rng = Rng::new(self.seed);
+        let mut placed: Vec<(Tuple, f32)> = vec![];
+        let mut instances = vec![];
+
+        let mut attempts = 0;
+        while instances.len() < self.count && attempts < self.count * self.max_attempts {
+            attempts += 1;
+
+            // Draw every random value up front so rejections don't shift the sequence
+            let candidate = sample(&mut rng);
+            let scale = rng.range(self.min_scale, self.max_scale);
+            let angle = rng.range(0., std::f32::consts::TAU);
+            let (position, normal) = match candidate {
+                Some(candidate) => candidate,
+                None => continue,
+            };
+
+            if let Some(spacing) = self.spacing {
+                let radius = spacing * scale;
+                let overlaps = placed
+                    .iter()
+                    .any(|(center, r)| (center.clone() - position.clone()).mag() < r + radius);
+                if overlaps {
+                    continue;
+                }
+                placed.push((position.clone(), radius));
+            }
+
+            let mut transform = Matrix4::translation(position.0, position.1, position.2);
+            if self.align_to_normal {
+                transform = transform * orient_to(&normal);
+            }
+            if self.random_rotation {
+                transform = transform * Matrix4::rotation_y(angle);
+            }
+            transform =
+                transform * Matrix4::scaling(scale, scale, scale) * *template.get_transform();
+
+            let mut instance = template.clone();
+            instance.set_transform(transform)?;
+            instances.push(instance);
+        }
+
+        Ok(instances)
+    }
+}
+
+// Rotation that maps the y axis onto the given normal
+fn orient_to(normal: &Tuple) -> Matrix4 {
+    let n = normal.normalize();
+    let helper = if n.2.abs() > 0.9 {
+        Tuple::vector(1., 0., 0.)
+    } else {
+        Tuple::vector(0., 0., 1.)
+    };
+    let t = n.cross(&helper).normalize();
+    let b = t.cross(&n);
+    Matrix4::new([
+        [t.0, n.0, b.0, 0.],
+        [t.1, n.1, b.1, 0.],
+        [t.2, n.2, b.2, 0.],
+        [0., 0., 0., 1.],
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plane::Plane;
+    use crate::sphere::Sphere;
+
+    fn position(shape: &dyn Shape) -> Tuple {
+        let m = shape.get_transform();
+        Tuple::point(m.get(0, 3), m.get(1, 3), m.get(2, 3))
+    }
+
+    #[test]
+    fn scatter_in_region_is_deterministic() {
+        let scatter = Scatter::new(20, 1234);
+        let min = Tuple::point(-5., 0., -5.);
+        let max = Tuple::point(5., 1., 5.);
+        let a = scatter.in_region(&Sphere::new(), &min, &max).unwrap();
+        let b = scatter.in_region(&Sphere::new(), &min, &max).unwrap();
+        assert_eq!(a.len(), 20);
+        assert_eq!(a, b);
+
+        let c = Scatter::new(20, 4321)
+            .in_region(&Sphere::new(), &min, &max)
+            .unwrap();
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn scatter_in_region_stays_inside() {
+        let mut scatter = Scatter::new(50, 7);
+        scatter.min_scale = 0.5;
+        scatter.max_scale = 2.;
+        let min = Tuple::point(-1., 2., 3.);
+        let max = Tuple::point(1., 4., 5.);
+        for s in scatter.in_region(&Sphere::new(), &min, &max).unwrap() {
+            let p = position(&s);
+            assert!(min.0 <= p.0 && p.0 <= max.0);
+            assert!(min.1 <= p.1 && p.1 <= max.1);
+            assert!(min.2 <= p.2 && p.2 <= max.2);
+        }
+    }
+
+    #[test]
+    fn scatter_keeps_template_material_and_transform() {
+        let mut template = Sphere::new();
+        template.get_material_mut().ambient = 0.7;
+        template
+            .set_transform(Matrix4::scaling(2., 2., 2.))
+            .unwrap();
+        let mut scatter = Scatter::new(1, 3);
+        scatter.random_rotation = false;
+        let origin = Tuple::point(0., 0., 0.);
+        let s = scatter.in_region(&template, &origin, &origin).unwrap();
+        assert_eq!(s[0].get_material().ambient, 0.7);
+        assert_eq!(s[0].get_transform(), &Matrix4::scaling(2., 2., 2.));
+    }
+
+    #[test]
+    fn scatter_without_overlap() {
+        let mut scatter = Scatter::new(30, 99);
+        scatter.spacing = Some(0.5);
+        scatter.min_scale = 0.5;
+        scatter.max_scale = 1.;
+        let min = Tuple::point(-10., 0., -10.);
+        let max = Tuple::point(10., 0., 10.);
+        let spheres = scatter.in_region(&Sphere::new(), &min, &max).unwrap();
+        assert_eq!(spheres.len(), 30);
+
+        for (i, a) in spheres.iter().enumerate() {
+            for b in spheres.iter().skip(i + 1) {
+                let distance = (position(a) - position(b)).mag();
+                let ra = a.get_transform().get(1, 1) * 0.5;
+                let rb = b.get_transform().get(1, 1) * 0.5;
+                assert!(distance >= ra + rb);
+            }
+        }
+    }
+
+    #[test]
+    fn scatter_gives_up_when_region_is_full() {
+        let mut scatter = Scatter::new(10, 5);
+        scatter.spacing = Some(1.);
+        let min = Tuple::point(0., 0., 0.);
+        let max = Tuple::point(1., 0., 1.);
+        let spheres = scatter.in_region(&Sphere::new(), &min, &max).unwrap();
+        assert!(!spheres.is_empty());
+        assert!(spheres.len() < 10);
+    }
+
+    #[test]
+    fn scatter_on_surface_places_on_plane() {
+        let mut floor = Plane::new();
+        floor
+            .set_transform(Matrix4::translation(0., 2., 0.))
+            .unwrap();
+        let scatter = Scatter::new(10, 11);
+        let min = Tuple::point(-5., 0., -5.);
+        let max = Tuple::point(5., 10., 5.);
+        let spheres = scatter
+            .on_surface(&Sphere::new(), &floor, &min, &max)
+            .unwrap();
+        assert_eq!(spheres.len(), 10);
+        for s in &spheres {
+            assert!((position(s).1 - 2.).abs() < 0.001);
+        }
+    }
+
+    #[test]
+    fn scatter_on_surface_outside_box_places_nothing() {
+        let mut floor = Plane::new();
+        floor
+            .set_transform(Matrix4::translation(0., -20., 0.))
+            .unwrap();
+        let scatter = Scatter::new(5, 11);
+        let min = Tuple::point(-5., 0., -5.);
+        let max = Tuple::point(5., 10., 5.);
+        let spheres = scatter
+            .on_surface(&Sphere::new(), &floor, &min, &max)
+            .unwrap();
+        assert!(spheres.is_empty());
+    }
+
+    #[test]
+    fn scatter_aligned_to_surface_normal() {
+        let mut slope = Plane::new();
+        slope
+            .set_transform(Matrix4::rotation_z(std::f32::consts::FRAC_PI_4))
+            .unwrap();
+        let normal = slope.normal_at(&Tuple::point(0., 0., 0.));
+
+        let mut scatter = Scatter::new(5, 21);
+        scatter.align_to_normal = true;
+        let min = Tuple::point(-3., -5., -3.);
+        let max = Tuple::point(3., 5., 3.);
+        let instances = scatter
+            .on_surface(&Sphere::new(), &slope, &min, &max)
+            .unwrap();
+        assert_eq!(instances.len(), 5);
+
+        for instance in &instances {
+            let up = *instance.get_transform() * Tuple::vector(0., 1., 0.);
+            assert!(up.normalize().approx_eq(&normal));
+        }
+    }
+
+    #[test]
+    fn orient_to_up_is_identity() {
+        assert_eq!(orient_to(&Tuple::vector(0., 1., 0.)), Matrix4::identity());
+    }
+}
@@ -0,0 +1,868 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::camera::Camera;
+use crate::cone::Cone;
+use crate::cube::Cube;
+use crate::cylinder::Cylinder;
+use crate::group::Group;
+use crate::light::{AreaLight, Light, PointLight};
+use crate::material::Material;
+use crate::matrix::Matrix4;
+use crate::obj::ObjFile;
+use crate::pattern::{CheckersPattern, GradientPattern, Pattern, RingPattern, StripePattern};
+use crate::plane::Plane;
+use crate::shape::Shape;
+use crate::sphere::Sphere;
+use crate::tuple::Tuple;
+use crate::world::World;
+
+// World and camera described by a scene file in the YAML format of the book's
+// bonus chapters: a list of `add` items (camera, light, shapes) and `define`
+// items naming materials and transforms for later items to use
+#[derive(Debug)]
+pub struct Scene {
+    pub world: World,
+    pub camera: Camera,
+}
+
+impl Scene {
+    pub fn parse(input: &str) -> Result<Self, String> {
+        Self::parse_in(input, Path::new("."))
+    }
+
+    // OBJ files referenced by the scene are looked up relative to dir
+    pub fn parse_in(input: &str, dir: &Path) -> Result<Self, String> {
+        let Value::List(items) = parse_yaml(input)? else {
+            return Err("scene must be a list of items".to_string());
+        };
+
+        let mut world = World::new();
+        let mut camera = None;
+        let mut defines: HashMap<String, Value> = HashMap::new();
+        for item in &items {
+            if let Some(name) = item.get("define") {
+                let mut value = item.require("value")?.clone();
+                if let Some(base) = item.get("extend") {
+                    let base = lookup(&defines, base.as_str()?)?;
+                    value = extend(base, value)?;
+                }
+                defines.insert(name.as_str()?.to_string(), value);
+                continue;
+            }
+            match item.require("add")?.as_str()? {
+                "camera" => camera = Some(build_camera(item)?),
+                "light" => world.lights.push(build_light(item)?),
+                _ => world.objects.push(build_shape(item, &defines, dir)?),
+            }
+        }
+
+        let camera = camera.ok_or("scene has no camera")?;
+        Ok(Scene { world, camera })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let input = fs::read_to_string(path)
+            .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+        Self::parse_in(&input, path.parent().unwrap_or(Path::new(".")))
+    }
+}
+
+fn build_camera(item: &Value) -> Result<Camera, String> {
+    let mut camera = Camera::new(
+        item.require("width")?.as_usize()?,
+        item.require("height")?.as_usize()?,
+        item.require("field-of-view")?.as_f32()?,
+    );
+    camera.set_transform(Matrix4::view_transform(
+        &item.require("from")?.as_point()?,
+        &item.require("to")?.as_point()?,
+        &item.require("up")?.as_vector()?,
+    ))?;
+    Ok(camera)
+}
+
+// Lights with a corner are area lights, anything else a point light
+fn build_light(item: &Value) -> Result<Light, String> {
+    let intensity = item.require("intensity")?.as_color()?;
+    if item.get("corner").is_none() {
+        return Ok(PointLight::new(item.require("at")?.as_point()?, intensity).into());
+    }
+    let mut light = AreaLight::new(
+        item.require("corner")?.as_point()?,
+        item.require("uvec")?.as_vector()?,
+        item.require("usteps")?.as_usize()?,
+        item.require("vvec")?.as_vector()?,
+        item.require("vsteps")?.as_usize()?,
+        intensity,
+    );
+    if item.get("jitter").map_or(Ok(false), Value::as_bool)? {
+        light.jitter = Some(0);
+    }
+    Ok(light.into())
+}
+
+fn build_shape(
+    item: &Value,
+    defines: &HashMap<String, Value>,
+    dir: &Path,
+) -> Result<Box<dyn Shape>, String> {
+    let limits = || -> Result<(f32, f32, bool), String> {
+        let limit = |key, default| item.get(key).map_or(Ok(default), Value::as_f32);
+        let closed = item.get("closed").map_or(Ok(false), Value::as_bool)?;
+        Ok((
+            limit("min", f32::NEG_INFINITY)?,
+            limit("max", f32::INFINITY)?,
+            closed,
+        ))
+    };
+
+    let mut shape: Box<dyn Shape> = match item.require("add")?.as_str()? {
+        "sphere" => Box::new(Sphere::new()),
+        "plane" => Box::new(Plane::new()),
+        "cube" => Box::new(Cube::new()),
+        "cylinder" => {
+            let (min, max, closed) = limits()?;
+            Box::new(Cylinder::truncated(min, max, closed))
+        }
+        "cone" => {
+            let (min, max, closed) = limits()?;
+            Box::new(Cone::truncated(min, max, closed))
+        }
+        "group" => {
+            let mut group = Group::new();
+            if let Some(children) = item.get("children") {
+                for child in children.as_list()? {
+                    group.add_child(build_shape(child, defines, dir)?);
+                }
+            }
+            Box::new(group)
+        }
+        "obj" => {
+            let file = item.require("file")?.as_str()?;
+            Box::new(ObjFile::load(&dir.join(file))?.into_group())
+        }
+        other => return Err(format!("unknown shape '{}'", other)),
+    };
+
+    if let Some(material) = item.get("material") {
+        shape.set_material(build_material(material, defines)?);
+    }
+    if let Some(transform) = item.get("transform") {
+        shape.set_transform(build_transform(transform, defines)?)?;
+    }
+    Ok(shape)
+}
+
+fn build_material(value: &Value, defines: &HashMap<String, Value>) -> Result<Material, String> {
+    let value = resolve(value, defines)?;
+    let Value::Map(entries) = value else {
+        return Err("material must be a mapping or the name of one".to_string());
+    };
+    let mut material = Material::new();
+    for (key, value) in entries {
+        match key.as_str() {
+            "color" => material.color = value.as_color()?,
+            "ambient" => material.ambient = value.as_f32()?,
+            "diffuse" => material.diffuse = value.as_f32()?,
+            "specular" => material.specular = value.as_f32()?,
+            "shininess" => material.shininess = value.as_f32()?,
+            "reflective" => material.reflective = value.as_f32()?,
+            "transparency" => material.transparency = value.as_f32()?,
+            "refractive-index" => material.refractive_index = value.as_f32()?,
+            "roughness" => material.roughness = value.as_f32()?,
+            "absorption" => material.absorption = value.as_color()?,
+            "density" => material.density = value.as_f32()?,
+            "pattern" => material.pattern = Some(build_pattern(value, defines)?),
+            other => return Err(format!("unknown material property '{}'", other)),
+        }
+    }
+    Ok(material)
+}
+
+fn build_pattern(
+    value: &Value,
+    defines: &HashMap<String, Value>,
+) -> Result<Arc<dyn Pattern>, String> {
+    let colors = value.require("colors")?.as_list()?;
+    let [a, b] = colors else {
+        return Err(format!("pattern needs 2 colors, got {}", colors.len()));
+    };
+    let (a, b) = (a.as_color()?, b.as_color()?);
+    let mut pattern: Box<dyn Pattern> = match value.require("type")?.as_str()? {
+        "stripes" => Box::new(StripePattern::new(a, b)),
+        "gradient" => Box::new(GradientPattern::new(a, b)),
+        "rings" => Box::new(RingPattern::new(a, b)),
+        "checkers" => Box::new(CheckersPattern::new(a, b)),
+        other => return Err(format!("unknown pattern '{}'", other)),
+    };
+    if let Some(transform) = value.get("transform") {
+        pattern.set_transform(build_transform(transform, defines)?)?;
+    }
+    Ok(Arc::from(pattern))
+}
+
+// Steps are applied in the order they are listed, names of defined transforms
+// insert their steps in place
+fn build_transform(value: &Value, defines: &HashMap<String, Value>) -> Result<Matrix4, String> {
+    expand_transform(value, defines, &mut vec![])
+}
+
+// Names holds the definitions being expanded, meeting one of them again would
+// never end
+fn expand_transform<'a>(
+    value: &'a Value,
+    defines: &'a HashMap<String, Value>,
+    names: &mut Vec<&'a str>,
+) -> Result<Matrix4, String> {
+    if let Value::Scalar(name) = value {
+        if names.contains(&name.as_str()) {
+            return Err(format!("recursive definition of '{}'", name));
+        }
+        names.push(name);
+        let transform = expand_transform(lookup(defines, name)?, defines, names);
+        names.pop();
+        return transform;
+    }
+    let mut transform = Matrix4::identity();
+    for step in value.as_list()? {
+        let step = match step {
+            Value::Scalar(_) => expand_transform(step, defines, names)?,
+            _ => build_transform_step(step.as_list()?)?,
+        };
+        transform = step * transform;
+    }
+    Ok(transform)
+}
+
+fn build_transform_step(step: &[Value]) -> Result<Matrix4, String> {
+    let (name, args) = step.split_first().ok_or("empty transform step")?;
+    let name = name.as_str()?;
+    let args = args
+        .iter()
+        .map(Value::as_f32)
+        .collect::<Result<Vec<_>, String>>()?;
+    match (name, args.as_slice()) {
+        ("translate", &[x, y, z]) => Ok(Matrix4::translation(x, y, z)),
+        ("scale", &[x, y, z]) => Ok(Matrix4::scaling(x, y, z)),
+        ("rotate-x", &[r]) => Ok(Matrix4::rotation_x(r)),
+        ("rotate-y", &[r]) => Ok(Matrix4::rotation_y(r)),
+        ("rotate-z", &[r]) => Ok(Matrix4::rotation_z(r)),
+        ("shear", &[xy, xz, yx, yz, zx, zy]) => Ok(Matrix4::shearing(xy, xz, yx, yz, zx, zy)),
+        _ => Err(format!(
+            "unknown transform '{}' with {} arguments",
+            name,
+            args.len()
+        )),
+    }
+}
+
+fn lookup<'a>(defines: &'a HashMap<String, Value>, name: &str) -> Result<&'a Value, String> {
+    defines
+        .get(name)
+        .ok_or_else(|| format!("unknown definition '{}'", name))
+}
+
+// Plain names refer to earlier definitions, anything else is used as is
+fn resolve<'a>(value: &'a Value, defines: &'a HashMap<String, Value>) -> Result<&'a Value, String> {
+    match value {
+        Value::Scalar(name) => lookup(defines, name),
+        _ => Ok(value),
+    }
+}
+
+// Mappings are merged with the extension's entries winning, lists are appended
+fn extend(base: &Value, extension: Value) -> Result<Value, String> {
+    match (base, extension) {
+        (Value::Map(base), Value::Map(extension)) => {
+            let mut entries: Vec<_> = base
+                .iter()
+                .filter(|(key, _)| extension.iter().all(|(k, _)| k != key))
+                .cloned()
+                .collect();
+            entries.extend(extension);
+            Ok(Value::Map(entries))
+        }
+        (Value::List(base), Value::List(extension)) => {
+            Ok(Value::List(base.iter().cloned().chain(extension).collect()))
+        }
+        _ => Err("can only extend a definition of the same kind".to_string()),
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+enum Value {
+    Scalar(String),
+    List(Vec<Value>),
+    Map(Vec<(String, Value)>),
+}
+
+impl Value {
+    fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Map(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    fn require(&self, key: &str) -> Result<&Value, String> {
+        self.get(key).ok_or_else(|| format!("missing '{}'", key))
+    }
+
+    fn as_str(&self) -> Result<&str, String> {
+        match self {
+            Value::Scalar(s) => Ok(s),
+            _ => Err(format!("expected a plain value, got {:?}", self)),
+        }
+    }
+
+    fn as_f32(&self) -> Result<f32, String> {
+        let s = self.as_str()?;
+        s.parse()
+            .map_err(|_| format!("expected a number, got '{}'", s))
+    }
+
+    fn as_usize(&self) -> Result<usize, String> {
+        let s = self.as_str()?;
+        s.parse()
+            .map_err(|_| format!("expected a positive integer, got '{}'", s))
+    }
+
+    fn as_bool(&self) -> Result<bool, String> {
+        match self.as_str()? {
+            "true" => Ok(true),
+            "false" => Ok(false),
+            s => Err(format!("expected true or false, got '{}'", s)),
+        }
+    }
+
+    fn as_list(&self) -> Result<&[Value], String> {
+        match self {
+            Value::List(items) => Ok(items),
+            _ => Err(format!("expected a list, got {:?}", self)),
+        }
+    }
+
+    fn as_xyz(&self) -> Result<(f32, f32, f32), String> {
+        match self.as_list()? {
+            [x, y, z] => Ok((x.as_f32()?, y.as_f32()?, z.as_f32()?)),
+            items => Err(format!("expected 3 numbers, got {}", items.len())),
+        }
+    }
+
+    fn as_point(&self) -> Result<Tuple, String> {
+        let (x, y, z) = self.as_xyz()?;
+        Ok(Tuple::point(x, y, z))
+    }
+
+    fn as_vector(&self) -> Result<Tuple, String> {
+        let (x, y, z) = self.as_xyz()?;
+        Ok(Tuple::vector(x, y, z))
+    }
+
+    fn as_color(&self) -> Result<Tuple, String> {
+        let (r, g, b) = self.as_xyz()?;
+        Ok(Tuple::color(r, g, b))
+    }
+}
+
+struct Line {
+    number: usize,
+    indent: usize,
+    text: String,
+}
+
+// Handles the subset of YAML scene files use: block lists and mappings nested
+// by indentation, [flow, lists] and plain or quoted scalars
+fn parse_yaml(input: &str) -> Result<Value, String> {
+    let mut lines: Vec<Line> = input
+        .lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let line = strip_comment(line).trim_end();
+            let text = line.trim_start();
+            if text.is_empty() || text == "---" {
+                return None;
+            }
+            Some(Line {
+                number: index + 1,
+                indent: line.len() - text.len(),
+                text: text.to_string(),
+            })
+        })
+        .collect();
+    let Some(first) = lines.first() else {
+        return Ok(Value::List(vec![]));
+    };
+
+    let mut pos = 0;
+    let indent = first.indent;
+    let value = parse_block(&mut lines, &mut pos, indent)?;
+    match lines.get(pos) {
+        Some(line) => Err(format!("line {}: unexpected indentation", line.number)),
+        None => Ok(value),
+    }
+}
+
+fn parse_block(lines: &mut [Line], pos: &mut usize, indent: usize) -> Result<Value, String> {
+    let line = &lines[*pos];
+    if is_item(&line.text) {
+        let mut items = vec![];
+        while *pos < lines.len() && lines[*pos].indent == indent && is_item(&lines[*pos].text) {
+            let line = &mut lines[*pos];
+            let rest = line.text[1..].trim_start().to_string();
+            if rest.is_empty() {
+                *pos += 1;
+                items.push(parse_nested(lines, pos, indent)?);
+            } else {
+                // Whatever follows the dash is parsed as if it started its own line
+                line.indent += line.text.len() - rest.len();
+                line.text = rest;
+                let item_indent = line.indent;
+                items.push(parse_block(lines, pos, item_indent)?);
+            }
+        }
+        return Ok(Value::List(items));
+    }
+
+    if split_key(&line.text).is_none() {
+        let value = parse_flow(&line.text, line.number)?;
+        *pos += 1;
+        return Ok(value);
+    }
+
+    let mut entries = vec![];
+    while *pos < lines.len() && lines[*pos].indent == indent && !is_item(&lines[*pos].text) {
+        let line = &lines[*pos];
+        let number = line.number;
+        let (key, rest) = split_key(&line.text)
+            .ok_or_else(|| format!("line {}: expected 'key: value'", number))?;
+        *pos += 1;
+        let value = if rest.is_empty() {
+            // Lists are allowed at the same indentation as their key
+            match lines.get(*pos) {
+                Some(next) if next.indent == indent && is_item(&next.text) => {
+                    parse_block(lines, pos, indent)?
+                }
+                _ => parse_nested(lines, pos, indent)?,
+            }
+        } else {
+            parse_flow(&rest, number)?
+        };
+        entries.push((key, value));
+    }
+    Ok(Value::Map(entries))
+}
+
+// Block indented below the current line, or an empty value if there is none
+fn parse_nested(lines: &mut [Line], pos: &mut usize, indent: usize) -> Result<Value, String> {
+    match lines.get(*pos) {
+        Some(next) if next.indent > indent => {
+            let next_indent = next.indent;
+            parse_block(lines, pos, next_indent)
+        }
+        _ => Ok(Value::Scalar(String::new())),
+    }
+}
+
+fn parse_flow(text: &str, number: usize) -> Result<Value, String> {
+    let text = text.trim();
+    let Some(inner) = text.strip_prefix('[') else {
+        let unquoted = ['"', '\'']
+            .iter()
+            .find_map(|q| text.strip_prefix(*q)?.strip_suffix(*q))
+            .unwrap_or(text);
+        return Ok(Value::Scalar(unquoted.to_string()));
+    };
+    let inner = inner
+        .strip_suffix(']')
+        .ok_or_else(|| format!("line {}: unclosed '['", number))?;
+    if inner.trim().is_empty() {
+        return Ok(Value::List(vec![]));
+    }
+
+    let mut items = vec![];
+    let (mut depth, mut start) = (0, 0);
+    for (i, c) in inner.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            ',' if depth == 0 => {
+                items.push(parse_flow(&inner[start..i], number)?);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(parse_flow(&inner[start..], number)?);
+    Ok(Value::List(items))
+}
+
+fn is_item(text: &str) -> bool {
+    text == "-" || text.starts_with("- ")
+}
+
+fn split_key(text: &str) -> Option<(String, String)> {
+    if text.starts_with(['[', '"', '\'']) {
+        return None;
+    }
+    let (key, rest) = match text.split_once(": ") {
+        Some(split) => split,
+        None => (text.strip_suffix(':')?, ""),
+    };
+    Some((key.trim().to_string(), rest.trim().to_string()))
+}
+
+// Comments start at a # that begins the line or follows whitespace, outside quotes
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut previous = ' ';
+    for (i, c) in line.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if q == c => quote = None,
+            (None, '#') if previous.is_whitespace() => return &line[..i],
+            _ => {}
+        }
+        previous = c;
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use super::*;
+
+    fn scalar(s: &str) -> Value {
+        Value::Scalar(s.to_string())
+    }
+
+    fn list(items: &[&str]) -> Value {
+        Value::List(items.iter().map(|s| scalar(s)).collect())
+    }
+
+    const CAMERA: &str = "
+- add: camera
+  width: 100
+  height: 50
+  field-of-view: 1.5707964
+  from: [0, 0, -5]
+  to: [0, 0, 0]
+  up: [0, 1, 0]
+";
+
+    #[test]
+    fn parse_nested_yaml() {
+        let input = "
+# comment
+- add: thing   # trailing comment
+  numbers: [1, 2.5, -3]
+  nested: [[a, b], []]
+  text: 'quoted # not a comment'
+  steps:
+  - [x, 1]
+  - name
+  map:
+    inner: true
+-
+  - 4
+";
+        let value = parse_yaml(input).unwrap();
+        assert_eq!(
+            value,
+            Value::List(vec![
+                Value::Map(vec![
+                    ("add".to_string(), scalar("thing")),
+                    ("numbers".to_string(), list(&["1", "2.5", "-3"])),
+                    (
+                        "nested".to_string(),
+                        Value::List(vec![list(&["a", "b"]), list(&[])])
+                    ),
+                    ("text".to_string(), scalar("quoted # not a comment")),
+                    (
+                        "steps".to_string(),
+                        Value::List(vec![list(&["x", "1"]), scalar("name")])
+                    ),
+                    (
+                        "map".to_string(),
+                        Value::Map(vec![("inner".to_string(), scalar("true"))])
+                    ),
+                ]),
+                list(&["4"]),
+            ])
+        );
+    }
+
+    #[test]
+    fn yaml_errors_have_line_numbers() {
+        assert_eq!(
+            parse_yaml("- a: [1, 2\n"),
+            Err("line 1: unclosed '['".to_string())
+        );
+        assert_eq!(
+            parse_yaml("- a: 1\n b: 2\n"),
+            Err("line 2: unexpected indentation".to_string())
+        );
+    }
+
+    #[test]
+    fn scene_with_camera_and_light() {
+        let input = format!(
+            "{}
+- add: light
+  at: [-10, 10, -10]
+  intensity: [1, 0.5, 1]
+",
+            CAMERA
+        );
+        let scene = Scene::parse(&input).unwrap();
+        assert_eq!(scene.camera.get_hsize(), 100);
+        assert_eq!(scene.camera.get_vsize(), 50);
+        assert_eq!(
+            scene.camera.get_transform(),
+            &(Matrix4::scaling(-1., 1., -1.) * Matrix4::translation(0., 0., 5.))
+        );
+        assert_eq!(
+            scene.world.lights,
+            vec![PointLight::new(Tuple::point(-10., 10., -10.), Tuple::color(1., 0.5, 1.)).into()]
+        );
+    }
+
+    #[test]
+    fn parse_area_light() {
+        let input = format!(
+            "{}
+- add: light
+  corner: [-1, 2, 4]
+  uvec: [2, 0, 0]
+  vvec: [0, 2, 0]
+  usteps: 4
+  vsteps: 2
+  jitter: true
+  intensity: [1.5, 1.5, 1.5]
+",
+            CAMERA
+        );
+        let scene = Scene::parse(&input).unwrap();
+        let mut expected = AreaLight::new(
+            Tuple::point(-1., 2., 4.),
+            Tuple::vector(2., 0., 0.),
+            4,
+            Tuple::vector(0., 2., 0.),
+            2,
+            Tuple::color(1.5, 1.5, 1.5),
+        );
+        expected.jitter = Some(0);
+        assert_eq!(scene.world.lights, vec![expected.into()]);
+    }
+
+    #[test]
+    fn scene_needs_camera() {
+        assert_eq!(
+            Scene::parse("- add: sphere").unwrap_err(),
+            "scene has no camera"
+        );
+    }
+
+    #[test]
+    fn shapes_with_material_and_transform() {
+        let input = format!(
+            "{}
+- add: sphere
+  material:
+    color: [1, 0, 0]
+    diffuse: 0.5
+    refractive-index: 1.5
+    absorption: [0, 0.5, 1]
+  transform:
+    - [scale, 2, 2, 2]
+    - [translate, 0, 1, 0]
+- add: cylinder
+  min: -1
+  max: 2
+  closed: true
+",
+            CAMERA
+        );
+        let scene = Scene::parse(&input).unwrap();
+        assert_eq!(scene.world.objects.len(), 2);
+
+        let sphere = &scene.world.objects[0];
+        let mut material = Material::new();
+        material.color = Tuple::color(1., 0., 0.);
+        material.diffuse = 0.5;
+        material.refractive_index = 1.5;
+        material.absorption = Tuple::color(0., 0.5, 1.);
+        assert_eq!(sphere.get_material(), &material);
+        assert_eq!(
+            sphere.get_transform(),
+            &(Matrix4::translation(0., 1., 0.) * Matrix4::scaling(2., 2., 2.))
+        );
+
+        assert_eq!(
+            format!("{:?}", scene.world.objects[1]),
+            format!("{:?}", Cylinder::truncated(-1., 2., true))
+        );
+    }
+
+    #[test]
+    fn definitions_can_be_extended_and_reused() {
+        let input = format!(
+            "{}
+- define: white
+  value:
+    color: [1, 1, 1]
+    ambient: 0.2
+- define: red
+  extend: white
+  value:
+    color: [1, 0, 0]
+- define: lift
+  value:
+    - [translate, 0, 1, 0]
+- define: big-lift
+  extend: lift
+  value:
+    - [scale, 3, 3, 3]
+- add: cube
+  material: red
+  transform:
+    - [rotate-y, 1.5707964]
+    - big-lift
+",
+            CAMERA
+        );
+        let scene = Scene::parse(&input).unwrap();
+        let cube = &scene.world.objects[0];
+        assert_eq!(cube.get_material().color, Tuple::color(1., 0., 0.));
+        assert_eq!(cube.get_material().ambient, 0.2);
+        assert_eq!(
+            cube.get_transform(),
+            &(Matrix4::scaling(3., 3., 3.)
+                * Matrix4::translation(0., 1., 0.)
+                * Matrix4::rotation_y(FRAC_PI_2))
+        );
+    }
+
+    #[test]
+    fn material_with_pattern() {
+        let input = format!(
+            "{}
+- add: plane
+  material:
+    pattern:
+      type: checkers
+      colors:
+        - [1, 1, 1]
+        - [0, 0, 0]
+      transform:
+        - [scale, 0.5, 0.5, 0.5]
+",
+            CAMERA
+        );
+        let scene = Scene::parse(&input).unwrap();
+        let pattern = scene.world.objects[0]
+            .get_material()
+            .pattern
+            .clone()
+            .unwrap();
+        assert_eq!(pattern.get_transform(), &Matrix4::scaling(0.5, 0.5, 0.5));
+        assert_eq!(
+            pattern.local_pattern_at(&Tuple::point(1., 0., 0.)),
+            Tuple::color(0., 0., 0.)
+        );
+    }
+
+    #[test]
+    fn groups_hold_children() {
+        let input = format!(
+            "{}
+- add: group
+  transform:
+    - [translate, 1, 0, 0]
+  children:
+    - add: sphere
+    - add: cone
+      max: 0
+",
+            CAMERA
+        );
+        let scene = Scene::parse(&input).unwrap();
+        let group = &scene.world.objects[0];
+        assert_eq!(group.get_children().len(), 2);
+        assert_eq!(
+            group.get_children()[0]
+                .get_base()
+                .get_world_inverse_transform(),
+            &Matrix4::translation(-1., 0., 0.)
+        );
+    }
+
+    #[test]
+    fn obj_files_are_relative_to_the_scene() {
+        let dir = std::env::temp_dir().join(format!("ray_tracer_scene_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("triangle.obj"),
+            "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n",
+        )
+        .unwrap();
+        let scene_path = dir.join("scene.yml");
+        fs::write(
+            &scene_path,
+            format!("{}\n- add: obj\n  file: triangle.obj\n", CAMERA),
+        )
+        .unwrap();
+
+        let scene = Scene::load(&scene_path);
+        fs::remove_dir_all(&dir).unwrap();
+        let scene = scene.unwrap();
+        assert_eq!(scene.world.objects[0].get_children().len(), 1);
+    }
+
+    #[test]
+    fn recursive_definitions_are_errors() {
+        let parse = |items: &str| Scene::parse(&format!("{}\n{}", CAMERA, items)).unwrap_err();
+        assert_eq!(
+            parse("- define: loop\n  value: [loop]\n- add: sphere\n  transform: loop"),
+            "recursive definition of 'loop'"
+        );
+        assert_eq!(
+            parse(
+                "- define: a\n  value: [b]\n- define: b\n  value: [[scale, 2, 2, 2], a]\n\
+                 - add: sphere\n  transform: [b]"
+            ),
+            "recursive definition of 'b'"
+        );
+    }
+
+    #[test]
+    fn scene_errors() {
+        let parse = |item: &str| Scene::parse(&format!("{}\n{}", CAMERA, item)).unwrap_err();
+        assert_eq!(parse("- add: teapot"), "unknown shape 'teapot'");
+        assert_eq!(
+            parse("- add: sphere\n  material: shiny"),
+            "unknown definition 'shiny'"
+        );
+        assert_eq!(
+            parse("- add: sphere\n  material:\n    glow: 1"),
+            "unknown material property 'glow'"
+        );
+        assert_eq!(
+            parse("- add: sphere\n  transform:\n    - [translate, 1, 2]"),
+            "unknown transform 'translate' with 2 arguments"
+        );
+        assert_eq!(
+            parse("- add: light\n  at: [0, 0, x]\n  intensity: [1, 1, 1]"),
+            "expected a number, got 'x'"
+        );
+        assert_eq!(
+            parse("- add: sphere\n  transform:\n    - [scale, 0, 1, 1]"),
+            "transform is not invertible"
+        );
+    }
+}
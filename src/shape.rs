@@ -0,0 +1,294 @@
+use std::fmt;
+
+use crate::bounds::BoundingBox;
+use crate::intersection::Intersection;
+use crate::material::Material;
+use crate::matrix::Matrix4;
+use crate::ray::Ray;
+use crate::tuple::Tuple;
+
+// State every shape carries, the inverse is cached since each ray needs it
+#[derive(PartialEq, Debug, Clone)]
+pub struct ShapeBase {
+    transform: Matrix4,
+    inverse_transform: Matrix4,
+    // Inverse of every group transform above the shape, identity at the top level
+    parent_inverse: Matrix4,
+    world_inverse: Matrix4,
+    pub material: Material,
+}
+
+impl ShapeBase {
+    pub fn new() -> Self {
+        ShapeBase {
+            transform: Matrix4::identity(),
+            inverse_transform: Matrix4::identity(),
+            parent_inverse: Matrix4::identity(),
+            world_inverse: Matrix4::identity(),
+            material: Material::new(),
+        }
+    }
+
+    pub fn get_transform(&self) -> &Matrix4 {
+        &self.transform
+    }
+
+    pub fn get_inverse_transform(&self) -> &Matrix4 {
+        &self.inverse_transform
+    }
+
+    // Maps world space straight to object space, including enclosing groups
+    pub fn get_world_inverse_transform(&self) -> &Matrix4 {
+        &self.world_inverse
+    }
+
+    pub fn set_transform(&mut self, transform: Matrix4) -> Result<(), String> {
+        self.inverse_transform = transform.inverse().ok_or("transform is not invertible")?;
+        self.transform = transform;
+        self.update_world_inverse();
+        Ok(())
+    }
+
+    pub fn set_parent_inverse(&mut self, parent_inverse: Matrix4) {
+        self.parent_inverse = parent_inverse;
+        self.update_world_inverse();
+    }
+
+    fn update_world_inverse(&mut self) {
+        self.world_inverse = self.inverse_transform * self.parent_inverse;
+    }
+}
+
+impl Default for ShapeBase {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub trait Shape: fmt::Debug + Send + Sync {
+    fn get_base(&self) -> &ShapeBase;
+    fn get_base_mut(&mut self) -> &mut ShapeBase;
+
+    // Both operate in object space, i.e. with the shape's transform already undone
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>>;
+    fn local_normal_at(&self, point: &Tuple) -> Tuple;
+    fn bounds(&self) -> BoundingBox;
+
+    // Shapes that interpolate normals across their surface can use the u/v of the hit
+    fn local_normal_at_hit(&self, point: &Tuple, _hit: &Intersection) -> Tuple {
+        self.local_normal_at(point)
+    }
+
+    // Shapes that contain other shapes expose them here so the scene can be walked
+    fn get_children(&self) -> &[Box<dyn Shape>] {
+        &[]
+    }
+
+    fn get_children_mut(&mut self) -> &mut [Box<dyn Shape>] {
+        &mut []
+    }
+
+    // Shapes that pass rays on somewhere else return the world space transform
+    // taking them there
+    fn get_portal_exit(&self) -> Option<Matrix4> {
+        None
+    }
+
+    fn get_transform(&self) -> &Matrix4 {
+        self.get_base().get_transform()
+    }
+
+    fn set_transform(&mut self, transform: Matrix4) -> Result<(), String> {
+        self.get_base_mut().set_transform(transform)
+    }
+
+    // Called by groups when the shape is added or a transform above it changes
+    fn set_parent_inverse(&mut self, parent_inverse: Matrix4) {
+        self.get_base_mut().set_parent_inverse(parent_inverse)
+    }
+
+    fn get_material(&self) -> &Material {
+        &self.get_base().material
+    }
+
+    fn get_material_mut(&mut self) -> &mut Material {
+        &mut self.get_base_mut().material
+    }
+
+    fn set_material(&mut self, material: Material) {
+        self.get_base_mut().material = material;
+    }
+
+    fn parent_space_bounds(&self) -> BoundingBox {
+        self.bounds().transform(self.get_transform())
+    }
+
+    // Splits groups with at least threshold children into sub-groups, other
+    // shapes have nothing to divide
+    fn divide(&mut self, _threshold: usize) {}
+
+    fn intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        let local_ray = ray.transform(self.get_base().get_inverse_transform());
+        self.local_intersect(&local_ray)
+    }
+
+    fn normal_at(&self, world_point: &Tuple) -> Tuple {
+        let local_point = world_to_object(self.get_base(), world_point);
+        let local_normal = self.local_normal_at(&local_point);
+        normal_to_world(self.get_base(), &local_normal)
+    }
+
+    fn normal_at_hit(&self, world_point: &Tuple, hit: &Intersection) -> Tuple {
+        let local_point = world_to_object(self.get_base(), world_point);
+        let local_normal = self.local_normal_at_hit(&local_point, hit);
+        normal_to_world(self.get_base(), &local_normal)
+    }
+}
+
+fn world_to_object(base: &ShapeBase, point: &Tuple) -> Tuple {
+    *base.get_world_inverse_transform() * point
+}
+
+fn normal_to_world(base: &ShapeBase, normal: &Tuple) -> Tuple {
+    let world_normal = base.get_world_inverse_transform().transpose() * normal;
+    Tuple::vector(world_normal.0, world_normal.1, world_normal.2).normalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    #[derive(Debug)]
+    struct TestShape {
+        base: ShapeBase,
+        saved_ray: Mutex<Option<Ray>>,
+    }
+
+    impl TestShape {
+        fn new() -> Self {
+            TestShape {
+                base: ShapeBase::new(),
+                saved_ray: Mutex::new(None),
+            }
+        }
+    }
+
+    impl Shape for TestShape {
+        fn get_base(&self) -> &ShapeBase {
+            &self.base
+        }
+
+        fn get_base_mut(&mut self) -> &mut ShapeBase {
+            &mut self.base
+        }
+
+        fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+            *self.saved_ray.lock().unwrap() = Some(ray.clone());
+            vec![]
+        }
+
+        fn local_normal_at(&self, point: &Tuple) -> Tuple {
+            Tuple::vector(point.0, point.1, point.2)
+        }
+
+        fn bounds(&self) -> BoundingBox {
+            BoundingBox::new(Tuple::point(-1., -1., -1.), Tuple::point(1., 1., 1.))
+        }
+    }
+
+    #[test]
+    fn default_transformation() {
+        let s = TestShape::new();
+        assert_eq!(s.get_transform(), &Matrix4::identity());
+    }
+
+    #[test]
+    fn assign_transformation() {
+        let mut s = TestShape::new();
+        s.set_transform(Matrix4::translation(2., 3., 4.)).unwrap();
+        assert_eq!(s.get_transform(), &Matrix4::translation(2., 3., 4.));
+    }
+
+    #[test]
+    fn reject_invalid_transformation() {
+        let mut s = TestShape::new();
+        assert!(s.set_transform(Matrix4::scaling(0., 1., 1.)).is_err());
+        assert_eq!(s.get_transform(), &Matrix4::identity());
+    }
+
+    #[test]
+    fn shapes_have_no_children_by_default() {
+        let mut s = TestShape::new();
+        assert!(s.get_children().is_empty());
+        assert!(s.get_children_mut().is_empty());
+    }
+
+    #[test]
+    fn default_material() {
+        let s = TestShape::new();
+        assert_eq!(s.get_material(), &Material::new());
+    }
+
+    #[test]
+    fn assign_material() {
+        let mut s = TestShape::new();
+        let mut m = Material::new();
+        m.ambient = 1.;
+        s.set_material(m.clone());
+        assert_eq!(s.get_material(), &m);
+        s.get_material_mut().diffuse = 0.5;
+        assert_eq!(s.get_material().diffuse, 0.5);
+    }
+
+    #[test]
+    fn intersect_scaled_shape_with_ray() {
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let mut s = TestShape::new();
+        s.set_transform(Matrix4::scaling(2., 2., 2.)).unwrap();
+        s.intersect(&r);
+        let saved_ray = s.saved_ray.lock().unwrap().clone().unwrap();
+        assert_eq!(saved_ray.origin, Tuple::point(0., 0., -2.5));
+        assert_eq!(saved_ray.direction, Tuple::vector(0., 0., 0.5));
+    }
+
+    #[test]
+    fn intersect_translated_shape_with_ray() {
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let mut s = TestShape::new();
+        s.set_transform(Matrix4::translation(5., 0., 0.)).unwrap();
+        s.intersect(&r);
+        let saved_ray = s.saved_ray.lock().unwrap().clone().unwrap();
+        assert_eq!(saved_ray.origin, Tuple::point(-5., 0., -5.));
+        assert_eq!(saved_ray.direction, Tuple::vector(0., 0., 1.));
+    }
+
+    #[test]
+    fn bounds_in_parent_space() {
+        let mut s = TestShape::new();
+        s.set_transform(Matrix4::translation(1., -3., 5.) * Matrix4::scaling(0.5, 2., 4.))
+            .unwrap();
+        let b = s.parent_space_bounds();
+        assert_eq!(b.min, Tuple::point(0.5, -5., 1.));
+        assert_eq!(b.max, Tuple::point(1.5, -1., 9.));
+    }
+
+    #[test]
+    fn normal_on_translated_shape() {
+        let mut s = TestShape::new();
+        s.set_transform(Matrix4::translation(0., 1., 0.)).unwrap();
+        let v = std::f32::consts::FRAC_1_SQRT_2;
+        let n = s.normal_at(&Tuple::point(0., 1. + v, -v));
+        assert!(n.approx_eq(&Tuple::vector(0., v, -v)));
+    }
+
+    #[test]
+    fn normal_on_scaled_shape() {
+        let mut s = TestShape::new();
+        s.set_transform(Matrix4::scaling(1., 0.5, 1.)).unwrap();
+        let v = 2_f32.sqrt() / 2.;
+        let n = s.normal_at(&Tuple::point(0., v, -v));
+        assert!(n.approx_eq(&Tuple::vector(0., 0.97014, -0.24254)));
+    }
+}
@@ -0,0 +1,179 @@
+use crate::bounds::BoundingBox;
+use crate::intersection::Intersection;
+use crate::ray::Ray;
+use crate::shape::{Shape, ShapeBase};
+use crate::triangle::moller_trumbore;
+use crate::tuple::Tuple;
+
+// Triangle with a normal per vertex that are interpolated across the face
+#[derive(PartialEq, Debug, Clone)]
+pub struct SmoothTriangle {
+    base: ShapeBase,
+    p1: Tuple,
+    p2: Tuple,
+    p3: Tuple,
+    n1: Tuple,
+    n2: Tuple,
+    n3: Tuple,
+    e1: Tuple,
+    e2: Tuple,
+}
+
+impl SmoothTriangle {
+    pub fn new(p1: Tuple, p2: Tuple, p3: Tuple, n1: Tuple, n2: Tuple, n3: Tuple) -> Self {
+        let e1 = p2.clone() - p1.clone();
+        let e2 = p3.clone() - p1.clone();
+        SmoothTriangle {
+            base: ShapeBase::new(),
+            p1,
+            p2,
+            p3,
+            n1,
+            n2,
+            n3,
+            e1,
+            e2,
+        }
+    }
+
+    pub fn get_p1(&self) -> &Tuple {
+        &self.p1
+    }
+
+    pub fn get_p2(&self) -> &Tuple {
+        &self.p2
+    }
+
+    pub fn get_p3(&self) -> &Tuple {
+        &self.p3
+    }
+
+    pub fn get_n1(&self) -> &Tuple {
+        &self.n1
+    }
+
+    pub fn get_n2(&self) -> &Tuple {
+        &self.n2
+    }
+
+    pub fn get_n3(&self) -> &Tuple {
+        &self.n3
+    }
+
+    fn interpolate_normal(&self, u: f32, v: f32) -> Tuple {
+        self.n2.clone() * u + self.n3.clone() * v + self.n1.clone() * (1. - u - v)
+    }
+}
+
+impl Shape for SmoothTriangle {
+    fn get_base(&self) -> &ShapeBase {
+        &self.base
+    }
+
+    fn get_base_mut(&mut self) -> &mut ShapeBase {
+        &mut self.base
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        match moller_trumbore(ray, &self.p1, &self.e1, &self.e2) {
+            Some((t, u, v)) => vec![Intersection::with_uv(t, self, u, v)],
+            None => vec![],
+        }
+    }
+
+    // Without a hit the barycentric coordinates are recovered from the point itself
+    fn local_normal_at(&self, point: &Tuple) -> Tuple {
+        let p = point.clone() - self.p1.clone();
+        let d00 = self.e1.dot(&self.e1);
+        let d01 = self.e1.dot(&self.e2);
+        let d11 = self.e2.dot(&self.e2);
+        let d20 = p.dot(&self.e1);
+        let d21 = p.dot(&self.e2);
+        let denom = d00 * d11 - d01 * d01;
+        let u = (d11 * d20 - d01 * d21) / denom;
+        let v = (d00 * d21 - d01 * d20) / denom;
+        self.interpolate_normal(u, v)
+    }
+
+    fn local_normal_at_hit(&self, point: &Tuple, hit: &Intersection) -> Tuple {
+        match hit.uv {
+            Some((u, v)) => self.interpolate_normal(u, v),
+            None => self.local_normal_at(point),
+        }
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        let mut bounds = BoundingBox::empty();
+        for point in [&self.p1, &self.p2, &self.p3] {
+            bounds.add_point(point);
+        }
+        bounds
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_cmp::approx_eq;
+
+    use super::*;
+    use crate::intersection::Intersections;
+
+    fn smooth_triangle() -> SmoothTriangle {
+        SmoothTriangle::new(
+            Tuple::point(0., 1., 0.),
+            Tuple::point(-1., 0., 0.),
+            Tuple::point(1., 0., 0.),
+            Tuple::vector(0., 1., 0.),
+            Tuple::vector(-1., 0., 0.),
+            Tuple::vector(1., 0., 0.),
+        )
+    }
+
+    #[test]
+    fn construct_smooth_triangle() {
+        let tri = smooth_triangle();
+        assert_eq!(tri.get_p1(), &Tuple::point(0., 1., 0.));
+        assert_eq!(tri.get_p2(), &Tuple::point(-1., 0., 0.));
+        assert_eq!(tri.get_p3(), &Tuple::point(1., 0., 0.));
+        assert_eq!(tri.get_n1(), &Tuple::vector(0., 1., 0.));
+        assert_eq!(tri.get_n2(), &Tuple::vector(-1., 0., 0.));
+        assert_eq!(tri.get_n3(), &Tuple::vector(1., 0., 0.));
+    }
+
+    #[test]
+    fn intersection_stores_uv() {
+        let tri = smooth_triangle();
+        let r = Ray::new(Tuple::point(-0.2, 0.3, -2.), Tuple::vector(0., 0., 1.));
+        let xs = tri.local_intersect(&r);
+        let (u, v) = xs[0].uv.unwrap();
+        assert!(approx_eq!(f32, u, 0.45, epsilon = 0.0001));
+        assert!(approx_eq!(f32, v, 0.25, epsilon = 0.0001));
+    }
+
+    #[test]
+    fn normal_interpolated_with_uv() {
+        let tri = smooth_triangle();
+        let i = Intersection::with_uv(1., &tri, 0.45, 0.25);
+        let n = tri.normal_at_hit(&Tuple::point(0., 0., 0.), &i);
+        assert!(n.approx_eq(&Tuple::vector(-0.5547, 0.83205, 0.)));
+    }
+
+    #[test]
+    fn normal_without_uv_uses_point() {
+        let tri = smooth_triangle();
+        let n = tri.normal_at(&Tuple::point(-0.2, 0.3, 0.));
+        assert!(n.approx_eq(&Tuple::vector(-0.5547, 0.83205, 0.)));
+    }
+
+    #[test]
+    fn prepare_normal_on_smooth_triangle() {
+        let tri = smooth_triangle();
+        let i = Intersection::with_uv(1., &tri, 0.45, 0.25);
+        let r = Ray::new(Tuple::point(-0.2, 0.3, -2.), Tuple::vector(0., 0., 1.));
+        let xs = Intersections::new(vec![i.clone()]);
+        let comps = i.prepare_computations(&r, &xs);
+        assert!(comps
+            .normalv
+            .approx_eq(&Tuple::vector(-0.5547, 0.83205, 0.)));
+    }
+}
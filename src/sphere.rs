@@ -0,0 +1,187 @@
+use crate::bounds::BoundingBox;
+use crate::intersection::Intersection;
+use crate::ray::Ray;
+use crate::shape::{Shape, ShapeBase};
+use crate::tuple::Tuple;
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct Sphere {
+    base: ShapeBase,
+}
+
+impl Sphere {
+    pub fn new() -> Self {
+        Sphere {
+            base: ShapeBase::new(),
+        }
+    }
+
+    pub fn glass() -> Self {
+        let mut sphere = Sphere::new();
+        sphere.base.material.transparency = 1.;
+        sphere.base.material.refractive_index = 1.5;
+        sphere
+    }
+}
+
+impl Default for Sphere {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shape for Sphere {
+    fn get_base(&self) -> &ShapeBase {
+        &self.base
+    }
+
+    fn get_base_mut(&mut self) -> &mut ShapeBase {
+        &mut self.base
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        let sphere_to_ray = ray.origin.clone() - Tuple::point(0., 0., 0.);
+        let a = ray.direction.dot(&ray.direction);
+        let b = 2. * ray.direction.dot(&sphere_to_ray);
+        let c = sphere_to_ray.dot(&sphere_to_ray) - 1.;
+
+        let discriminant = b.powf(2.) - 4. * a * c;
+        if discriminant < 0. {
+            return vec![];
+        }
+
+        let t1 = (-b - discriminant.sqrt()) / (2. * a);
+        let t2 = (-b + discriminant.sqrt()) / (2. * a);
+        vec![Intersection::new(t1, self), Intersection::new(t2, self)]
+    }
+
+    fn local_normal_at(&self, point: &Tuple) -> Tuple {
+        point.clone() - Tuple::point(0., 0., 0.)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(Tuple::point(-1., -1., -1.), Tuple::point(1., 1., 1.))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matrix::Matrix4;
+
+    #[test]
+    fn ray_intersects_sphere_at_two_points() {
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let s = Sphere::new();
+        let xs = s.intersect(&r);
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].t, 4.);
+        assert_eq!(xs[1].t, 6.);
+    }
+
+    #[test]
+    fn ray_intersects_sphere_at_tangent() {
+        let r = Ray::new(Tuple::point(0., 1., -5.), Tuple::vector(0., 0., 1.));
+        let s = Sphere::new();
+        let xs = s.intersect(&r);
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].t, 5.);
+        assert_eq!(xs[1].t, 5.);
+    }
+
+    #[test]
+    fn ray_misses_sphere() {
+        let r = Ray::new(Tuple::point(0., 2., -5.), Tuple::vector(0., 0., 1.));
+        let s = Sphere::new();
+        assert!(s.intersect(&r).is_empty());
+    }
+
+    #[test]
+    fn ray_originates_inside_sphere() {
+        let r = Ray::new(Tuple::point(0., 0., 0.), Tuple::vector(0., 0., 1.));
+        let s = Sphere::new();
+        let xs = s.intersect(&r);
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].t, -1.);
+        assert_eq!(xs[1].t, 1.);
+    }
+
+    #[test]
+    fn sphere_behind_ray() {
+        let r = Ray::new(Tuple::point(0., 0., 5.), Tuple::vector(0., 0., 1.));
+        let s = Sphere::new();
+        let xs = s.intersect(&r);
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].t, -6.);
+        assert_eq!(xs[1].t, -4.);
+    }
+
+    #[test]
+    fn intersect_sets_object() {
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let s = Sphere::new();
+        let xs = s.intersect(&r);
+        assert!(std::ptr::addr_eq(xs[0].object, &s));
+        assert!(std::ptr::addr_eq(xs[1].object, &s));
+    }
+
+    #[test]
+    fn intersect_scaled_sphere() {
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let mut s = Sphere::new();
+        s.set_transform(Matrix4::scaling(2., 2., 2.)).unwrap();
+        let xs = s.intersect(&r);
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].t, 3.);
+        assert_eq!(xs[1].t, 7.);
+    }
+
+    #[test]
+    fn intersect_translated_sphere() {
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let mut s = Sphere::new();
+        s.set_transform(Matrix4::translation(5., 0., 0.)).unwrap();
+        assert!(s.intersect(&r).is_empty());
+    }
+
+    #[test]
+    fn normal_on_sphere_axes() {
+        let s = Sphere::new();
+        assert_eq!(
+            s.normal_at(&Tuple::point(1., 0., 0.)),
+            Tuple::vector(1., 0., 0.)
+        );
+        assert_eq!(
+            s.normal_at(&Tuple::point(0., 1., 0.)),
+            Tuple::vector(0., 1., 0.)
+        );
+        assert_eq!(
+            s.normal_at(&Tuple::point(0., 0., 1.)),
+            Tuple::vector(0., 0., 1.)
+        );
+    }
+
+    #[test]
+    fn normal_on_sphere_nonaxial_point() {
+        let s = Sphere::new();
+        let v = 3_f32.sqrt() / 3.;
+        let n = s.normal_at(&Tuple::point(v, v, v));
+        assert!(n.approx_eq(&Tuple::vector(v, v, v)));
+        assert!(n.approx_eq(&n.normalize()));
+    }
+
+    #[test]
+    fn glass_sphere() {
+        let s = Sphere::glass();
+        assert_eq!(s.get_transform(), &Matrix4::identity());
+        assert_eq!(s.get_material().transparency, 1.);
+        assert_eq!(s.get_material().refractive_index, 1.5);
+    }
+
+    #[test]
+    fn sphere_has_bounding_box() {
+        let b = Sphere::new().bounds();
+        assert_eq!(b.min, Tuple::point(-1., -1., -1.));
+        assert_eq!(b.max, Tuple::point(1., 1., 1.));
+    }
+}
@@ -0,0 +1,208 @@
+use std::cell::Cell;
+
+use crate::canvas::Canvas;
+use crate::tuple::Tuple;
+
+// How much work went into a pixel. Counting happens per thread, so a render
+// can read the counters after each pixel without any synchronization.
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub struct RayStats {
+    pub primary_rays: u64,
+    pub secondary_rays: u64,
+    pub shadow_rays: u64,
+    pub bvh_node_visits: u64,
+}
+
+thread_local! {
+    static STATS: Cell<RayStats> = Cell::new(RayStats::default());
+}
+
+impl RayStats {
+    // Returns the counters of the calling thread and resets them
+    pub fn take() -> RayStats {
+        STATS.with(|stats| stats.replace(RayStats::default()))
+    }
+
+    pub(crate) fn record(update: impl FnOnce(&mut RayStats)) {
+        STATS.with(|stats| {
+            let mut current = stats.get();
+            update(&mut current);
+            stats.set(current);
+        });
+    }
+
+    pub fn get(&self, counter: RayCounter) -> u64 {
+        match counter {
+            RayCounter::Primary => self.primary_rays,
+            RayCounter::Secondary => self.secondary_rays,
+            RayCounter::Shadow => self.shadow_rays,
+            RayCounter::BvhNodes => self.bvh_node_visits,
+            RayCounter::AllRays => self.primary_rays + self.secondary_rays + self.shadow_rays,
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum RayCounter {
+    Primary,
+    Secondary,
+    Shadow,
+    BvhNodes,
+    AllRays,
+}
+
+// Per-pixel statistics of a whole render, row-major like a canvas
+#[derive(PartialEq, Debug, Clone)]
+pub struct RayStatsMap {
+    width: usize,
+    height: usize,
+    pixels: Vec<RayStats>,
+}
+
+impl RayStatsMap {
+    pub fn new(width: usize, height: usize) -> Self {
+        RayStatsMap {
+            width,
+            height,
+            pixels: vec![RayStats::default(); width * height],
+        }
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get_stats_at(&self, x: usize, y: usize) -> Result<&RayStats, String> {
+        if x >= self.width {
+            return Err(format!("x {} out of range", x));
+        }
+        if y >= self.height {
+            return Err(format!("y {} out of range", y));
+        }
+        Ok(&self.pixels[y * self.width + x])
+    }
+
+    pub fn write_stats_at(&mut self, x: usize, y: usize, stats: RayStats) -> Result<(), String> {
+        if x >= self.width {
+            return Err(format!("x {} out of range", x));
+        }
+        if y >= self.height {
+            return Err(format!("y {} out of range", y));
+        }
+        self.pixels[y * self.width + x] = stats;
+        Ok(())
+    }
+
+    pub fn total(&self) -> RayStats {
+        self.pixels
+            .iter()
+            .fold(RayStats::default(), |sum, s| RayStats {
+                primary_rays: sum.primary_rays + s.primary_rays,
+                secondary_rays: sum.secondary_rays + s.secondary_rays,
+                shadow_rays: sum.shadow_rays + s.shadow_rays,
+                bvh_node_visits: sum.bvh_node_visits + s.bvh_node_visits,
+            })
+    }
+
+    // Scales the counter by its maximum over the image and maps it from black
+    // through red and yellow to white
+    pub fn heatmap(&self, counter: RayCounter) -> Canvas {
+        let max = self
+            .pixels
+            .iter()
+            .map(|s| s.get(counter))
+            .max()
+            .unwrap_or(0);
+        let mut canvas = Canvas::new(self.width, self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let value = self.pixels[y * self.width + x].get(counter);
+                let t = if max == 0 {
+                    0.
+                } else {
+                    value as f32 / max as f32
+                };
+                canvas
+                    .write_pixel_at(x, y, heat_color(t))
+                    .expect("Stats map and canvas have the same size");
+            }
+        }
+        canvas
+    }
+}
+
+fn heat_color(t: f32) -> Tuple {
+    let t = t.clamp(0., 1.) * 3.;
+    Tuple::color(t.min(1.), (t - 1.).clamp(0., 1.), (t - 2.).clamp(0., 1.))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_and_take_thread_stats() {
+        RayStats::take();
+        RayStats::record(|s| s.primary_rays += 1);
+        RayStats::record(|s| s.shadow_rays += 2);
+        let stats = RayStats::take();
+        assert_eq!(stats.primary_rays, 1);
+        assert_eq!(stats.shadow_rays, 2);
+        assert_eq!(stats.get(RayCounter::AllRays), 3);
+        assert_eq!(RayStats::take(), RayStats::default());
+    }
+
+    #[test]
+    fn stats_are_counted_per_thread() {
+        RayStats::take();
+        std::thread::spawn(|| RayStats::record(|s| s.primary_rays += 5))
+            .join()
+            .unwrap();
+        assert_eq!(RayStats::take().primary_rays, 0);
+    }
+
+    #[test]
+    fn stats_map_bounds() {
+        let mut map = RayStatsMap::new(2, 3);
+        assert!(map.write_stats_at(2, 0, RayStats::default()).is_err());
+        assert!(map.get_stats_at(0, 3).is_err());
+        let stats = RayStats {
+            secondary_rays: 4,
+            ..RayStats::default()
+        };
+        map.write_stats_at(1, 2, stats).unwrap();
+        assert_eq!(map.get_stats_at(1, 2).unwrap(), &stats);
+        assert_eq!(map.total().secondary_rays, 4);
+    }
+
+    #[test]
+    fn heatmap_scales_by_maximum() {
+        let mut map = RayStatsMap::new(3, 1);
+        for (x, rays) in [0, 3, 6].into_iter().enumerate() {
+            let stats = RayStats {
+                shadow_rays: rays,
+                ..RayStats::default()
+            };
+            map.write_stats_at(x, 0, stats).unwrap();
+        }
+        let heatmap = map.heatmap(RayCounter::Shadow);
+        assert_eq!(
+            heatmap.get_pixel_at(0, 0).unwrap(),
+            &Tuple::color(0., 0., 0.)
+        );
+        assert!(heatmap
+            .get_pixel_at(1, 0)
+            .unwrap()
+            .approx_eq(&Tuple::color(1., 0.5, 0.)));
+        assert_eq!(
+            heatmap.get_pixel_at(2, 0).unwrap(),
+            &Tuple::color(1., 1., 1.)
+        );
+        let empty = map.heatmap(RayCounter::Primary);
+        assert_eq!(empty.get_pixel_at(2, 0).unwrap(), &Tuple::color(0., 0., 0.));
+    }
+}
@@ -0,0 +1,510 @@
+use std::f32::consts::PI;
+use std::fmt;
+
+use crate::canvas::Canvas;
+use crate::pattern::{Pattern, PatternBase};
+use crate::tuple::Tuple;
+
+// Ways of unwrapping a 3D point into 2D texture coordinates, u and v are in [0, 1)
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum UvMapping {
+    Spherical,
+    Planar,
+    Cylindrical,
+}
+
+impl UvMapping {
+    pub fn map(&self, point: &Tuple) -> (f32, f32) {
+        match self {
+            UvMapping::Spherical => spherical_map(point),
+            UvMapping::Planar => planar_map(point),
+            UvMapping::Cylindrical => cylindrical_map(point),
+        }
+    }
+}
+
+fn spherical_map(point: &Tuple) -> (f32, f32) {
+    // Azimuth around the y axis and polar angle from the north pole
+    let theta = point.0.atan2(point.2);
+    let radius = Tuple::vector(point.0, point.1, point.2).mag();
+    let phi = (point.1 / radius).acos();
+    let raw_u = theta / (2. * PI);
+    (1. - (raw_u + 0.5), 1. - phi / PI)
+}
+
+fn planar_map(point: &Tuple) -> (f32, f32) {
+    (point.0.rem_euclid(1.), point.2.rem_euclid(1.))
+}
+
+fn cylindrical_map(point: &Tuple) -> (f32, f32) {
+    let theta = point.0.atan2(point.2);
+    let raw_u = theta / (2. * PI);
+    (1. - (raw_u + 0.5), point.1.rem_euclid(1.))
+}
+
+// Face of the unit cube a point lies on, in the order CubeMapPattern expects faces
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum CubeFace {
+    Left,
+    Front,
+    Right,
+    Back,
+    Up,
+    Down,
+}
+
+impl CubeFace {
+    pub fn from_point(point: &Tuple) -> Self {
+        let coord = point.0.abs().max(point.1.abs()).max(point.2.abs());
+        if coord == point.0 {
+            CubeFace::Right
+        } else if coord == -point.0 {
+            CubeFace::Left
+        } else if coord == point.1 {
+            CubeFace::Up
+        } else if coord == -point.1 {
+            CubeFace::Down
+        } else if coord == point.2 {
+            CubeFace::Front
+        } else {
+            CubeFace::Back
+        }
+    }
+
+    // Each face is unwrapped as seen from outside the cube with up pointing up,
+    // the top and bottom faces as seen from the front
+    pub fn map(&self, point: &Tuple) -> (f32, f32) {
+        let (x, y, z) = (point.0, point.1, point.2);
+        let (u, v) = match self {
+            CubeFace::Front => (x + 1., y + 1.),
+            CubeFace::Back => (1. - x, y + 1.),
+            CubeFace::Left => (z + 1., y + 1.),
+            CubeFace::Right => (1. - z, y + 1.),
+            CubeFace::Up => (x + 1., 1. - z),
+            CubeFace::Down => (x + 1., z + 1.),
+        };
+        (u.rem_euclid(2.) / 2., v.rem_euclid(2.) / 2.)
+    }
+}
+
+pub trait UvPattern: fmt::Debug + Send + Sync {
+    fn uv_pattern_at(&self, u: f32, v: f32) -> Tuple;
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct UvCheckers {
+    pub width: f32,
+    pub height: f32,
+    pub a: Tuple,
+    pub b: Tuple,
+}
+
+impl UvCheckers {
+    pub fn new(width: f32, height: f32, a: Tuple, b: Tuple) -> Self {
+        UvCheckers {
+            width,
+            height,
+            a,
+            b,
+        }
+    }
+}
+
+impl UvPattern for UvCheckers {
+    fn uv_pattern_at(&self, u: f32, v: f32) -> Tuple {
+        let u2 = (u * self.width).floor() as i64;
+        let v2 = (v * self.height).floor() as i64;
+        if (u2 + v2).rem_euclid(2) == 0 {
+            self.a.clone()
+        } else {
+            self.b.clone()
+        }
+    }
+}
+
+// Solid color with a differently colored square in each corner, useful to check
+// how textures end up oriented
+#[derive(PartialEq, Debug, Clone)]
+pub struct UvAlignCheck {
+    pub main: Tuple,
+    pub upper_left: Tuple,
+    pub upper_right: Tuple,
+    pub bottom_left: Tuple,
+    pub bottom_right: Tuple,
+}
+
+impl UvPattern for UvAlignCheck {
+    fn uv_pattern_at(&self, u: f32, v: f32) -> Tuple {
+        if v > 0.8 {
+            if u < 0.2 {
+                return self.upper_left.clone();
+            }
+            if u > 0.8 {
+                return self.upper_right.clone();
+            }
+        } else if v < 0.2 {
+            if u < 0.2 {
+                return self.bottom_left.clone();
+            }
+            if u > 0.8 {
+                return self.bottom_right.clone();
+            }
+        }
+        self.main.clone()
+    }
+}
+
+// Samples the nearest pixel of an image, v runs from the bottom row to the top one
+#[derive(Debug, Clone)]
+pub struct UvImage {
+    canvas: Canvas,
+}
+
+impl UvImage {
+    pub fn new(canvas: Canvas) -> Self {
+        UvImage { canvas }
+    }
+
+    pub fn get_canvas(&self) -> &Canvas {
+        &self.canvas
+    }
+}
+
+impl UvPattern for UvImage {
+    fn uv_pattern_at(&self, u: f32, v: f32) -> Tuple {
+        let v = 1. - v;
+        let x = (u * self.canvas.get_width().saturating_sub(1) as f32).round();
+        let y = (v * self.canvas.get_height().saturating_sub(1) as f32).round();
+        self.canvas
+            .get_pixel_at(x as usize, y as usize)
+            .cloned()
+            .unwrap_or(Tuple::color(0., 0., 0.))
+    }
+}
+
+// Wraps a 2D pattern around an object with one of the UV mappings
+#[derive(Debug)]
+pub struct TextureMapPattern {
+    base: PatternBase,
+    pub uv_pattern: Box<dyn UvPattern>,
+    pub mapping: UvMapping,
+}
+
+impl TextureMapPattern {
+    pub fn new(uv_pattern: Box<dyn UvPattern>, mapping: UvMapping) -> Self {
+        TextureMapPattern {
+            base: PatternBase::new(),
+            uv_pattern,
+            mapping,
+        }
+    }
+}
+
+impl Pattern for TextureMapPattern {
+    fn get_base(&self) -> &PatternBase {
+        &self.base
+    }
+
+    fn get_base_mut(&mut self) -> &mut PatternBase {
+        &mut self.base
+    }
+
+    fn local_pattern_at(&self, point: &Tuple) -> Tuple {
+        let (u, v) = self.mapping.map(point);
+        self.uv_pattern.uv_pattern_at(u, v)
+    }
+}
+
+// One 2D pattern per cube face, e.g. the six images of a skybox
+#[derive(Debug)]
+pub struct CubeMapPattern {
+    base: PatternBase,
+    // Ordered left, front, right, back, up, down like CubeFace
+    pub faces: [Box<dyn UvPattern>; 6],
+}
+
+impl CubeMapPattern {
+    pub fn new(faces: [Box<dyn UvPattern>; 6]) -> Self {
+        CubeMapPattern {
+            base: PatternBase::new(),
+            faces,
+        }
+    }
+}
+
+impl Pattern for CubeMapPattern {
+    fn get_base(&self) -> &PatternBase {
+        &self.base
+    }
+
+    fn get_base_mut(&mut self) -> &mut PatternBase {
+        &mut self.base
+    }
+
+    fn local_pattern_at(&self, point: &Tuple) -> Tuple {
+        let face = CubeFace::from_point(point);
+        let (u, v) = face.map(point);
+        self.faces[face as usize].uv_pattern_at(u, v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_cmp::approx_eq;
+
+    use super::*;
+
+    fn white() -> Tuple {
+        Tuple::color(1., 1., 1.)
+    }
+
+    fn black() -> Tuple {
+        Tuple::color(0., 0., 0.)
+    }
+
+    fn assert_uv(actual: (f32, f32), expected: (f32, f32)) {
+        assert!(
+            approx_eq!(f32, actual.0, expected.0, epsilon = 0.0001)
+                && approx_eq!(f32, actual.1, expected.1, epsilon = 0.0001),
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn checker_pattern_in_2d() {
+        let checkers = UvCheckers::new(2., 2., black(), white());
+        let cases = [
+            (0., 0., black()),
+            (0.5, 0., white()),
+            (0., 0.5, white()),
+            (0.5, 0.5, black()),
+            (1., 1., black()),
+        ];
+        for (u, v, expected) in cases {
+            assert_eq!(checkers.uv_pattern_at(u, v), expected);
+        }
+    }
+
+    #[test]
+    fn spherical_mapping_on_3d_point() {
+        let v = 2_f32.sqrt() / 2.;
+        let cases = [
+            (Tuple::point(0., 0., -1.), (0., 0.5)),
+            (Tuple::point(1., 0., 0.), (0.25, 0.5)),
+            (Tuple::point(0., 0., 1.), (0.5, 0.5)),
+            (Tuple::point(-1., 0., 0.), (0.75, 0.5)),
+            (Tuple::point(0., 1., 0.), (0.5, 1.)),
+            (Tuple::point(0., -1., 0.), (0.5, 0.)),
+            (Tuple::point(v, v, 0.), (0.25, 0.75)),
+        ];
+        for (point, expected) in cases {
+            assert_uv(UvMapping::Spherical.map(&point), expected);
+        }
+    }
+
+    #[test]
+    fn texture_map_pattern_with_spherical_map() {
+        let checkers = UvCheckers::new(16., 8., black(), white());
+        let pattern = TextureMapPattern::new(Box::new(checkers), UvMapping::Spherical);
+        let cases = [
+            (0.4315, 0.467, 0.7719, white()),
+            (-0.9654, 0.2552, -0.0534, black()),
+            (0.1039, 0.709, 0.6975, white()),
+            (-0.4986, -0.7856, -0.3663, black()),
+            (-0.0317, -0.9395, 0.3411, black()),
+            (0.4809, -0.7721, 0.4154, black()),
+            (0.0285, -0.9612, -0.2745, black()),
+            (-0.5734, -0.2162, -0.7903, white()),
+            (0.7688, -0.147, 0.6223, black()),
+            (-0.7652, 0.2175, 0.606, black()),
+        ];
+        for (x, y, z, expected) in cases {
+            assert_eq!(pattern.local_pattern_at(&Tuple::point(x, y, z)), expected);
+        }
+    }
+
+    #[test]
+    fn planar_mapping_on_3d_point() {
+        let cases = [
+            (Tuple::point(0.25, 0., 0.5), (0.25, 0.5)),
+            (Tuple::point(0.25, 0., -0.25), (0.25, 0.75)),
+            (Tuple::point(0.25, 0.5, -0.25), (0.25, 0.75)),
+            (Tuple::point(1.25, 0., 0.5), (0.25, 0.5)),
+            (Tuple::point(0.25, 0., -1.75), (0.25, 0.25)),
+            (Tuple::point(1., 0., -1.), (0., 0.)),
+            (Tuple::point(0., 0., 0.), (0., 0.)),
+        ];
+        for (point, expected) in cases {
+            assert_uv(UvMapping::Planar.map(&point), expected);
+        }
+    }
+
+    #[test]
+    fn cylindrical_mapping_on_3d_point() {
+        let v = 2_f32.sqrt() / 2.;
+        let cases = [
+            (Tuple::point(0., 0., -1.), (0., 0.)),
+            (Tuple::point(0., 0.5, -1.), (0., 0.5)),
+            (Tuple::point(0., 1., -1.), (0., 0.)),
+            (Tuple::point(v, 0.5, -v), (0.125, 0.5)),
+            (Tuple::point(1., 0.5, 0.), (0.25, 0.5)),
+            (Tuple::point(v, 0.5, v), (0.375, 0.5)),
+            (Tuple::point(0., -0.25, 1.), (0.5, 0.75)),
+            (Tuple::point(-v, 0.5, v), (0.625, 0.5)),
+            (Tuple::point(-1., 1.25, 0.), (0.75, 0.25)),
+            (Tuple::point(-v, 0.5, -v), (0.875, 0.5)),
+        ];
+        for (point, expected) in cases {
+            assert_uv(UvMapping::Cylindrical.map(&point), expected);
+        }
+    }
+
+    fn align_check(main: f32, ul: f32, ur: f32, bl: f32, br: f32) -> UvAlignCheck {
+        let gray = |c: f32| Tuple::color(c, c, c);
+        UvAlignCheck {
+            main: gray(main),
+            upper_left: gray(ul),
+            upper_right: gray(ur),
+            bottom_left: gray(bl),
+            bottom_right: gray(br),
+        }
+    }
+
+    #[test]
+    fn layout_of_align_check_pattern() {
+        let pattern = align_check(1., 0.1, 0.2, 0.3, 0.4);
+        let cases = [
+            (0.5, 0.5, 1.),
+            (0.1, 0.9, 0.1),
+            (0.9, 0.9, 0.2),
+            (0.1, 0.1, 0.3),
+            (0.9, 0.1, 0.4),
+        ];
+        for (u, v, expected) in cases {
+            let c = pattern.uv_pattern_at(u, v);
+            assert_eq!(c, Tuple::color(expected, expected, expected));
+        }
+    }
+
+    #[test]
+    fn identify_face_of_cube_from_point() {
+        let cases = [
+            (Tuple::point(-1., 0.5, -0.25), CubeFace::Left),
+            (Tuple::point(1.1, -0.75, 0.8), CubeFace::Right),
+            (Tuple::point(0.1, 0.6, 0.9), CubeFace::Front),
+            (Tuple::point(-0.7, 0., -2.), CubeFace::Back),
+            (Tuple::point(0.5, 1., 0.9), CubeFace::Up),
+            (Tuple::point(-0.2, -1.3, 1.1), CubeFace::Down),
+        ];
+        for (point, expected) in cases {
+            assert_eq!(CubeFace::from_point(&point), expected);
+        }
+    }
+
+    #[test]
+    fn uv_mapping_of_cube_faces() {
+        let cases = [
+            (CubeFace::Front, Tuple::point(-0.5, 0.5, 1.), (0.25, 0.75)),
+            (CubeFace::Front, Tuple::point(0.5, -0.5, 1.), (0.75, 0.25)),
+            (CubeFace::Back, Tuple::point(0.5, 0.5, -1.), (0.25, 0.75)),
+            (CubeFace::Back, Tuple::point(-0.5, -0.5, -1.), (0.75, 0.25)),
+            (CubeFace::Left, Tuple::point(-1., 0.5, -0.5), (0.25, 0.75)),
+            (CubeFace::Left, Tuple::point(-1., -0.5, 0.5), (0.75, 0.25)),
+            (CubeFace::Right, Tuple::point(1., 0.5, 0.5), (0.25, 0.75)),
+            (CubeFace::Right, Tuple::point(1., -0.5, -0.5), (0.75, 0.25)),
+            (CubeFace::Up, Tuple::point(-0.5, 1., -0.5), (0.25, 0.75)),
+            (CubeFace::Up, Tuple::point(0.5, 1., 0.5), (0.75, 0.25)),
+            (CubeFace::Down, Tuple::point(-0.5, -1., 0.5), (0.25, 0.75)),
+            (CubeFace::Down, Tuple::point(0.5, -1., -0.5), (0.75, 0.25)),
+        ];
+        for (face, point, expected) in cases {
+            assert_uv(face.map(&point), expected);
+        }
+    }
+
+    #[test]
+    fn find_colors_on_mapped_cube() {
+        let red = 0.1;
+        let yellow = 0.2;
+        let brown = 0.3;
+        let green = 0.4;
+        let cyan = 0.5;
+        let blue = 0.6;
+        let purple = 0.7;
+        let white = 0.8;
+        let pattern = CubeMapPattern::new([
+            Box::new(align_check(yellow, cyan, red, blue, brown)),
+            Box::new(align_check(cyan, red, yellow, brown, green)),
+            Box::new(align_check(red, yellow, purple, green, white)),
+            Box::new(align_check(green, purple, cyan, white, blue)),
+            Box::new(align_check(brown, cyan, purple, red, yellow)),
+            Box::new(align_check(purple, brown, green, blue, white)),
+        ]);
+        let cases = [
+            (Tuple::point(-1., 0., 0.), yellow),
+            (Tuple::point(-1., 0.9, -0.9), cyan),
+            (Tuple::point(-1., 0.9, 0.9), red),
+            (Tuple::point(-1., -0.9, -0.9), blue),
+            (Tuple::point(-1., -0.9, 0.9), brown),
+            (Tuple::point(0., 0., 1.), cyan),
+            (Tuple::point(-0.9, 0.9, 1.), red),
+            (Tuple::point(0.9, 0.9, 1.), yellow),
+            (Tuple::point(-0.9, -0.9, 1.), brown),
+            (Tuple::point(0.9, -0.9, 1.), green),
+            (Tuple::point(1., 0., 0.), red),
+            (Tuple::point(1., 0.9, 0.9), yellow),
+            (Tuple::point(1., 0.9, -0.9), purple),
+            (Tuple::point(1., -0.9, 0.9), green),
+            (Tuple::point(1., -0.9, -0.9), white),
+            (Tuple::point(0., 0., -1.), green),
+            (Tuple::point(0.9, 0.9, -1.), purple),
+            (Tuple::point(-0.9, 0.9, -1.), cyan),
+            (Tuple::point(0.9, -0.9, -1.), white),
+            (Tuple::point(-0.9, -0.9, -1.), blue),
+            (Tuple::point(0., 1., 0.), brown),
+            (Tuple::point(-0.9, 1., -0.9), cyan),
+            (Tuple::point(0.9, 1., -0.9), purple),
+            (Tuple::point(-0.9, 1., 0.9), red),
+            (Tuple::point(0.9, 1., 0.9), yellow),
+            (Tuple::point(0., -1., 0.), purple),
+            (Tuple::point(-0.9, -1., 0.9), brown),
+            (Tuple::point(0.9, -1., 0.9), green),
+            (Tuple::point(-0.9, -1., -0.9), blue),
+            (Tuple::point(0.9, -1., -0.9), white),
+        ];
+        for (point, expected) in cases {
+            let c = pattern.local_pattern_at(&point);
+            assert_eq!(c, Tuple::color(expected, expected, expected), "{:?}", point);
+        }
+    }
+
+    #[test]
+    fn image_pattern_samples_canvas() {
+        let mut ppm = String::from("P3\n10 10\n20\n");
+        for y in 0..10 {
+            for x in 0..10 {
+                let c = x + y;
+                ppm.push_str(&format!("{} {} {}\n", c, c, c));
+            }
+        }
+        let pattern = UvImage::new(Canvas::from_ppm(ppm.as_bytes()).unwrap());
+        let cases = [
+            (0., 0., 0.45),
+            (0.3, 0., 0.6),
+            (0.6, 0.3, 0.55),
+            (1., 1., 0.45),
+        ];
+        for (u, v, expected) in cases {
+            let c = pattern.uv_pattern_at(u, v);
+            assert!(c.approx_eq(&Tuple::color(expected, expected, expected)));
+        }
+    }
+
+    #[test]
+    fn image_pattern_on_empty_canvas_is_black() {
+        let pattern = UvImage::new(Canvas::new(0, 0));
+        assert_eq!(pattern.uv_pattern_at(0.5, 0.5), Tuple::color(0., 0., 0.));
+    }
+}
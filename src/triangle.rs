@@ -0,0 +1,194 @@
+use crate::bounds::BoundingBox;
+use crate::intersection::Intersection;
+use crate::ray::Ray;
+use crate::shape::{Shape, ShapeBase};
+use crate::tuple::Tuple;
+use crate::EPSILON;
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct Triangle {
+    base: ShapeBase,
+    p1: Tuple,
+    p2: Tuple,
+    p3: Tuple,
+    e1: Tuple,
+    e2: Tuple,
+    normal: Tuple,
+}
+
+impl Triangle {
+    pub fn new(p1: Tuple, p2: Tuple, p3: Tuple) -> Self {
+        let e1 = p2.clone() - p1.clone();
+        let e2 = p3.clone() - p1.clone();
+        let normal = e2.cross(&e1).normalize();
+        Triangle {
+            base: ShapeBase::new(),
+            p1,
+            p2,
+            p3,
+            e1,
+            e2,
+            normal,
+        }
+    }
+
+    pub fn get_p1(&self) -> &Tuple {
+        &self.p1
+    }
+
+    pub fn get_p2(&self) -> &Tuple {
+        &self.p2
+    }
+
+    pub fn get_p3(&self) -> &Tuple {
+        &self.p3
+    }
+
+    pub fn get_e1(&self) -> &Tuple {
+        &self.e1
+    }
+
+    pub fn get_e2(&self) -> &Tuple {
+        &self.e2
+    }
+
+    pub fn get_normal(&self) -> &Tuple {
+        &self.normal
+    }
+}
+
+impl Shape for Triangle {
+    fn get_base(&self) -> &ShapeBase {
+        &self.base
+    }
+
+    fn get_base_mut(&mut self) -> &mut ShapeBase {
+        &mut self.base
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        match moller_trumbore(ray, &self.p1, &self.e1, &self.e2) {
+            Some((t, u, v)) => vec![Intersection::with_uv(t, self, u, v)],
+            None => vec![],
+        }
+    }
+
+    fn local_normal_at(&self, _point: &Tuple) -> Tuple {
+        self.normal.clone()
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        let mut bounds = BoundingBox::empty();
+        for point in [&self.p1, &self.p2, &self.p3] {
+            bounds.add_point(point);
+        }
+        bounds
+    }
+}
+
+// Möller–Trumbore ray/triangle test, returns t and the barycentric u/v of the hit
+pub(crate) fn moller_trumbore(
+    ray: &Ray,
+    p1: &Tuple,
+    e1: &Tuple,
+    e2: &Tuple,
+) -> Option<(f32, f32, f32)> {
+    let dir_cross_e2 = ray.direction.cross(e2);
+    let det = e1.dot(&dir_cross_e2);
+    if det.abs() < EPSILON {
+        return None;
+    }
+
+    let f = 1. / det;
+    let p1_to_origin = ray.origin.clone() - p1.clone();
+    let u = f * p1_to_origin.dot(&dir_cross_e2);
+    if !(0. ..=1.).contains(&u) {
+        return None;
+    }
+
+    let origin_cross_e1 = p1_to_origin.cross(e1);
+    let v = f * ray.direction.dot(&origin_cross_e1);
+    if v < 0. || u + v > 1. {
+        return None;
+    }
+
+    let t = f * e2.dot(&origin_cross_e1);
+    Some((t, u, v))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn triangle() -> Triangle {
+        Triangle::new(
+            Tuple::point(0., 1., 0.),
+            Tuple::point(-1., 0., 0.),
+            Tuple::point(1., 0., 0.),
+        )
+    }
+
+    #[test]
+    fn construct_triangle() {
+        let t = triangle();
+        assert_eq!(t.get_p1(), &Tuple::point(0., 1., 0.));
+        assert_eq!(t.get_p2(), &Tuple::point(-1., 0., 0.));
+        assert_eq!(t.get_p3(), &Tuple::point(1., 0., 0.));
+        assert_eq!(t.get_e1(), &Tuple::vector(-1., -1., 0.));
+        assert_eq!(t.get_e2(), &Tuple::vector(1., -1., 0.));
+        assert_eq!(t.get_normal(), &Tuple::vector(0., 0., -1.));
+    }
+
+    #[test]
+    fn normal_on_triangle() {
+        let t = triangle();
+        for point in [
+            Tuple::point(0., 0.5, 0.),
+            Tuple::point(-0.5, 0.75, 0.),
+            Tuple::point(0.5, 0.25, 0.),
+        ] {
+            assert_eq!(&t.local_normal_at(&point), t.get_normal());
+        }
+    }
+
+    #[test]
+    fn ray_parallel_to_triangle() {
+        let t = triangle();
+        let r = Ray::new(Tuple::point(0., -1., -2.), Tuple::vector(0., 1., 0.));
+        assert!(t.local_intersect(&r).is_empty());
+    }
+
+    #[test]
+    fn ray_misses_edges() {
+        let t = triangle();
+        for origin in [
+            Tuple::point(1., 1., -2.),
+            Tuple::point(-1., 1., -2.),
+            Tuple::point(0., -1., -2.),
+        ] {
+            let r = Ray::new(origin, Tuple::vector(0., 0., 1.));
+            assert!(t.local_intersect(&r).is_empty());
+        }
+    }
+
+    #[test]
+    fn ray_strikes_triangle() {
+        let t = triangle();
+        let r = Ray::new(Tuple::point(0., 0.5, -2.), Tuple::vector(0., 0., 1.));
+        let xs = t.local_intersect(&r);
+        assert_eq!(xs.len(), 1);
+        assert_eq!(xs[0].t, 2.);
+    }
+
+    #[test]
+    fn triangle_has_bounding_box() {
+        let t = Triangle::new(
+            Tuple::point(-3., 7., 2.),
+            Tuple::point(6., 2., -4.),
+            Tuple::point(2., -1., -1.),
+        );
+        let b = t.bounds();
+        assert_eq!(b.min, Tuple::point(-3., -1., -4.));
+        assert_eq!(b.max, Tuple::point(6., 7., 2.));
+    }
+}
@@ -1,5 +1,7 @@
 use std::ops;
 
+use crate::EPSILON;
+
 #[derive(PartialEq, Debug, Clone)]
 pub struct Tuple(pub f32, pub f32, pub f32, pub f32);
 
@@ -45,6 +47,17 @@ impl Tuple {
         )
     }
 
+    pub fn reflect(&self, normal: &Tuple) -> Self {
+        self.clone() - normal.clone() * 2. * self.dot(normal)
+    }
+
+    pub fn approx_eq(&self, other: &Tuple) -> bool {
+        (self.0 - other.0).abs() < EPSILON
+            && (self.1 - other.1).abs() < EPSILON
+            && (self.2 - other.2).abs() < EPSILON
+            && (self.3 - other.3).abs() < EPSILON
+    }
+
     pub fn hadamard(&self, other: &Tuple) -> Self {
         Self(
             self.0 * other.0,
@@ -254,4 +267,26 @@ mod tests {
         let c2 = Tuple::color(0.9, 1., 0.1);
         assert_eq!(c1.hadamard(&c2), Tuple::color(0.9, 0.2, 0.05));
     }
+
+    #[test]
+    fn approximately_equal_tuples() {
+        let t = Tuple::point(1., 2., 3.);
+        assert!(t.approx_eq(&Tuple::point(1.00001, 1.99999, 3.)));
+        assert!(!t.approx_eq(&Tuple::point(1.001, 2., 3.)));
+        assert!(!t.approx_eq(&Tuple::vector(1., 2., 3.)));
+    }
+
+    #[test]
+    fn reflect_vector_approaching_at_45_degrees() {
+        let v = Tuple::vector(1., -1., 0.);
+        let n = Tuple::vector(0., 1., 0.);
+        assert_eq!(v.reflect(&n), Tuple::vector(1., 1., 0.));
+    }
+
+    #[test]
+    fn reflect_vector_off_slanted_surface() {
+        let v = Tuple::vector(0., -1., 0.);
+        let n = Tuple::vector(2_f32.sqrt() / 2., 2_f32.sqrt() / 2., 0.);
+        assert!(v.reflect(&n).approx_eq(&Tuple::vector(1., 0., 0.)));
+    }
 }
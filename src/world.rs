@@ -0,0 +1,1120 @@
+use std::f32::consts::{FRAC_PI_2, TAU};
+
+use crate::background::Background;
+use crate::intersection::{Computations, Intersections};
+use crate::light::Light;
+use crate::material::lighting_with_color;
+use crate::matrix::Matrix4;
+use crate::medium::MediumStack;
+use crate::random::{seed_from, Rng};
+use crate::ray::Ray;
+use crate::shape::Shape;
+use crate::stats::RayStats;
+use crate::tuple::Tuple;
+
+pub const DEFAULT_MAX_DEPTH: usize = 5;
+
+#[derive(Debug)]
+pub struct World {
+    pub objects: Vec<Box<dyn Shape>>,
+    pub lights: Vec<Light>,
+    pub max_depth: usize,
+    pub background: Background,
+}
+
+impl World {
+    pub fn new() -> Self {
+        World {
+            objects: vec![],
+            lights: vec![],
+            max_depth: DEFAULT_MAX_DEPTH,
+            background: Background::default(),
+        }
+    }
+
+    // Walks every object depth first, passing its depth below the world and
+    // its transform combined with those of all its parents
+    pub fn visit<F>(&self, mut f: F)
+    where
+        F: FnMut(&dyn Shape, usize, &Matrix4),
+    {
+        for object in &self.objects {
+            visit_node(object.as_ref(), 0, &Matrix4::identity(), &mut f);
+        }
+    }
+
+    pub fn visit_mut<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&mut dyn Shape, usize, &Matrix4),
+    {
+        for object in &mut self.objects {
+            visit_node_mut(object.as_mut(), 0, &Matrix4::identity(), &mut f);
+        }
+    }
+
+    pub fn intersect_world(&self, ray: &Ray) -> Intersections<'_> {
+        let mut xs = Intersections::default();
+        for object in &self.objects {
+            xs.extend(object.intersect(ray));
+        }
+        xs
+    }
+
+    pub fn shade_hit(&self, comps: &Computations, remaining: usize) -> Tuple {
+        if let Some(exit) = comps.object.get_portal_exit() {
+            return self.portal_color(comps, &exit, remaining);
+        }
+
+        let material = comps.object.get_material();
+        let color = material.color_at(comps.object, &comps.pattern_point);
+        let surface = self
+            .lights
+            .iter()
+            .map(|light| {
+                lighting_with_color(
+                    material,
+                    &color,
+                    light,
+                    &comps.over_point,
+                    &comps.eyev,
+                    &comps.normalv,
+                    self.intensity_from(&comps.over_point, light, comps.viewpoint.as_ref()),
+                )
+            })
+            .fold(Tuple::color(0., 0., 0.), |acc, c| acc + c);
+
+        let reflected = self.reflected_color(comps, remaining);
+        let refracted = self.refracted_color(comps, remaining);
+
+        let color = if material.reflective > 0. && material.transparency > 0. {
+            let reflectance = comps.schlick();
+            surface + reflected * reflectance + refracted * (1. - reflectance)
+        } else {
+            surface + reflected + refracted
+        };
+        Tuple::color(color.0, color.1, color.2)
+    }
+
+    pub fn is_shadowed(&self, point: &Tuple, light_position: &Tuple) -> bool {
+        self.is_shadowed_from(point, light_position, None)
+    }
+
+    // Shadow rays of a hit see the scene from the viewpoint of the ray that made it
+    fn is_shadowed_from(
+        &self,
+        point: &Tuple,
+        light_position: &Tuple,
+        viewpoint: Option<&Tuple>,
+    ) -> bool {
+        RayStats::record(|stats| stats.shadow_rays += 1);
+        let v = light_position.clone() - point.clone();
+        let distance = v.mag();
+        let ray = Ray::new(point.clone(), v.normalize()).with_viewpoint(viewpoint.cloned());
+        // Light isn't followed through portals, they simply don't block it
+        self.intersect_world(&ray)
+            .iter()
+            .any(|i| i.t >= 0. && i.t < distance && i.object.get_portal_exit().is_none())
+    }
+
+    // Fraction of the light's sample points visible from the point
+    pub fn intensity_at(&self, point: &Tuple, light: &Light) -> f32 {
+        self.intensity_from(point, light, None)
+    }
+
+    fn intensity_from(&self, point: &Tuple, light: &Light, viewpoint: Option<&Tuple>) -> f32 {
+        let samples = light.sample_points(point);
+        let visible = samples
+            .iter()
+            .filter(|position| !self.is_shadowed_from(point, position, viewpoint))
+            .count();
+        visible as f32 / samples.len() as f32
+    }
+
+    pub fn color_at(&self, ray: &Ray) -> Tuple {
+        RayStats::record(|stats| stats.primary_rays += 1);
+        self.color_at_depth(ray, self.max_depth)
+    }
+
+    pub fn color_at_depth(&self, ray: &Ray, remaining: usize) -> Tuple {
+        let xs = self.intersect_world(ray);
+        match xs.hit() {
+            Some(hit) => {
+                let comps = hit.prepare_computations(ray, &xs);
+                absorb(self.shade_hit(&comps, remaining), &comps.media, comps.t)
+            }
+            None => self.background.color_at(ray),
+        }
+    }
+
+    // Follows a secondary ray that knows which objects it's travelling through
+    pub fn color_in_media(&self, ray: &Ray, media: MediumStack, remaining: usize) -> Tuple {
+        let xs = self.intersect_world(ray);
+        match xs.hit() {
+            Some(hit) => {
+                let comps = hit.prepare_computations_in(ray, media);
+                absorb(self.shade_hit(&comps, remaining), &comps.media, comps.t)
+            }
+            None => absorb(self.background.color_at(ray), &media, f32::INFINITY),
+        }
+    }
+
+    pub fn reflected_color(&self, comps: &Computations, remaining: usize) -> Tuple {
+        let material = comps.object.get_material();
+        let reflective = material.reflective;
+        if remaining == 0 || reflective == 0. {
+            return Tuple::color(0., 0., 0.);
+        }
+
+        RayStats::record(|stats| stats.secondary_rays += 1);
+        let direction = roughen(
+            &comps.reflectv,
+            &comps.normalv,
+            material.roughness,
+            &comps.point,
+        );
+        let reflect_ray =
+            Ray::new(comps.over_point.clone(), direction).with_viewpoint(comps.viewpoint.clone());
+        let color =
+            self.color_in_media(&reflect_ray, comps.media.clone(), remaining - 1) * reflective;
+        Tuple::color(color.0, color.1, color.2)
+    }
+
+    // Continues the ray from the matching spot behind the linked portal. Each
+    // pass uses up a bounce so portals facing each other can't recurse forever.
+    pub fn portal_color(&self, comps: &Computations, exit: &Matrix4, remaining: usize) -> Tuple {
+        if remaining == 0 {
+            return Tuple::color(0., 0., 0.);
+        }
+
+        RayStats::record(|stats| stats.secondary_rays += 1);
+        let ray = Ray::new(comps.under_point.clone(), -comps.eyev.clone())
+            .with_viewpoint(comps.viewpoint.clone())
+            .transform(exit);
+        self.color_in_media(&ray, comps.media.clone(), remaining - 1)
+    }
+
+    pub fn refracted_color(&self, comps: &Computations, remaining: usize) -> Tuple {
+        let material = comps.object.get_material();
+        let transparency = material.transparency;
+        if remaining == 0 || transparency == 0. {
+            return Tuple::color(0., 0., 0.);
+        }
+
+        // Snell's law, sin(theta_t)^2 > 1 means total internal reflection
+        let n_ratio = comps.n1 / comps.n2;
+        let cos_i = comps.eyev.dot(&comps.normalv);
+        let sin2_t = n_ratio.powf(2.) * (1. - cos_i.powf(2.));
+        if sin2_t > 1. {
+            return Tuple::color(0., 0., 0.);
+        }
+
+        let cos_t = (1. - sin2_t).sqrt();
+        let direction =
+            comps.normalv.clone() * (n_ratio * cos_i - cos_t) - comps.eyev.clone() * n_ratio;
+        let direction = roughen(&direction, &comps.normalv, material.roughness, &comps.point);
+        RayStats::record(|stats| stats.secondary_rays += 1);
+        let refract_ray =
+            Ray::new(comps.under_point.clone(), direction).with_viewpoint(comps.viewpoint.clone());
+        let media = comps.media.crossing(comps.object);
+        let color = self.color_in_media(&refract_ray, media, remaining - 1) * transparency;
+        Tuple::color(color.0, color.1, color.2)
+    }
+}
+
+// Rough surfaces scatter a ray anywhere in a cone around its ideal direction,
+// up to 90 degrees wide at roughness 1. Each bounce sends a single ray seeded by
+// where it starts, so the blur is repeatable and averages out over the samples
+// the camera takes per pixel.
+fn roughen(direction: &Tuple, normal: &Tuple, roughness: f32, point: &Tuple) -> Tuple {
+    if roughness <= 0. {
+        return direction.clone();
+    }
+    let mut rng = Rng::new(seed_from(&[
+        point.0,
+        point.1,
+        point.2,
+        direction.0,
+        direction.1,
+        direction.2,
+    ]));
+
+    let max_angle = roughness.min(1.) * FRAC_PI_2;
+    let cos = 1. - rng.next_f32() * (1. - max_angle.cos());
+    let sin = (1. - cos * cos).sqrt();
+    let (sin_phi, cos_phi) = (rng.next_f32() * TAU).sin_cos();
+
+    let axis = direction.normalize();
+    let helper = if axis.0.abs() > 0.9 {
+        Tuple::vector(0., 1., 0.)
+    } else {
+        Tuple::vector(1., 0., 0.)
+    };
+    let t = axis.cross(&helper).normalize();
+    let b = axis.cross(&t);
+    let scattered = axis * cos + (t * cos_phi + b * sin_phi) * sin;
+
+    // Mirror rays that were scattered through the surface back to the ideal side
+    let ideal_side = direction.dot(normal);
+    let side = scattered.dot(normal);
+    if ideal_side * side < 0. {
+        (scattered - normal.clone() * (2. * side)).normalize()
+    } else {
+        scattered.normalize()
+    }
+}
+
+// Light reaching the eye fades over the distance it travelled through the innermost medium
+fn absorb(color: Tuple, media: &MediumStack, distance: f32) -> Tuple {
+    match media.innermost() {
+        Some(medium) => color.hadamard(&medium.get_material().transmittance(distance)),
+        None => color,
+    }
+}
+
+fn visit_node<F>(node: &dyn Shape, depth: usize, parent_transform: &Matrix4, f: &mut F)
+where
+    F: FnMut(&dyn Shape, usize, &Matrix4),
+{
+    let world_transform = *parent_transform * *node.get_transform();
+    f(node, depth, &world_transform);
+    for child in node.get_children() {
+        visit_node(child.as_ref(), depth + 1, &world_transform, f);
+    }
+}
+
+fn visit_node_mut<F>(node: &mut dyn Shape, depth: usize, parent_transform: &Matrix4, f: &mut F)
+where
+    F: FnMut(&mut dyn Shape, usize, &Matrix4),
+{
+    let world_transform = *parent_transform * *node.get_transform();
+    f(node, depth, &world_transform);
+    // The visitor may have moved the node, its children have to see where it is now
+    let world_transform = *parent_transform * *node.get_transform();
+    for child in node.get_children_mut() {
+        visit_node_mut(child.as_mut(), depth + 1, &world_transform, f);
+    }
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::bounds::BoundingBox;
+    use crate::intersection::Intersection;
+    use crate::light::{AreaLight, PointLight};
+    use crate::material::Material;
+    use crate::plane::Plane;
+    use crate::portal::Portal;
+    use crate::shape::ShapeBase;
+    use crate::sphere::Sphere;
+
+    #[derive(Debug)]
+    struct TestContainer {
+        base: ShapeBase,
+        children: Vec<Box<dyn Shape>>,
+    }
+
+    impl Shape for TestContainer {
+        fn get_base(&self) -> &ShapeBase {
+            &self.base
+        }
+
+        fn get_base_mut(&mut self) -> &mut ShapeBase {
+            &mut self.base
+        }
+
+        fn get_children(&self) -> &[Box<dyn Shape>] {
+            &self.children
+        }
+
+        fn get_children_mut(&mut self) -> &mut [Box<dyn Shape>] {
+            &mut self.children
+        }
+
+        fn local_intersect(&self, _ray: &Ray) -> Vec<Intersection<'_>> {
+            vec![]
+        }
+
+        fn local_normal_at(&self, point: &Tuple) -> Tuple {
+            Tuple::vector(point.0, point.1, point.2)
+        }
+
+        fn bounds(&self) -> BoundingBox {
+            let mut bounds = BoundingBox::empty();
+            for child in &self.children {
+                bounds.merge(&child.parent_space_bounds());
+            }
+            bounds
+        }
+    }
+
+    pub(crate) fn default_world() -> World {
+        let light: Light =
+            PointLight::new(Tuple::point(-10., 10., -10.), Tuple::color(1., 1., 1.)).into();
+
+        let mut s1 = Sphere::new();
+        s1.get_material_mut().color = Tuple::color(0.8, 1.0, 0.6);
+        s1.get_material_mut().diffuse = 0.7;
+        s1.get_material_mut().specular = 0.2;
+
+        let mut s2 = Sphere::new();
+        s2.set_transform(Matrix4::scaling(0.5, 0.5, 0.5)).unwrap();
+
+        World {
+            objects: vec![Box::new(s1), Box::new(s2)],
+            lights: vec![light],
+            ..World::new()
+        }
+    }
+
+    #[test]
+    fn create_world() {
+        let w = World::new();
+        assert!(w.objects.is_empty());
+        assert!(w.lights.is_empty());
+    }
+
+    #[test]
+    fn intersect_world_with_ray() {
+        let w = default_world();
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let xs = w.intersect_world(&r);
+        let ts: Vec<f32> = xs.iter().map(|i| i.t).collect();
+        assert_eq!(ts, vec![4., 4.5, 5.5, 6.]);
+    }
+
+    #[test]
+    fn shade_intersection() {
+        let w = default_world();
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let i = Intersection::new(4., w.objects[0].as_ref());
+        let comps = i.prepare_computations(&r, &Intersections::new(vec![i.clone()]));
+        let c = w.shade_hit(&comps, DEFAULT_MAX_DEPTH);
+        assert!(c.approx_eq(&Tuple::color(0.38066, 0.47583, 0.2855)));
+    }
+
+    #[test]
+    fn shade_intersection_from_inside() {
+        let mut w = default_world();
+        w.lights =
+            vec![PointLight::new(Tuple::point(0., 0.25, 0.), Tuple::color(1., 1., 1.)).into()];
+        let r = Ray::new(Tuple::point(0., 0., 0.), Tuple::vector(0., 0., 1.));
+        let i = Intersection::new(0.5, w.objects[1].as_ref());
+        let comps = i.prepare_computations(&r, &Intersections::new(vec![i.clone()]));
+        let c = w.shade_hit(&comps, DEFAULT_MAX_DEPTH);
+        assert!(c.approx_eq(&Tuple::color(0.90498, 0.90498, 0.90498)));
+    }
+
+    #[test]
+    fn shade_with_multiple_lights() {
+        let mut w = default_world();
+        w.lights.push(w.lights[0].clone());
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let c = w.color_at(&r);
+        assert!(c.approx_eq(&Tuple::color(0.76132, 0.95166, 0.5710)));
+    }
+
+    #[test]
+    fn color_when_ray_misses() {
+        let w = default_world();
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 1., 0.));
+        assert_eq!(w.color_at(&r), Tuple::color(0., 0., 0.));
+    }
+
+    #[test]
+    fn color_when_ray_hits() {
+        let w = default_world();
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let c = w.color_at(&r);
+        assert!(c.approx_eq(&Tuple::color(0.38066, 0.47583, 0.2855)));
+    }
+
+    #[test]
+    fn color_with_intersection_behind_ray() {
+        let mut w = default_world();
+        w.objects[0].get_material_mut().ambient = 1.;
+        w.objects[1].get_material_mut().ambient = 1.;
+        let r = Ray::new(Tuple::point(0., 0., 0.75), Tuple::vector(0., 0., -1.));
+        let c = w.color_at(&r);
+        assert_eq!(c, w.objects[1].get_material().color);
+    }
+
+    #[test]
+    fn no_shadow_when_nothing_collinear_with_point_and_light() {
+        let w = default_world();
+        assert!(!w.is_shadowed(&Tuple::point(0., 10., 0.), &Tuple::point(-10., 10., -10.)));
+    }
+
+    #[test]
+    fn shadow_when_object_between_point_and_light() {
+        let w = default_world();
+        assert!(w.is_shadowed(
+            &Tuple::point(10., -10., 10.),
+            &Tuple::point(-10., 10., -10.)
+        ));
+    }
+
+    #[test]
+    fn no_shadow_when_object_behind_light() {
+        let w = default_world();
+        assert!(!w.is_shadowed(
+            &Tuple::point(-20., 20., -20.),
+            &Tuple::point(-10., 10., -10.)
+        ));
+    }
+
+    #[test]
+    fn no_shadow_when_object_behind_point() {
+        let w = default_world();
+        assert!(!w.is_shadowed(&Tuple::point(-2., 2., -2.), &Tuple::point(-10., 10., -10.)));
+    }
+
+    #[test]
+    fn point_light_intensity_is_all_or_nothing() {
+        let w = default_world();
+        let light = w.lights[0].clone();
+        let cases = [
+            (Tuple::point(0., 1.0001, 0.), 1.),
+            (Tuple::point(-1.0001, 0., 0.), 1.),
+            (Tuple::point(0., 0., -1.0001), 1.),
+            (Tuple::point(0., 0., 1.0001), 0.),
+            (Tuple::point(1.0001, 0., 0.), 0.),
+            (Tuple::point(0., -1.0001, 0.), 0.),
+            (Tuple::point(0., 0., 0.), 0.),
+        ];
+        for (point, expected) in cases {
+            assert_eq!(w.intensity_at(&point, &light), expected);
+        }
+    }
+
+    #[test]
+    fn area_light_intensity_is_fraction_of_visible_samples() {
+        let w = default_world();
+        let light: Light = AreaLight::new(
+            Tuple::point(-0.5, -0.5, -5.),
+            Tuple::vector(1., 0., 0.),
+            2,
+            Tuple::vector(0., 1., 0.),
+            2,
+            Tuple::color(1., 1., 1.),
+        )
+        .into();
+        let cases = [
+            (Tuple::point(0., 0., 2.), 0.),
+            (Tuple::point(1., -1., 2.), 0.25),
+            (Tuple::point(1.5, 0., 2.), 0.5),
+            (Tuple::point(1.25, 1.25, 3.), 0.75),
+            (Tuple::point(0., 0., -2.), 1.),
+        ];
+        for (point, expected) in cases {
+            assert_eq!(w.intensity_at(&point, &light), expected);
+        }
+    }
+
+    // Portal at the origin facing up, linked to one 10 units below, with a red
+    // sphere under the exit and the light above everything
+    fn portal_world(linked: bool) -> World {
+        let mut w = World::new();
+        w.lights
+            .push(PointLight::new(Tuple::point(0., 10., 0.), Tuple::color(1., 1., 1.)).into());
+        let mut entry = Portal::new();
+        entry.get_material_mut().color = Tuple::color(0., 0., 1.);
+        entry.get_material_mut().ambient = 1.;
+        entry.get_material_mut().diffuse = 0.;
+        entry.get_material_mut().specular = 0.;
+        let mut exit = Portal::new();
+        exit.set_transform(Matrix4::translation(0., -10., 0.))
+            .unwrap();
+        if linked {
+            Portal::link(&mut entry, &mut exit);
+        }
+        let mut ball = Sphere::new();
+        ball.set_transform(Matrix4::translation(0., -13., 0.))
+            .unwrap();
+        ball.get_material_mut().color = Tuple::color(1., 0., 0.);
+        ball.get_material_mut().ambient = 1.;
+        ball.get_material_mut().diffuse = 0.;
+        ball.get_material_mut().specular = 0.;
+        w.objects.push(Box::new(entry));
+        w.objects.push(Box::new(exit));
+        w.objects.push(Box::new(ball));
+        w
+    }
+
+    #[test]
+    fn ray_continues_through_linked_portal() {
+        let w = portal_world(true);
+        let r = Ray::new(Tuple::point(0., 1., 0.), Tuple::vector(0., -1., 0.));
+        assert!(w.color_at(&r).approx_eq(&Tuple::color(1., 0., 0.)));
+    }
+
+    #[test]
+    fn unlinked_portal_is_plain_surface() {
+        let w = portal_world(false);
+        let r = Ray::new(Tuple::point(0., 1., 0.), Tuple::vector(0., -1., 0.));
+        assert!(w.color_at(&r).approx_eq(&Tuple::color(0., 0., 1.)));
+    }
+
+    #[test]
+    fn portal_uses_up_a_bounce() {
+        let w = portal_world(true);
+        let r = Ray::new(Tuple::point(0., 1., 0.), Tuple::vector(0., -1., 0.));
+        assert_eq!(w.color_at_depth(&r, 0), Tuple::color(0., 0., 0.));
+        assert!(w.color_at_depth(&r, 1).approx_eq(&Tuple::color(1., 0., 0.)));
+    }
+
+    #[test]
+    fn portals_do_not_cast_shadows() {
+        let w = portal_world(true);
+        assert!(!w.is_shadowed(&Tuple::point(0., -5., 0.), &Tuple::point(0., 10., 0.)));
+        assert!(w.is_shadowed(&Tuple::point(0., -20., 0.), &Tuple::point(0., 10., 0.)));
+    }
+
+    #[test]
+    fn shade_hit_given_intersection_in_shadow() {
+        let mut w = World::new();
+        w.lights
+            .push(PointLight::new(Tuple::point(0., 0., -10.), Tuple::color(1., 1., 1.)).into());
+        w.objects.push(Box::new(Sphere::new()));
+        let mut s2 = Sphere::new();
+        s2.set_transform(Matrix4::translation(0., 0., 10.)).unwrap();
+        w.objects.push(Box::new(s2));
+
+        let r = Ray::new(Tuple::point(0., 0., 5.), Tuple::vector(0., 0., 1.));
+        let i = Intersection::new(4., w.objects[1].as_ref());
+        let comps = i.prepare_computations(&r, &Intersections::new(vec![i.clone()]));
+        assert!(w
+            .shade_hit(&comps, DEFAULT_MAX_DEPTH)
+            .approx_eq(&Tuple::color(0.1, 0.1, 0.1)));
+    }
+
+    fn ambient_only(color: Tuple) -> Material {
+        let mut m = Material::new();
+        m.color = color;
+        m.ambient = 1.;
+        m.diffuse = 0.;
+        m.specular = 0.;
+        m
+    }
+
+    fn mirror_world() -> World {
+        let mut w = World::new();
+        w.lights
+            .push(PointLight::new(Tuple::point(0., 10., -10.), Tuple::color(1., 1., 1.)).into());
+
+        let mut mirror = Sphere::new();
+        mirror.set_material(ambient_only(Tuple::color(0., 0., 0.)));
+        mirror.get_material_mut().ambient = 0.;
+        mirror.get_material_mut().reflective = 0.5;
+        w.objects.push(Box::new(mirror));
+
+        let mut backdrop = Sphere::new();
+        backdrop
+            .set_transform(Matrix4::translation(0., 0., -10.))
+            .unwrap();
+        backdrop.set_material(ambient_only(Tuple::color(0.2, 0.4, 0.6)));
+        w.objects.push(Box::new(backdrop));
+        w
+    }
+
+    #[test]
+    fn color_when_ray_misses_uses_background() {
+        let mut w = default_world();
+        w.background = Background::Solid(Tuple::color(0.1, 0.2, 0.3));
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 1., 0.));
+        assert_eq!(w.color_at(&r), Tuple::color(0.1, 0.2, 0.3));
+    }
+
+    #[test]
+    fn mirror_reflects_background() {
+        let mut w = World::new();
+        let mut mirror = Plane::new();
+        mirror.set_material(ambient_only(Tuple::color(0., 0., 0.)));
+        mirror.get_material_mut().ambient = 0.;
+        mirror.get_material_mut().reflective = 1.;
+        w.objects.push(Box::new(mirror));
+        w.background = Background::Gradient {
+            bottom: Tuple::color(0., 0., 0.),
+            top: Tuple::color(1., 1., 1.),
+        };
+        let r = Ray::new(Tuple::point(0., 1., 0.), Tuple::vector(0., -1., 0.));
+        assert!(w.color_at(&r).approx_eq(&Tuple::color(1., 1., 1.)));
+    }
+
+    #[test]
+    fn color_at_counts_rays() {
+        let w = mirror_world();
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        RayStats::take();
+        w.color_at(&r);
+        let stats = RayStats::take();
+        assert_eq!(stats.primary_rays, 1);
+        assert_eq!(stats.secondary_rays, 1);
+        assert_eq!(stats.shadow_rays, 2);
+    }
+
+    #[test]
+    fn reflected_color_for_nonreflective_material() {
+        let mut w = default_world();
+        let r = Ray::new(Tuple::point(0., 0., 0.), Tuple::vector(0., 0., 1.));
+        w.objects[1].get_material_mut().ambient = 1.;
+        let i = Intersection::new(1., w.objects[1].as_ref());
+        let comps = i.prepare_computations(&r, &Intersections::new(vec![i.clone()]));
+        assert_eq!(
+            w.reflected_color(&comps, DEFAULT_MAX_DEPTH),
+            Tuple::color(0., 0., 0.)
+        );
+    }
+
+    #[test]
+    fn reflected_color_for_reflective_material() {
+        let w = mirror_world();
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let xs = w.intersect_world(&r);
+        let hit = xs.hit().unwrap();
+        assert!(std::ptr::addr_eq(hit.object, w.objects[0].as_ref()));
+        let comps = hit.prepare_computations(&r, &xs);
+        let color = w.reflected_color(&comps, DEFAULT_MAX_DEPTH);
+        assert!(color.approx_eq(&Tuple::color(0.1, 0.2, 0.3)));
+    }
+
+    #[test]
+    fn shade_hit_with_reflective_material() {
+        let w = mirror_world();
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        assert!(w.color_at(&r).approx_eq(&Tuple::color(0.1, 0.2, 0.3)));
+    }
+
+    #[test]
+    fn color_at_with_mutually_reflective_surfaces() {
+        let mut w = World::new();
+        w.lights
+            .push(PointLight::new(Tuple::point(0., 0., 0.), Tuple::color(1., 1., 1.)).into());
+        let mut lower = Sphere::new();
+        lower.get_material_mut().reflective = 1.;
+        lower
+            .set_transform(Matrix4::translation(0., -2., 0.))
+            .unwrap();
+        let mut upper = Sphere::new();
+        upper.get_material_mut().reflective = 1.;
+        upper
+            .set_transform(Matrix4::translation(0., 2., 0.))
+            .unwrap();
+        w.objects = vec![Box::new(lower), Box::new(upper)];
+
+        // Terminates thanks to the recursion limit
+        let r = Ray::new(Tuple::point(0., 0., 0.), Tuple::vector(0., 1., 0.));
+        w.color_at(&r);
+    }
+
+    #[test]
+    fn reflected_color_at_maximum_recursive_depth() {
+        let w = mirror_world();
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let xs = w.intersect_world(&r);
+        let comps = xs.hit().unwrap().prepare_computations(&r, &xs);
+        assert_eq!(w.reflected_color(&comps, 0), Tuple::color(0., 0., 0.));
+    }
+
+    #[test]
+    fn max_depth_limits_reflections() {
+        let mut w = mirror_world();
+        w.max_depth = 0;
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        assert_eq!(w.color_at(&r), Tuple::color(0., 0., 0.));
+    }
+
+    #[test]
+    fn refracted_color_with_opaque_surface() {
+        let w = default_world();
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let xs = Intersections::new(vec![
+            Intersection::new(4., w.objects[0].as_ref()),
+            Intersection::new(6., w.objects[0].as_ref()),
+        ]);
+        let comps = xs[0].prepare_computations(&r, &xs);
+        assert_eq!(
+            w.refracted_color(&comps, DEFAULT_MAX_DEPTH),
+            Tuple::color(0., 0., 0.)
+        );
+    }
+
+    #[test]
+    fn refracted_color_at_maximum_recursive_depth() {
+        let mut w = default_world();
+        w.objects[0].get_material_mut().transparency = 1.;
+        w.objects[0].get_material_mut().refractive_index = 1.5;
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let xs = Intersections::new(vec![
+            Intersection::new(4., w.objects[0].as_ref()),
+            Intersection::new(6., w.objects[0].as_ref()),
+        ]);
+        let comps = xs[0].prepare_computations(&r, &xs);
+        assert_eq!(w.refracted_color(&comps, 0), Tuple::color(0., 0., 0.));
+    }
+
+    #[test]
+    fn refracted_color_under_total_internal_reflection() {
+        let mut w = default_world();
+        w.objects[0].get_material_mut().transparency = 1.;
+        w.objects[0].get_material_mut().refractive_index = 1.5;
+        let v = 2_f32.sqrt() / 2.;
+        let r = Ray::new(Tuple::point(0., 0., v), Tuple::vector(0., 1., 0.));
+        let xs = Intersections::new(vec![
+            Intersection::new(-v, w.objects[0].as_ref()),
+            Intersection::new(v, w.objects[0].as_ref()),
+        ]);
+        // Inside the sphere, so look at the second intersection
+        let comps = xs[1].prepare_computations(&r, &xs);
+        assert_eq!(
+            w.refracted_color(&comps, DEFAULT_MAX_DEPTH),
+            Tuple::color(0., 0., 0.)
+        );
+    }
+
+    fn absorbing_ball(absorption: Tuple) -> World {
+        let mut w = World::new();
+        w.background = Background::Solid(Tuple::color(1., 1., 1.));
+        let mut ball = Sphere::glass();
+        let material = ball.get_material_mut();
+        material.refractive_index = 1.;
+        material.ambient = 0.;
+        material.absorption = absorption;
+        w.objects.push(Box::new(ball));
+        w
+    }
+
+    #[test]
+    fn light_fades_through_absorbing_material() {
+        // Straight through the middle of the ball is 2 units of glass
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let clear = absorbing_ball(Tuple::color(0., 0., 0.)).color_at(&r);
+        assert!(clear.approx_eq(&Tuple::color(1., 1., 1.)));
+
+        let tinted = absorbing_ball(Tuple::color(0., 0.5, 1.)).color_at(&r);
+        assert!(tinted.approx_eq(&Tuple::color(1., (-1_f32).exp(), (-2_f32).exp())));
+
+        // Near the rim the ray crosses less glass and keeps more of its light
+        let r = Ray::new(Tuple::point(0., 0.8, -5.), Tuple::vector(0., 0., 1.));
+        let rim = absorbing_ball(Tuple::color(0., 0.5, 1.)).color_at(&r);
+        assert!(rim.approx_eq(&Tuple::color(1., (-0.6_f32).exp(), (-1.2_f32).exp())));
+    }
+
+    #[test]
+    fn total_internal_reflection_under_water_surface() {
+        let mut w = World::new();
+        let mut water = Plane::new();
+        water.get_material_mut().transparency = 1.;
+        water.get_material_mut().refractive_index = 1.333;
+        w.objects.push(Box::new(water));
+
+        // Grazing the surface from below only works out if the ray knows it's in the water
+        let r = Ray::new(
+            Tuple::point(-1., -0.2, 0.),
+            Tuple::vector(1., 0.2, 0.).normalize(),
+        );
+        let xs = w.intersect_world(&r);
+        let hit = xs.hit().unwrap();
+        let media = MediumStack::new().crossing(w.objects[0].as_ref());
+        let comps = hit.prepare_computations_in(&r, media);
+        assert_eq!(
+            w.refracted_color(&comps, DEFAULT_MAX_DEPTH),
+            Tuple::color(0., 0., 0.)
+        );
+    }
+
+    #[test]
+    fn refracted_ray_carries_media() {
+        let mut w = World::new();
+        let mut glass = Sphere::glass();
+        glass.get_material_mut().ambient = 1.;
+        glass.get_material_mut().color = Tuple::color(0.2, 0.4, 0.6);
+        w.objects.push(Box::new(glass));
+
+        // The refracted ray starts inside the glass and knows it, so leaving
+        // through the far side goes from glass back to air
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let xs = w.intersect_world(&r);
+        let comps = xs.hit().unwrap().prepare_computations(&r, &xs);
+        assert!(comps.media.is_empty());
+        let inside = comps.media.crossing(comps.object);
+        let r2 = Ray::new(comps.under_point.clone(), r.direction.clone());
+        let xs2 = w.intersect_world(&r2);
+        let comps2 = xs2.hit().unwrap().prepare_computations_in(&r2, inside);
+        assert_eq!((comps2.n1, comps2.n2), (1.5, 1.));
+        assert!(comps2.media.crossing(comps2.object).is_empty());
+    }
+
+    #[test]
+    fn refracted_color_with_refracted_ray() {
+        let mut w = World::new();
+        w.lights
+            .push(PointLight::new(Tuple::point(0., 10., -10.), Tuple::color(1., 1., 1.)).into());
+
+        // An index of 1 doesn't bend the ray, so it continues straight to the backdrop
+        let mut glass = Sphere::new();
+        glass.set_material(ambient_only(Tuple::color(0., 0., 0.)));
+        glass.get_material_mut().ambient = 0.;
+        glass.get_material_mut().transparency = 0.5;
+        glass.get_material_mut().refractive_index = 1.;
+        w.objects.push(Box::new(glass));
+
+        let mut backdrop = Sphere::new();
+        backdrop
+            .set_transform(Matrix4::translation(0., 0., 10.))
+            .unwrap();
+        backdrop.set_material(ambient_only(Tuple::color(0.2, 0.4, 0.6)));
+        w.objects.push(Box::new(backdrop));
+
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let xs = w.intersect_world(&r);
+        let comps = xs.hit().unwrap().prepare_computations(&r, &xs);
+        // Passes through both glass surfaces, each halving the color
+        assert!(w
+            .refracted_color(&comps, DEFAULT_MAX_DEPTH)
+            .approx_eq(&Tuple::color(0.05, 0.1, 0.15)));
+    }
+
+    #[test]
+    fn shade_hit_with_reflective_transparent_material() {
+        let mut w = World::new();
+        w.lights
+            .push(PointLight::new(Tuple::point(0., 10., -10.), Tuple::color(1., 1., 1.)).into());
+
+        let mut glass = Sphere::glass();
+        glass.get_material_mut().ambient = 0.;
+        glass.get_material_mut().diffuse = 0.;
+        glass.get_material_mut().specular = 0.;
+        glass.get_material_mut().color = Tuple::color(0., 0., 0.);
+        glass.get_material_mut().reflective = 1.;
+        glass.get_material_mut().refractive_index = 1.;
+        w.objects.push(Box::new(glass));
+
+        let mut backdrop = Sphere::new();
+        backdrop
+            .set_transform(Matrix4::translation(0., 0., 10.))
+            .unwrap();
+        backdrop.set_material(ambient_only(Tuple::color(1., 1., 1.)));
+        w.objects.push(Box::new(backdrop));
+
+        // Matching indices have zero reflectance, so only the refracted backdrop shows
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        assert!(w.color_at(&r).approx_eq(&Tuple::color(1., 1., 1.)));
+    }
+
+    #[test]
+    fn reflected_color_off_reflective_plane() {
+        let mut w = default_world();
+        let mut shape = Plane::new();
+        shape.get_material_mut().reflective = 0.5;
+        shape
+            .set_transform(Matrix4::translation(0., -1., 0.))
+            .unwrap();
+        w.objects.push(Box::new(shape));
+
+        let v = 2_f32.sqrt() / 2.;
+        let r = Ray::new(Tuple::point(0., 0., -3.), Tuple::vector(0., -v, v));
+        let i = Intersection::new(2_f32.sqrt(), w.objects[2].as_ref());
+        let comps = i.prepare_computations(&r, &Intersections::new(vec![i.clone()]));
+        assert!(w
+            .reflected_color(&comps, DEFAULT_MAX_DEPTH)
+            .approx_eq(&Tuple::color(0.19032, 0.2379, 0.14274)));
+        assert!(w
+            .shade_hit(&comps, DEFAULT_MAX_DEPTH)
+            .approx_eq(&Tuple::color(0.87677, 0.92436, 0.82918)));
+    }
+
+    #[test]
+    fn smooth_surfaces_keep_the_ideal_direction() {
+        let d = Tuple::vector(0., 1., 0.);
+        let n = Tuple::vector(0., 1., 0.);
+        assert_eq!(roughen(&d, &n, 0., &Tuple::point(1., 2., 3.)), d);
+    }
+
+    #[test]
+    fn rough_surfaces_scatter_within_a_cone() {
+        let v = 2_f32.sqrt() / 2.;
+        let d = Tuple::vector(v, v, 0.);
+        let n = Tuple::vector(0., 1., 0.);
+        let mut distinct = vec![];
+        for i in 0..200 {
+            let p = Tuple::point(i as f32 * 0.1, 0., 0.);
+            let scattered = roughen(&d, &n, 0.5, &p);
+            assert!((scattered.mag() - 1.).abs() < 0.0001);
+            // Within 45 degrees of the ideal direction and never through the surface
+            assert!(scattered.dot(&d) >= v - 0.0001);
+            assert!(scattered.1 >= 0.);
+            assert_eq!(scattered, roughen(&d, &n, 0.5, &p));
+            distinct.push(scattered);
+        }
+        distinct.dedup();
+        assert!(distinct.len() > 100);
+    }
+
+    #[test]
+    fn fully_rough_refraction_stays_below_surface() {
+        let d = Tuple::vector(0., -1., 0.);
+        let n = Tuple::vector(0., 1., 0.);
+        for i in 0..200 {
+            let p = Tuple::point(0., 0., i as f32 * 0.1);
+            assert!(roughen(&d, &n, 1., &p).1 <= 0.);
+        }
+    }
+
+    #[test]
+    fn reflected_color_off_rough_plane() {
+        let mut w = default_world();
+        let mut shape = Plane::new();
+        shape.get_material_mut().reflective = 0.5;
+        shape
+            .set_transform(Matrix4::translation(0., -1., 0.))
+            .unwrap();
+        w.objects.push(Box::new(shape));
+
+        let v = 2_f32.sqrt() / 2.;
+        let r = Ray::new(Tuple::point(0., 0., -3.), Tuple::vector(0., -v, v));
+        let i = Intersection::new(2_f32.sqrt(), w.objects[2].as_ref());
+        let xs = Intersections::new(vec![i.clone()]);
+        let smooth = w.reflected_color(&i.prepare_computations(&r, &xs), DEFAULT_MAX_DEPTH);
+
+        w.objects[2].get_material_mut().roughness = 0.3;
+        let i = Intersection::new(2_f32.sqrt(), w.objects[2].as_ref());
+        let xs = Intersections::new(vec![i.clone()]);
+        let comps = i.prepare_computations(&r, &xs);
+        let rough = w.reflected_color(&comps, DEFAULT_MAX_DEPTH);
+        assert!(!rough.approx_eq(&smooth));
+        assert_eq!(rough, w.reflected_color(&comps, DEFAULT_MAX_DEPTH));
+    }
+
+    #[test]
+    fn shade_hit_with_transparent_plane() {
+        let mut w = default_world();
+        let mut floor = Plane::new();
+        floor
+            .set_transform(Matrix4::translation(0., -1., 0.))
+            .unwrap();
+        floor.get_material_mut().transparency = 0.5;
+        floor.get_material_mut().refractive_index = 1.5;
+        w.objects.push(Box::new(floor));
+
+        let mut ball = Sphere::new();
+        ball.get_material_mut().color = Tuple::color(1., 0., 0.);
+        ball.get_material_mut().ambient = 0.5;
+        ball.set_transform(Matrix4::translation(0., -3.5, -0.5))
+            .unwrap();
+        w.objects.push(Box::new(ball));
+
+        let v = 2_f32.sqrt() / 2.;
+        let r = Ray::new(Tuple::point(0., 0., -3.), Tuple::vector(0., -v, v));
+        let xs = Intersections::new(vec![Intersection::new(2_f32.sqrt(), w.objects[2].as_ref())]);
+        let comps = xs[0].prepare_computations(&r, &xs);
+        assert!(w
+            .shade_hit(&comps, DEFAULT_MAX_DEPTH)
+            .approx_eq(&Tuple::color(0.93642, 0.68642, 0.68642)));
+
+        w.objects[2].get_material_mut().reflective = 0.5;
+        let xs = Intersections::new(vec![Intersection::new(2_f32.sqrt(), w.objects[2].as_ref())]);
+        let comps = xs[0].prepare_computations(&r, &xs);
+        assert!(w
+            .shade_hit(&comps, DEFAULT_MAX_DEPTH)
+            .approx_eq(&Tuple::color(0.93391, 0.69643, 0.69243)));
+    }
+
+    fn nested_world() -> World {
+        let mut inner = TestContainer {
+            base: ShapeBase::new(),
+            children: vec![],
+        };
+        inner
+            .set_transform(Matrix4::translation(0., 2., 0.))
+            .unwrap();
+        let mut leaf = Sphere::new();
+        leaf.set_transform(Matrix4::scaling(3., 3., 3.)).unwrap();
+        inner.children.push(Box::new(leaf));
+
+        let mut outer = TestContainer {
+            base: ShapeBase::new(),
+            children: vec![Box::new(inner)],
+        };
+        outer
+            .set_transform(Matrix4::translation(1., 0., 0.))
+            .unwrap();
+
+        let mut w = World::new();
+        w.objects.push(Box::new(outer));
+        w.objects.push(Box::new(Plane::new()));
+        w
+    }
+
+    #[test]
+    fn visit_flat_world() {
+        let w = default_world();
+        let mut visited = vec![];
+        w.visit(|node, depth, transform| {
+            visited.push((depth, *transform, *node.get_transform()));
+        });
+        assert_eq!(visited.len(), 2);
+        for (depth, world_transform, transform) in visited {
+            assert_eq!(depth, 0);
+            assert_eq!(world_transform, transform);
+        }
+    }
+
+    #[test]
+    fn visit_nested_objects() {
+        let w = nested_world();
+        let mut visited = vec![];
+        w.visit(|_, depth, transform| visited.push((depth, *transform)));
+
+        assert_eq!(visited.len(), 4);
+        assert_eq!(visited[0], (0, Matrix4::translation(1., 0., 0.)));
+        assert_eq!(visited[1], (1, Matrix4::translation(1., 2., 0.)));
+        assert_eq!(
+            visited[2],
+            (
+                2,
+                Matrix4::translation(1., 2., 0.) * Matrix4::scaling(3., 3., 3.)
+            )
+        );
+        assert_eq!(visited[3], (0, Matrix4::identity()));
+    }
+
+    #[test]
+    fn visit_mut_edits_every_object() {
+        let mut w = nested_world();
+        w.visit_mut(|node, depth, _| node.get_material_mut().ambient = depth as f32);
+
+        let mut ambients = vec![];
+        w.visit(|node, _, _| ambients.push(node.get_material().ambient));
+        assert_eq!(ambients, vec![0., 1., 2., 0.]);
+    }
+
+    #[test]
+    fn visit_mut_passes_edited_transforms_to_children() {
+        let mut w = nested_world();
+        let mut visited = vec![];
+        w.visit_mut(|node, depth, transform| {
+            if depth == 0 {
+                let scaled = *node.get_transform() * Matrix4::scaling(2., 2., 2.);
+                node.set_transform(scaled).unwrap();
+            } else {
+                visited.push((depth, *transform));
+            }
+        });
+
+        assert_eq!(
+            visited[0],
+            (
+                1,
+                Matrix4::translation(1., 0., 0.)
+                    * Matrix4::scaling(2., 2., 2.)
+                    * Matrix4::translation(0., 2., 0.)
+            )
+        );
+    }
+}